@@ -0,0 +1,64 @@
+//! Times `to_vec` and record conversion on an MNIST sized float file, compare runs with and without
+//! `--features parallel`
+
+use std::io::Cursor;
+use std::time::Instant;
+
+use idxrs::IdxCursor;
+
+const RECORDS: u32 = 60_000;
+const RECORD_LEN: u32 = 784;
+const RUNS: u32 = 10;
+
+/// In memory N x 784 f32 file with values 0.0, 1.0, 2.0, ...
+fn float_file() -> Vec<u8> {
+    let mut data = vec![0, 0, 0x0D, 2];
+    data.extend_from_slice(&RECORDS.to_be_bytes());
+    data.extend_from_slice(&RECORD_LEN.to_be_bytes());
+    for i in 0..RECORDS * RECORD_LEN {
+        data.extend_from_slice(&(i as f32).to_be_bytes());
+    }
+    data
+}
+
+fn bench<F: FnMut()>(name: &str, mut f: F) {
+    f();
+    let start = Instant::now();
+    for _ in 0..RUNS {
+        f();
+    }
+    println!("{:<24} {:>10.2?} per run", name, start.elapsed() / RUNS);
+}
+
+fn main() {
+    let data = float_file();
+    let mut cursor = IdxCursor::new(Cursor::new(data)).unwrap();
+    println!("parallel feature: {}", cfg!(feature = "parallel"));
+    bench("to_vec::<f32>", || {
+        let (values, _) = cursor.to_vec::<f32>().unwrap();
+        assert_eq!(values.len(), (RECORDS * RECORD_LEN) as usize);
+    });
+    bench("to_vec_lossy::<f64>", || {
+        let (values, _) = cursor.to_vec_lossy::<f64>().unwrap();
+        assert_eq!(values.len(), (RECORDS * RECORD_LEN) as usize);
+    });
+    bench("to_vec_lossy::<u8>", || {
+        let (values, _) = cursor.to_vec_lossy::<u8>().unwrap();
+        assert_eq!(values.len(), (RECORDS * RECORD_LEN) as usize);
+    });
+    let mut record = Vec::new();
+    bench("cast_record_into::<f64>", || {
+        for i in 0..RECORDS {
+            cursor.cast_record_into::<f64>(i, &mut record).unwrap();
+        }
+        assert_eq!(record.len(), RECORD_LEN as usize);
+    });
+    bench("record get_as::<f64>", || {
+        for i in 0..RECORDS {
+            let slice = cursor.get_record(&[i]).unwrap();
+            record.clear();
+            record.extend((0..RECORD_LEN).map(|j| slice.get_as::<f64>(&[j]).unwrap()));
+        }
+        assert_eq!(record.len(), RECORD_LEN as usize);
+    });
+}
@@ -0,0 +1,94 @@
+//! Times the access patterns of an MNIST sized byte file, from element by element `get` to whole file loads
+
+use std::io::Cursor;
+use std::time::Instant;
+
+use idxrs::IdxCursor;
+
+const RECORDS: u32 = 60_000;
+const RECORD_LEN: u32 = 784;
+const RUNS: u32 = 10;
+
+/// In memory N x 28 x 28 u8 file with values 0, 1, ..., 255, 0, ...
+fn byte_file() -> Vec<u8> {
+    let mut data = vec![0, 0, 0x08, 3];
+    data.extend_from_slice(&RECORDS.to_be_bytes());
+    data.extend_from_slice(&28u32.to_be_bytes());
+    data.extend_from_slice(&28u32.to_be_bytes());
+    data.extend((0..RECORDS * RECORD_LEN).map(|i| i as u8));
+    data
+}
+
+fn bench<F: FnMut()>(name: &str, runs: u32, mut f: F) {
+    f();
+    let start = Instant::now();
+    for _ in 0..runs {
+        f();
+    }
+    println!("{:<28} {:>10.2?} per run", name, start.elapsed() / runs);
+}
+
+fn main() {
+    let mut cursor = IdxCursor::new(Cursor::new(byte_file())).unwrap();
+    // Element by element is slow, it runs on the first thousand records only
+    bench("get, 1000 records", 1, || {
+        let mut sum = 0.0;
+        for i in 0..1000 {
+            for y in 0..28 {
+                for x in 0..28 {
+                    sum += cursor.get(&[i, y, x]).unwrap().as_f64();
+                }
+            }
+        }
+        assert!(sum > 0.0);
+    });
+    // Offset arithmetic alone, without decoding into an IdxValue
+    bench("get_ref, 1000 records", 1, || {
+        let mut sum = 0u64;
+        for i in 0..1000 {
+            for y in 0..28 {
+                for x in 0..28 {
+                    sum += cursor.get_ref(&[i, y, x]).unwrap()[0] as u64;
+                }
+            }
+        }
+        assert!(sum > 0);
+    });
+    bench("get_unvalidated, 1000 records", 1, || {
+        let mut sum = 0.0;
+        for i in 0..1000 {
+            for y in 0..28 {
+                for x in 0..28 {
+                    // The indices stay inside of the 60000 x 28 x 28 dimensions
+                    sum += cursor.get_unvalidated(&[i, y, x]).unwrap().as_f64();
+                }
+            }
+        }
+        assert!(sum > 0.0);
+    });
+    bench("get_record", RUNS, || {
+        for i in 0..RECORDS {
+            assert_eq!(cursor.get_record(&[i]).unwrap().len(), RECORD_LEN as usize);
+        }
+    });
+    let mut values = Vec::new();
+    bench("cast_record_into::<f32>", RUNS, || {
+        for i in 0..RECORDS {
+            cursor.cast_record_into::<f32>(i, &mut values).unwrap();
+        }
+        assert_eq!(values.len(), RECORD_LEN as usize);
+    });
+    bench("read_records_into::<f32>", RUNS, || {
+        cursor.read_records_into::<f32>(0..RECORDS, &mut values).unwrap();
+        assert_eq!(values.len(), (RECORDS * RECORD_LEN) as usize);
+    });
+    bench("read_records_into, batch 64", RUNS, || {
+        for start in (0..RECORDS).step_by(64) {
+            cursor.read_records_into::<f32>(start..(start + 64).min(RECORDS), &mut values).unwrap();
+        }
+    });
+    bench("to_vec::<u8>", RUNS, || {
+        let (values, _) = cursor.to_vec::<u8>().unwrap();
+        assert_eq!(values.len(), (RECORDS * RECORD_LEN) as usize);
+    });
+}
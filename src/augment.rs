@@ -0,0 +1,235 @@
+//! Random augmentation of 2-D u8 and f32 records, applied lazily as a step of a `transforms::Pipeline`
+//!
+//! ```no_run
+//! use idxrs::augment::Augment;
+//! use idxrs::transforms;
+//! use idxrs::IdxFile;
+//!
+//! let mut images = IdxFile::open("train-images-idx3-ubyte")?;
+//! let out = std::fs::File::create("train-images-augmented.idx")?;
+//! let augment = Augment::new(7).rotation(15.0).shift(2).elastic(34.0, 4.0);
+//! transforms::records(&mut images).augment(augment)?.collect_to_idx(out)?;
+//! # Ok::<(), idxrs::IdxError>(())
+//! ```
+
+use crate::rng::Rng;
+use crate::transforms::Pipeline;
+use crate::IdxDataType;
+use crate::IdxError;
+use crate::IdxValue;
+
+/// Data types of the records `Pipeline::augment` accepts
+const SUPPORTED: &[IdxDataType] = &[IdxDataType::UnsignedByte, IdxDataType::Float];
+
+/// Random transforms of an image, every record gets its own draw that only depends on the seed and the record
+/// position
+///
+/// Pixels that come from outside of the image are 0.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Augment {
+    seed: u64,
+    rotation: f64,
+    shift: u32,
+    flip: bool,
+    elastic: Option<(f64, f64)>,
+}
+
+impl Augment {
+    /// Leaves records unchanged until transforms are enabled
+    pub fn new(seed: u64) -> Augment {
+        Augment { seed, rotation: 0.0, shift: 0, flip: false, elastic: None }
+    }
+
+    /// Rotates around the centre by up to `degrees` in either direction
+    pub fn rotation(mut self, degrees: f64) -> Augment {
+        self.rotation = degrees;
+        self
+    }
+
+    /// Moves by up to `pixels` rows and columns in either direction
+    pub fn shift(mut self, pixels: u32) -> Augment {
+        self.shift = pixels;
+        self
+    }
+
+    /// Mirrors half of the records left to right
+    pub fn flip_horizontal(mut self, flip: bool) -> Augment {
+        self.flip = flip;
+        self
+    }
+
+    /// Displaces every pixel by a random field smoothed with a Gaussian of `sigma` and scaled by `alpha`,
+    /// 34 and 4 are the values of Simard et al. for MNIST
+    pub fn elastic(mut self, alpha: f64, sigma: f64) -> Augment {
+        self.elastic = Some((alpha, sigma));
+        self
+    }
+
+    /// Source position of every pixel of a `rows` x `cols` image in storage order
+    fn sample_positions(&self, rng: &mut Rng, rows: usize, cols: usize) -> Vec<(f64, f64)> {
+        let angle = (uniform(rng) * 2.0 - 1.0) * self.rotation.to_radians();
+        let shift = |rng: &mut Rng| rng.below(2 * self.shift as u64 + 1) as f64 - self.shift as f64;
+        let (dy, dx) = (shift(rng), shift(rng));
+        let flip = self.flip && rng.next_u64() & 1 == 1;
+        let displacement = self.elastic.map(|(alpha, sigma)| {
+            (displacement_field(rng, rows, cols, alpha, sigma), displacement_field(rng, rows, cols, alpha, sigma))
+        });
+        let (cy, cx) = ((rows as f64 - 1.0) / 2.0, (cols as f64 - 1.0) / 2.0);
+        let (sin, cos) = angle.sin_cos();
+        let mut positions = Vec::with_capacity(rows * cols);
+        for row in 0..rows {
+            for col in 0..cols {
+                // Inverse of the forward transform, so every target pixel finds the pixel it comes from
+                let (mut y, mut x) = (row as f64 - dy, col as f64 - dx);
+                if let Some((field_y, field_x)) = &displacement {
+                    y += field_y[row * cols + col];
+                    x += field_x[row * cols + col];
+                }
+                let (ry, rx) = (y - cy, x - cx);
+                let (y, x) = (cy + cos * ry - sin * rx, cx + sin * ry + cos * rx);
+                positions.push((y, if flip { cols as f64 - 1.0 - x } else { x }));
+            }
+        }
+        positions
+    }
+}
+
+/// Number in `0.0..1.0` from the upper 53 bits
+fn uniform(rng: &mut Rng) -> f64 {
+    (rng.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+}
+
+/// Uniform noise in `-1.0..1.0` blurred by a separable Gaussian and multiplied by `alpha`
+fn displacement_field(rng: &mut Rng, rows: usize, cols: usize, alpha: f64, sigma: f64) -> Vec<f64> {
+    let noise: Vec<f64> = (0..rows * cols).map(|_| uniform(rng) * 2.0 - 1.0).collect();
+    let radius = (3.0 * sigma).ceil().max(0.0) as isize;
+    // The centre weight is set directly, so a sigma of 0 leaves the noise unblurred instead of dividing by 0
+    let kernel: Vec<f64> = (-radius..=radius)
+        .map(|i| if i == 0 { 1.0 } else { (-((i * i) as f64) / (2.0 * sigma * sigma)).exp() })
+        .collect();
+    let sum: f64 = kernel.iter().sum();
+    let blur = |values: &[f64], along_rows: bool| -> Vec<f64> {
+        let mut out = vec![0.0; values.len()];
+        for row in 0..rows as isize {
+            for col in 0..cols as isize {
+                let mut acc = 0.0;
+                for (k, weight) in kernel.iter().enumerate() {
+                    let offset = k as isize - radius;
+                    let (r, c) = if along_rows { (row + offset, col) } else { (row, col + offset) };
+                    if r >= 0 && r < rows as isize && c >= 0 && c < cols as isize {
+                        acc += weight * values[(r * cols as isize + c) as usize];
+                    }
+                }
+                out[(row * cols as isize + col) as usize] = acc / sum;
+            }
+        }
+        out
+    };
+    blur(&blur(&noise, false), true).into_iter().map(|v| v * alpha).collect()
+}
+
+/// Bilinear interpolation of the image at a fractional position
+fn interpolate(pixels: &[f64], rows: usize, cols: usize, (y, x): (f64, f64)) -> f64 {
+    let (y0, x0) = (y.floor(), x.floor());
+    let (fy, fx) = (y - y0, x - x0);
+    let pixel = |r: f64, c: f64| {
+        if r < 0.0 || c < 0.0 || r >= rows as f64 || c >= cols as f64 {
+            0.0
+        } else {
+            pixels[r as usize * cols + c as usize]
+        }
+    };
+    let top = pixel(y0, x0) * (1.0 - fx) + pixel(y0, x0 + 1.0) * fx;
+    let bottom = pixel(y0 + 1.0, x0) * (1.0 - fx) + pixel(y0 + 1.0, x0 + 1.0) * fx;
+    top * (1.0 - fy) + bottom * fy
+}
+
+impl<'a> Pipeline<'a> {
+    /// Applies random transforms to every record, records have to be u8 or f32 images of rows x cols
+    ///
+    /// Fails with `DimensionMismatch` for records that are not 2-D and with `UnsupportedDataType` for other types.
+    pub fn augment(self, augment: Augment) -> Result<Pipeline<'a>, IdxError> {
+        let record_shape = self.record_shape().to_vec();
+        if record_shape.len() != 2 {
+            return Err(IdxError::DimensionMismatch{ needed: 2, supplied: record_shape.len() });
+        }
+        let data_type = self.data_type();
+        if !SUPPORTED.contains(&data_type) {
+            return Err(IdxError::UnsupportedDataType{ supported: SUPPORTED, found: data_type });
+        }
+        let (rows, cols) = (record_shape[0] as usize, record_shape[1] as usize);
+        let mut record = 0u64;
+        Ok(self.map_records(data_type, record_shape, move |values| {
+            let mut rng = Rng::new(augment.seed ^ Rng::new(record).next_u64());
+            record += 1;
+            let pixels: Vec<f64> = values.iter().map(|v| v.as_f64()).collect();
+            augment.sample_positions(&mut rng, rows, cols).into_iter().map(|pos| {
+                let v = interpolate(&pixels, rows, cols, pos);
+                if data_type == IdxDataType::UnsignedByte {
+                    IdxValue::UnsignedByte(v.round().clamp(0.0, 255.0) as u8)
+                } else {
+                    IdxValue::Float(v as f32)
+                }
+            }).collect()
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io;
+
+    use super::Augment;
+    use crate::testing;
+    use crate::transforms::records;
+    use crate::IdxCursor;
+    use crate::IdxDataType;
+    use crate::IdxError;
+    use crate::IdxWriter;
+
+    /// Every value of all records a pipeline with `augment` produces from the cursor
+    fn augmented(cursor: &mut IdxCursor<io::Cursor<Vec<u8>>>, augment: Augment) -> Vec<f64> {
+        records(cursor).augment(augment).unwrap().flat_map(|r| r.unwrap()).map(|v| v.as_f64()).collect()
+    }
+
+    fn images() -> IdxCursor<io::Cursor<Vec<u8>>> {
+        IdxCursor::from_bytes(testing::generate_random_idx(IdxDataType::UnsignedByte, &[4, 12, 10], 3).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn same_seed_same_output() {
+        let augment = |seed| Augment::new(seed).rotation(15.0).shift(2).flip_horizontal(true).elastic(34.0, 4.0);
+        let mut cursor = images();
+        let first = augmented(&mut cursor, augment(7));
+        assert_eq!(first.len(), 4 * 12 * 10);
+        assert_eq!(augmented(&mut cursor, augment(7)), first);
+        assert_ne!(augmented(&mut cursor, augment(8)), first);
+    }
+
+    #[test]
+    fn identity_without_transforms() {
+        let mut cursor = images();
+        let (expected, _) = cursor.to_vec::<f64>().unwrap();
+        assert_eq!(augmented(&mut cursor, Augment::new(5)), expected);
+
+        let pixels: Vec<f32> = (0..2 * 3 * 5).map(|v| v as f32 * -1.25 + 0.1).collect();
+        let data = IdxWriter::write_array(io::Cursor::new(Vec::new()), &[2, 3, 5], &pixels).unwrap().into_inner();
+        let mut cursor = IdxCursor::from_bytes(data).unwrap();
+        let values: Vec<f32> = augmented(&mut cursor, Augment::new(5)).into_iter().map(|v| v as f32).collect();
+        assert_eq!(values, pixels);
+    }
+
+    #[test]
+    fn rejected_records() {
+        let data = IdxWriter::write_array(io::Cursor::new(Vec::new()), &[2, 2, 2], &[1i16, 2, 3, 4, 5, 6, 7, 8]).unwrap().into_inner();
+        let mut cursor = IdxCursor::from_bytes(data).unwrap();
+        match records(&mut cursor).augment(Augment::new(1)).err() {
+            Some(IdxError::UnsupportedDataType{ supported, found: IdxDataType::Short }) => {
+                assert_eq!(supported, &[IdxDataType::UnsignedByte, IdxDataType::Float]);
+            }
+            other => panic!("{:?}", other.map(|e| e.to_string())),
+        }
+        let mut cursor = IdxCursor::from_bytes(vec![0, 0, 0x08, 1, 0, 0, 0, 2, 1, 2]).unwrap();
+        assert!(matches!(records(&mut cursor).augment(Augment::new(1)).err(), Some(IdxError::DimensionMismatch{ needed: 2, supplied: 0 })));
+    }
+}
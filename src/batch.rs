@@ -0,0 +1,155 @@
+use std::io::Read;
+use std::io::Seek;
+use std::marker::PhantomData;
+
+use crate::decode_into;
+use crate::rng::Rng;
+use crate::IdxCursor;
+use crate::IdxElement;
+use crate::IdxError;
+use crate::RecordOrder;
+
+/// Consecutive buffer of several records converted to `T`
+pub struct Batch<T> {
+    /// Elements of all records in order
+    pub data: Vec<T>,
+    /// Number of records followed by the record shape
+    pub shape: Vec<u32>,
+    /// Leading index of each record in the file
+    pub indices: Vec<u32>,
+}
+
+/// Iterator over fixed size batches of records, created by `IdxCursor::batches`
+pub struct Batches<'a, R: Read + Seek, T: IdxElement> {
+    cursor: &'a mut IdxCursor<R>,
+    order: Vec<u32>,
+    batch_size: usize,
+    drop_last: bool,
+    next: usize,
+    failed: bool,
+    element: PhantomData<T>,
+}
+
+impl<R: Read + Seek> IdxCursor<R> {
+    /// Iterates over records in batches of `batch_size`, values are converted like `to_vec`
+    ///
+    /// Fails with `DimensionMismatch` for a file without dimensions, which has no records to batch. Panics if
+    /// `batch_size` is 0.
+    pub fn batches<T: IdxElement>(&mut self, batch_size: usize) -> Result<Batches<'_, R, T>, IdxError> {
+        assert!(batch_size > 0, "batch size must not be zero");
+        let records = *self.dimensions().first().ok_or(IdxError::DimensionMismatch{ needed: 1, supplied: 0 })?;
+        Ok(Batches {
+            cursor: self,
+            order: (0..records).collect(),
+            batch_size,
+            drop_last: false,
+            next: 0,
+            failed: false,
+            element: PhantomData,
+        })
+    }
+}
+
+impl<'a, R: Read + Seek, T: IdxElement> Batches<'a, R, T> {
+    /// Skips the last batch if it has fewer than `batch_size` records
+    pub fn drop_last(mut self, drop_last: bool) -> Batches<'a, R, T> {
+        self.drop_last = drop_last;
+        self
+    }
+
+    /// Visits records in the given order, e.g. a permutation computed for the epoch
+    ///
+    /// Fails with `OutOfBounds` if a record of the permutation is not in the file.
+    pub fn order(mut self, order: RecordOrder) -> Result<Batches<'a, R, T>, IdxError> {
+        let records = self.cursor.num_records();
+        self.order = match order {
+            RecordOrder::Forward              => (0..records).collect(),
+            RecordOrder::Reverse              => (0..records).rev().collect(),
+            RecordOrder::Permutation(indices) => {
+                if let Some(index) = indices.iter().copied().find(|i| *i >= records) {
+                    return Err(IdxError::OutOfBounds{ dimension: 0, max: records, index });
+                }
+                indices
+            }
+        };
+        Ok(self)
+    }
+
+    /// Visits records in an order that only depends on the seed
+    pub fn shuffle(mut self, seed: u64) -> Batches<'a, R, T> {
+        Rng::new(seed).shuffle(&mut self.order);
+        self
+    }
+
+    /// Shuffles with an order that differs for every epoch but only depends on seed and epoch
+    pub fn shuffle_epoch(self, seed: u64, epoch: u64) -> Batches<'a, R, T> {
+        self.shuffle(seed ^ Rng::new(epoch).next_u64())
+    }
+
+    fn read_batch(&mut self, indices: Vec<u32>) -> Result<Batch<T>, IdxError> {
+        let data_type = self.cursor.data_type;
+        let mut shape = self.cursor.dimensions().to_vec();
+        shape[0] = indices.len() as u32;
+        let mut data = Vec::with_capacity(shape.iter().map(|d| *d as usize).product());
+        for record in self.cursor.get_records(&indices)? {
+            decode_into(data_type, record.as_bytes(), &mut data, false)?;
+        }
+        Ok(Batch { data, shape, indices })
+    }
+}
+
+impl<'a, R: Read + Seek, T: IdxElement> Iterator for Batches<'a, R, T> {
+    type Item = Result<Batch<T>, IdxError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let remaining = self.order.len() - self.next;
+        if self.failed || remaining == 0 || (self.drop_last && remaining < self.batch_size) {
+            return None;
+        }
+        let end = self.next + remaining.min(self.batch_size);
+        let indices = self.order[self.next..end].to_vec();
+        self.next = end;
+        let res = self.read_batch(indices);
+        self.failed = res.is_err();
+        Some(res)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::IdxCursor;
+    use crate::IdxError;
+    use crate::RecordOrder;
+
+    /// u8 file of 5 records of 2 elements, record i holds 10 * i and 10 * i + 1
+    fn cursor() -> IdxCursor<std::io::Cursor<Vec<u8>>> {
+        let mut data = vec![0, 0, 0x08, 2, 0, 0, 0, 5, 0, 0, 0, 2];
+        data.extend((0..5u8).flat_map(|i| vec![10 * i, 10 * i + 1]));
+        IdxCursor::from_bytes(data).unwrap()
+    }
+
+    #[test]
+    fn scalar_has_no_batches() {
+        let mut cursor = IdxCursor::from_bytes(vec![0, 0, 0x08, 0, 5]).unwrap();
+        assert!(matches!(cursor.batches::<u8>(2), Err(IdxError::DimensionMismatch{ needed: 1, supplied: 0 })));
+    }
+
+    #[test]
+    fn permutation_out_of_bounds() {
+        let mut cursor = cursor();
+        let res = cursor.batches::<u8>(2).unwrap().order(RecordOrder::Permutation(vec![0, 5, 1]));
+        assert!(matches!(res, Err(IdxError::OutOfBounds{ dimension: 0, max: 5, index: 5 })));
+    }
+
+    #[test]
+    fn batches_in_order() {
+        let mut cursor = cursor();
+        let batches = cursor.batches::<u8>(2).unwrap().order(RecordOrder::Permutation(vec![4, 0, 2])).unwrap();
+        let batches: Vec<_> = batches.map(|b| b.unwrap()).collect();
+        assert_eq!(batches.len(), 2);
+        assert_eq!((batches[0].data.clone(), batches[0].shape.clone()), (vec![40, 41, 0, 1], vec![2, 2]));
+        assert_eq!((batches[1].data.clone(), batches[1].indices.clone()), (vec![20, 21], vec![2]));
+        let dropped = cursor.batches::<u8>(2).unwrap().drop_last(true).count();
+        assert_eq!(dropped, 2);
+    }
+}
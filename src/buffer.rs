@@ -0,0 +1,118 @@
+use std::io;
+use std::io::Read;
+use std::io::Seek;
+use std::io::SeekFrom;
+
+/// Read buffer of a cursor, sized by `IdxOptions::buffer_size`
+///
+/// Unlike `BufReader`, seeking to a position inside the buffer keeps it, so reading records that lie close together
+/// only reads from the stream when the buffer runs out. Capacity 0 forwards every read to the reader, seeks to the
+/// current position are skipped either way.
+pub(crate) struct Buffered<R: Read + Seek> {
+    reader: R,
+    buf: Box<[u8]>,
+    pos: usize,
+    filled: usize,
+    // Position of the reader, i.e. of the end of the buffered bytes, unknown until the first seek
+    reader_pos: Option<u64>,
+}
+
+impl<R: Read + Seek> Buffered<R> {
+    pub(crate) fn new(reader: R, capacity: usize) -> Buffered<R> {
+        Buffered { reader, buf: vec![0; capacity].into_boxed_slice(), pos: 0, filled: 0, reader_pos: None }
+    }
+
+    /// Reader for direct use, the buffer is dropped since the reader may be moved
+    pub(crate) fn get_mut(&mut self) -> &mut R {
+        self.discard();
+        self.reader_pos = None;
+        &mut self.reader
+    }
+
+    pub(crate) fn capacity(&self) -> usize {
+        self.buf.len()
+    }
+
+    pub(crate) fn into_inner(self) -> R {
+        self.reader
+    }
+
+    fn discard(&mut self) {
+        self.pos = 0;
+        self.filled = 0;
+    }
+
+    /// Offset of `pos` in the buffer if the position is buffered
+    fn buffered(&self, pos: u64) -> Option<usize> {
+        let end = self.reader_pos?;
+        let start = end - self.filled as u64;
+        if self.filled > 0 && start <= pos && pos <= end {
+            Some((pos - start) as usize)
+        } else {
+            None
+        }
+    }
+}
+
+impl<R: Read + Seek> Read for Buffered<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        // Reads at least as large as the buffer bypass it
+        if self.pos == self.filled && buf.len() >= self.buf.len() {
+            self.discard();
+            let n = self.reader.read(buf)?;
+            self.reader_pos = self.reader_pos.map(|p| p + n as u64);
+            return Ok(n);
+        }
+        if self.pos == self.filled {
+            self.discard();
+            self.filled = self.reader.read(&mut self.buf)?;
+            self.reader_pos = self.reader_pos.map(|p| p + self.filled as u64);
+        }
+        let n = buf.len().min(self.filled - self.pos);
+        buf[..n].copy_from_slice(&self.buf[self.pos..self.pos + n]);
+        self.pos += n;
+        Ok(n)
+    }
+}
+
+impl<R: Read + Seek> Seek for Buffered<R> {
+    fn seek(&mut self, target: SeekFrom) -> io::Result<u64> {
+        // Bytes the reader is ahead of the logical position
+        let ahead = (self.filled - self.pos) as u64;
+        let absolute = match target {
+            SeekFrom::Start(p)   => Some(p),
+            SeekFrom::Current(d) => self.reader_pos.and_then(|end| {
+                let pos = end - ahead;
+                if d < 0 { pos.checked_sub(d.unsigned_abs()) } else { pos.checked_add(d as u64) }
+            }),
+            SeekFrom::End(_)     => None,
+        };
+        // Reading records in order asks for the current position, which needs no seek even without a buffer
+        if let (Some(pos), Some(end)) = (absolute, self.reader_pos) {
+            if pos == end - ahead {
+                return Ok(pos);
+            }
+        }
+        if let Some((pos, offset)) = absolute.and_then(|p| Some((p, self.buffered(p)?))) {
+            self.pos = offset;
+            return Ok(pos);
+        }
+        // Without a position the buffer cannot be used again if a seek fails
+        self.discard();
+        self.reader_pos = None;
+        let pos = match target {
+            SeekFrom::Current(d) => {
+                // The reader is ahead by the unread buffered bytes
+                if let Some(d) = d.checked_sub(ahead as i64) {
+                    self.reader.seek(SeekFrom::Current(d))?
+                } else {
+                    self.reader.seek(SeekFrom::Current(-(ahead as i64)))?;
+                    self.reader.seek(SeekFrom::Current(d))?
+                }
+            }
+            other => self.reader.seek(other)?,
+        };
+        self.reader_pos = Some(pos);
+        Ok(pos)
+    }
+}
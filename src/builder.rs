@@ -0,0 +1,92 @@
+use std::io::Read;
+use std::io::Seek;
+
+use crate::Endianness;
+use crate::IdxCache;
+use crate::IdxCursor;
+use crate::IdxError;
+use crate::IdxOptions;
+use crate::IdxWarning;
+
+/// Collects the settings for opening a cursor, created by `IdxCursor::builder`
+///
+/// ```no_run
+/// use idxrs::IdxCursor;
+/// use std::fs::File;
+///
+/// let cache = IdxCursor::builder(File::open("train-images.idx3-ubyte")?)
+///     .strict(true)
+///     .validate_length(true)
+///     .open_cached(64 << 20)?;
+/// # Ok::<(), idxrs::IdxError>(())
+/// ```
+pub struct IdxCursorBuilder<R: Read + Seek> {
+    reader: R,
+    options: IdxOptions,
+}
+
+impl<R: Read + Seek> IdxCursor<R> {
+    pub fn builder(reader: R) -> IdxCursorBuilder<R> {
+        IdxCursorBuilder { reader, options: IdxOptions::default() }
+    }
+}
+
+impl<R: Read + Seek> IdxCursorBuilder<R> {
+    /// Only accepts the data types of the IDX specification, see `IdxOptions::strict`
+    pub fn strict(mut self, strict: bool) -> IdxCursorBuilder<R> {
+        self.options.strict = strict;
+        self
+    }
+
+    /// Checks the stream length against the header, see `IdxOptions::validate_length`
+    pub fn validate_length(mut self, validate_length: bool) -> IdxCursorBuilder<R> {
+        self.options.validate_length = validate_length;
+        self
+    }
+
+    /// Byte order the file was written in
+    pub fn endianness(mut self, endianness: Endianness) -> IdxCursorBuilder<R> {
+        self.options.endianness = endianness;
+        self
+    }
+
+    /// Rejects files whose header announces more than `max` payload bytes, see `IdxOptions::max_payload_bytes`
+    pub fn max_payload_bytes(mut self, max: u64) -> IdxCursorBuilder<R> {
+        self.options.max_payload_bytes = Some(max);
+        self
+    }
+
+    /// Accepts magic numbers whose first two bytes are not zero, see `IdxOptions::lenient`
+    pub fn lenient(mut self, lenient: bool) -> IdxCursorBuilder<R> {
+        self.options.lenient = lenient;
+        self
+    }
+
+    /// Receives the problems lenient parsing accepted, e.g. to log them
+    pub fn diagnostics(mut self, diagnostics: fn(IdxWarning)) -> IdxCursorBuilder<R> {
+        self.options.diagnostics = Some(diagnostics);
+        self
+    }
+
+    /// Size of the read buffer in bytes, see `IdxOptions::buffer_size`
+    pub fn buffer_size(mut self, buffer_size: usize) -> IdxCursorBuilder<R> {
+        self.options.buffer_size = buffer_size;
+        self
+    }
+
+    /// Replaces all settings at once
+    pub fn options(mut self, options: IdxOptions) -> IdxCursorBuilder<R> {
+        self.options = options;
+        self
+    }
+
+    /// Reads the header and creates the cursor
+    pub fn open(self) -> Result<IdxCursor<R>, IdxError> {
+        IdxCursor::with_options(self.reader, self.options)
+    }
+
+    /// Like `open`, but keeps up to `capacity` bytes of recently read records in memory
+    pub fn open_cached(self, capacity: usize) -> Result<IdxCache<R>, IdxError> {
+        Ok(IdxCache::new(self.open()?, capacity))
+    }
+}
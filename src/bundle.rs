@@ -0,0 +1,215 @@
+//! Container of several named IDX files, so a dataset ships as one `.idxs` file
+//!
+//! All numbers are big endian:
+//!
+//! ```text
+//! "IDXS"   magic
+//! u32      version, currently 1
+//! u64      offset of the index table
+//! ...      entries, each a complete IDX file
+//! u32      number of entries, start of the index table
+//! ...      per entry: u16 name length, UTF-8 name, u64 offset and u64 length of the IDX file
+//! ```
+//!
+//! ```no_run
+//! use idxrs::bundle::Bundle;
+//!
+//! let mut bundle = Bundle::open("mnist-train.idxs")?;
+//! let (pixels, shape) = bundle.cursor("images")?.to_vec::<u8>()?;
+//! let (labels, _) = bundle.cursor("labels")?.to_vec::<u8>()?;
+//! # Ok::<(), idxrs::IdxError>(())
+//! ```
+
+use std::convert::TryFrom;
+use std::fs::File;
+use std::io;
+use std::io::BufReader;
+use std::io::Read;
+use std::io::Seek;
+use std::io::SeekFrom;
+use std::io::Write;
+use std::path::Path;
+
+use crate::IdxCursor;
+use crate::IdxError;
+use crate::SubReader;
+
+const MAGIC: &[u8; 4] = b"IDXS";
+const VERSION: u32 = 1;
+/// Magic, version and table offset
+const HEADER_LEN: u64 = 16;
+
+fn error(kind: io::ErrorKind, msg: String) -> IdxError {
+    IdxError::IoError(io::Error::new(kind, msg))
+}
+
+/// Name and location of one IDX file in a bundle
+#[derive(Clone, Debug, PartialEq)]
+pub struct BundleEntry {
+    pub name: String,
+    /// Position of the IDX file from the start of the bundle
+    pub offset: u64,
+    /// Size of the IDX file in bytes
+    pub len: u64,
+}
+
+/// Reader of a bundle, entries are opened as cursors on the shared stream
+pub struct Bundle<R: Read + Seek> {
+    reader: R,
+    entries: Vec<BundleEntry>,
+}
+
+impl Bundle<BufReader<File>> {
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Bundle<BufReader<File>>, IdxError> {
+        Bundle::new(BufReader::new(File::open(path)?))
+    }
+}
+
+impl<R: Read + Seek> Bundle<R> {
+    /// Reads the index table, fails unless every entry lies between the header and the table
+    pub fn new(mut reader: R) -> Result<Bundle<R>, IdxError> {
+        reader.seek(SeekFrom::Start(0))?;
+        let mut header = [0; HEADER_LEN as usize];
+        reader.read_exact(&mut header)?;
+        if &header[..4] != MAGIC {
+            return Err(error(io::ErrorKind::InvalidData, "not an IDX bundle, magic has to be IDXS".into()));
+        }
+        let version = u32::from_be_bytes([header[4], header[5], header[6], header[7]]);
+        if version != VERSION {
+            return Err(error(io::ErrorKind::InvalidData, format!("unsupported bundle version {}", version)));
+        }
+        let mut table_offset = [0; 8];
+        table_offset.copy_from_slice(&header[8..]);
+        let table_offset = u64::from_be_bytes(table_offset);
+        reader.seek(SeekFrom::Start(table_offset))?;
+        let count = read_u32(&mut reader)?;
+        let mut entries = Vec::new();
+        for _ in 0..count {
+            let mut name = vec![0; read_u16(&mut reader)? as usize];
+            reader.read_exact(&mut name)?;
+            let name = String::from_utf8(name).map_err(|_| error(io::ErrorKind::InvalidData, "entry name is not UTF-8".into()))?;
+            let (offset, len) = (read_u64(&mut reader)?, read_u64(&mut reader)?);
+            let end = offset.checked_add(len).ok_or(IdxError::Overflow)?;
+            if offset < HEADER_LEN || end > table_offset {
+                return Err(error(io::ErrorKind::InvalidData, format!("entry '{}' lies outside of the bundle data", name)));
+            }
+            entries.push(BundleEntry { name, offset, len });
+        }
+        Ok(Bundle { reader, entries })
+    }
+
+    /// Entries in the order they were added
+    pub fn entries(&self) -> &[BundleEntry] {
+        &self.entries
+    }
+
+    fn entry(&self, name: &str) -> Result<BundleEntry, IdxError> {
+        self.entries.iter()
+            .find(|entry| entry.name == name)
+            .cloned()
+            .ok_or_else(|| error(io::ErrorKind::NotFound, format!("bundle has no entry '{}'", name)))
+    }
+
+    /// Raw bytes of the entry with the given name, i.e. the complete IDX file
+    pub fn reader(&mut self, name: &str) -> Result<SubReader<&mut R>, IdxError> {
+        let entry = self.entry(name)?;
+        Ok(SubReader::with_len(&mut self.reader, entry.offset, entry.len)?)
+    }
+
+    /// Opens the entry with the given name, its length is checked against its header
+    pub fn cursor(&mut self, name: &str) -> Result<IdxCursor<SubReader<&mut R>>, IdxError> {
+        IdxCursor::builder(self.reader(name)?).validate_length(true).open()
+    }
+
+    /// Like `cursor`, but hands the whole stream to the cursor
+    pub fn into_cursor(self, name: &str) -> Result<IdxCursor<SubReader<R>>, IdxError> {
+        let entry = self.entry(name)?;
+        let reader = SubReader::with_len(self.reader, entry.offset, entry.len)?;
+        IdxCursor::builder(reader).validate_length(true).open()
+    }
+
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+}
+
+fn read_u16<R: Read>(reader: &mut R) -> Result<u16, IdxError> {
+    let mut buf = [0; 2];
+    reader.read_exact(&mut buf)?;
+    Ok(u16::from_be_bytes(buf))
+}
+
+fn read_u32<R: Read>(reader: &mut R) -> Result<u32, IdxError> {
+    let mut buf = [0; 4];
+    reader.read_exact(&mut buf)?;
+    Ok(u32::from_be_bytes(buf))
+}
+
+fn read_u64<R: Read>(reader: &mut R) -> Result<u64, IdxError> {
+    let mut buf = [0; 8];
+    reader.read_exact(&mut buf)?;
+    Ok(u64::from_be_bytes(buf))
+}
+
+/// Writes a bundle entry by entry, the index table is written by `finish`
+pub struct BundleWriter<W: Write + Seek> {
+    writer: W,
+    entries: Vec<BundleEntry>,
+    pos: u64,
+}
+
+impl<W: Write + Seek> BundleWriter<W> {
+    /// Writes the header, the bundle starts at the beginning of the stream
+    pub fn new(mut writer: W) -> Result<BundleWriter<W>, IdxError> {
+        writer.seek(SeekFrom::Start(0))?;
+        writer.write_all(MAGIC)?;
+        writer.write_all(&VERSION.to_be_bytes())?;
+        // Table offset is filled in by finish
+        writer.write_all(&0u64.to_be_bytes())?;
+        Ok(BundleWriter { writer, entries: Vec::new(), pos: HEADER_LEN })
+    }
+
+    /// Copies a whole IDX file into the bundle, after checking that its length matches its header
+    ///
+    /// Fails if the name is taken or longer than 65535 bytes, nothing is written in that case.
+    pub fn add<R: Read + Seek>(&mut self, name: &str, mut reader: R) -> Result<(), IdxError> {
+        if self.entries.iter().any(|entry| entry.name == name) {
+            return Err(error(io::ErrorKind::InvalidInput, format!("bundle already has an entry '{}'", name)));
+        }
+        if u16::try_from(name.len()).is_err() {
+            return Err(error(io::ErrorKind::InvalidInput, format!("entry name of {} bytes is too long", name.len())));
+        }
+        let expected = IdxCursor::new(&mut reader)?.header().file_bytes();
+        let actual = reader.seek(SeekFrom::End(0))?;
+        if actual != expected {
+            return Err(IdxError::LengthMismatch{ expected, actual });
+        }
+        reader.seek(SeekFrom::Start(0))?;
+        let len = io::copy(&mut reader, &mut self.writer)?;
+        self.entries.push(BundleEntry { name: name.to_string(), offset: self.pos, len });
+        self.pos += len;
+        Ok(())
+    }
+
+    /// Entries added so far
+    pub fn entries(&self) -> &[BundleEntry] {
+        &self.entries
+    }
+
+    /// Writes the index table, updates the header and returns the writer
+    pub fn finish(mut self) -> Result<W, IdxError> {
+        self.writer.write_all(&(self.entries.len() as u32).to_be_bytes())?;
+        for entry in &self.entries {
+            self.writer.write_all(&(entry.name.len() as u16).to_be_bytes())?;
+            self.writer.write_all(entry.name.as_bytes())?;
+            self.writer.write_all(&entry.offset.to_be_bytes())?;
+            self.writer.write_all(&entry.len.to_be_bytes())?;
+        }
+        let end = self.writer.stream_position()?;
+        self.writer.seek(SeekFrom::Start(8))?;
+        self.writer.write_all(&self.pos.to_be_bytes())?;
+        self.writer.seek(SeekFrom::Start(end))?;
+        self.writer.flush()?;
+        Ok(self.writer)
+    }
+}
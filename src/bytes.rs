@@ -0,0 +1,155 @@
+use alloc::vec;
+use alloc::vec::Vec;
+
+use crate::header_len;
+use crate::parse_header;
+use crate::shape::advance;
+use crate::IdxDataType;
+use crate::IdxElement;
+use crate::IdxError;
+use crate::IdxHeader;
+use crate::IdxOptions;
+use crate::IdxShape;
+use crate::IdxSlice;
+use crate::IdxValue;
+
+/// Zero-copy reader over an IDX file that is completely held in memory
+pub struct IdxBytes<B: AsRef<[u8]>> {
+    data: B,
+    shape: IdxShape,
+    data_type: IdxDataType,
+}
+
+impl<B: AsRef<[u8]>> IdxBytes<B> {
+    /// Parses header once and checks that the payload is complete
+    pub fn new(data: B) -> Result<IdxBytes<B>, IdxError> {
+        let (data_type, shape) = parse_header(data.as_ref(), &IdxOptions::default())?;
+        let expected = header_len(shape.dimensions().len()) + shape.len() * data_type.get_size() as u64;
+        let actual = data.as_ref().len() as u64;
+        if actual < expected {
+            return Err(IdxError::LengthMismatch{ expected, actual });
+        }
+        Ok(IdxBytes {
+            data, shape, data_type
+        })
+    }
+
+    pub fn dimensions(&self) -> &[u32] {
+        self.shape.dimensions()
+    }
+
+    pub fn data_type(&self) -> IdxDataType {
+        self.data_type
+    }
+
+    pub fn header(&self) -> IdxHeader {
+        IdxHeader::new(self.data_type, self.shape.dimensions())
+    }
+
+    /// Dimensions with the strides of each in elements, the stride of the leading dimension is the record length
+    pub fn shape(&self) -> &IdxShape {
+        &self.shape
+    }
+
+    /// Raw big endian bytes of all elements
+    pub fn payload(&self) -> &[u8] {
+        let size = self.data_type.get_size() as usize;
+        let start = header_len(self.shape.dimensions().len()) as usize;
+        &self.data.as_ref()[start..start + self.shape.len() as usize * size]
+    }
+
+    pub fn get(&self, indices: &[u32]) -> Result<IdxValue, IdxError> {
+        let size = self.data_type.get_size() as usize;
+        let pos = self.shape.flat_offset(indices)? as usize * size;
+        IdxValue::decode(self.data_type, &self.payload()[pos..pos + size])
+    }
+
+    /// Reads value and converts it to `T`, fails if the value is not exactly representable
+    pub fn get_as<T: IdxElement>(&self, indices: &[u32]) -> Result<T, IdxError> {
+        T::from_value(self.get(indices)?)
+    }
+
+    /// Borrows the whole block spanned by the trailing dimensions after the leading indices
+    pub fn get_record(&self, leading_indices: &[u32]) -> Result<IdxSlice<&[u8]>, IdxError> {
+        let size = self.data_type.get_size() as usize;
+        let pos = self.shape.record_offset(leading_indices)? as usize * size;
+        let record_len = self.shape.record_len(leading_indices.len()) as usize * size;
+        let trailing = self.shape.dimensions()[leading_indices.len()..].to_vec();
+        Ok(IdxSlice::new(&self.payload()[pos..pos + record_len], self.data_type, IdxShape::new(trailing)))
+    }
+
+    /// Borrows the raw big endian bytes of record `i` straight from the data, e.g. an `IdxMmap`, for decoding them
+    /// elsewhere
+    ///
+    /// A scalar file is a single record. `shape()` and `data_type()` describe the layout of the bytes.
+    ///
+    /// ```
+    /// use idxrs::{IdxBytes, IdxDataType};
+    ///
+    /// let data = vec![0, 0, 0x0b, 2, 0, 0, 0, 2, 0, 0, 0, 3, 0, 1, 0, 2, 0, 3, 1, 0, 2, 0, 3, 0];
+    /// let bytes = IdxBytes::new(data)?;
+    /// assert_eq!(bytes.data_type(), IdxDataType::Short);
+    /// assert_eq!(bytes.shape().strides(), &[3, 1]);
+    /// assert_eq!(bytes.raw_record_bytes(1)?, &[1, 0, 2, 0, 3, 0]);
+    /// # Ok::<(), idxrs::IdxError>(())
+    /// ```
+    pub fn raw_record_bytes(&self, i: u32) -> Result<&[u8], IdxError> {
+        let index = [i];
+        // A scalar file is a single record without leading index
+        let leading = self.shape.dimensions().len().min(1);
+        if leading == 0 && i != 0 {
+            return Err(IdxError::OutOfBounds{ dimension: 0, max: 1, index: i });
+        }
+        let size = self.data_type.get_size() as usize;
+        let pos = self.shape.record_offset(&index[..leading])? as usize * size;
+        let len = self.shape.record_len(leading) as usize * size;
+        Ok(&self.payload()[pos..pos + len])
+    }
+
+    /// Iterates over all elements in storage order
+    pub fn iter(&self) -> IdxBytesIter<'_> {
+        IdxBytesIter {
+            payload: self.payload(),
+            data_type: self.data_type,
+            dimensions: self.shape.dimensions(),
+            index: vec![0; self.shape.dimensions().len()],
+        }
+    }
+
+    pub fn into_inner(self) -> B {
+        self.data
+    }
+}
+
+/// Iterator over the elements of an `IdxBytes` in storage order together with their indices
+pub struct IdxBytesIter<'a> {
+    payload: &'a [u8],
+    data_type: IdxDataType,
+    dimensions: &'a [u32],
+    index: Vec<u32>,
+}
+
+impl<'a> Iterator for IdxBytesIter<'a> {
+    type Item = (Vec<u32>, IdxValue);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let size = self.data_type.get_size() as usize;
+        if self.payload.len() < size {
+            return None;
+        }
+        let (bytes, rest) = self.payload.split_at(size);
+        self.payload = rest;
+        // Cannot fail, the chunk always has the size of one element
+        let value = IdxValue::decode(self.data_type, bytes).ok()?;
+        let index = self.index.clone();
+        advance(&mut self.index, self.dimensions);
+        Some((index, value))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let n = self.payload.len() / self.data_type.get_size() as usize;
+        (n, Some(n))
+    }
+}
+
+impl<'a> ExactSizeIterator for IdxBytesIter<'a> {}
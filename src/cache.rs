@@ -0,0 +1,220 @@
+use std::collections::BTreeMap;
+use std::collections::HashMap;
+use std::io::Read;
+use std::io::Seek;
+use std::sync::Arc;
+
+use crate::IdxCursor;
+use crate::IdxError;
+use crate::IdxShape;
+use crate::IdxSlice;
+use crate::IdxValue;
+
+struct Entry {
+    data: Arc<[u8]>,
+    last_used: u64,
+}
+
+/// Cursor that keeps recently read records in memory, least recently used ones are dropped first
+pub struct IdxCache<R: Read + Seek> {
+    cursor: IdxCursor<R>,
+    record_shape: IdxShape,
+    capacity: usize,
+    size: usize,
+    entries: HashMap<u32, Entry>,
+    // Access time to record index, the first entry is the next to be evicted
+    order: BTreeMap<u64, u32>,
+    clock: u64,
+    hits: u64,
+    misses: u64,
+}
+
+impl<R: Read + Seek> IdxCache<R> {
+    /// Caches up to `capacity` bytes of record data, records larger than that are never cached
+    pub fn new(cursor: IdxCursor<R>, capacity: usize) -> IdxCache<R> {
+        let record_shape = IdxShape::new(cursor.header().record_shape().to_vec());
+        IdxCache {
+            cursor,
+            record_shape,
+            capacity,
+            size: 0,
+            entries: HashMap::new(),
+            order: BTreeMap::new(),
+            clock: 0,
+            hits: 0,
+            misses: 0,
+        }
+    }
+
+    /// Reads record from the cache, falls back to the file and caches the result
+    pub fn get_record(&mut self, leading: u32) -> Result<IdxSlice<Arc<[u8]>>, IdxError> {
+        self.clock += 1;
+        let data_type = self.cursor.data_type;
+        if let Some(entry) = self.entries.get_mut(&leading) {
+            self.order.remove(&entry.last_used);
+            self.order.insert(self.clock, leading);
+            entry.last_used = self.clock;
+            self.hits += 1;
+            return Ok(IdxSlice::new(entry.data.clone(), data_type, self.record_shape.clone()));
+        }
+        self.misses += 1;
+        let data: Arc<[u8]> = Arc::from(self.cursor.get_record(&[leading])?.into_inner());
+        if data.len() <= self.capacity {
+            while self.size + data.len() > self.capacity {
+                self.evict();
+            }
+            self.size += data.len();
+            self.order.insert(self.clock, leading);
+            self.entries.insert(leading, Entry { data: data.clone(), last_used: self.clock });
+        }
+        Ok(IdxSlice::new(data, data_type, self.record_shape.clone()))
+    }
+
+    /// Reads single element through the cached record it belongs to
+    pub fn get(&mut self, indices: &[u32]) -> Result<IdxValue, IdxError> {
+        let dimensions = self.cursor.dimensions().len();
+        if indices.len() != dimensions || indices.is_empty() {
+            return Err(IdxError::DimensionMismatch{ needed: dimensions, supplied: indices.len() });
+        }
+        self.get_record(indices[0])?.get(&indices[1..])
+    }
+
+    fn evict(&mut self) {
+        let (&last_used, &leading) = match self.order.iter().next() {
+            Some(first) => first,
+            None => return,
+        };
+        self.order.remove(&last_used);
+        if let Some(entry) = self.entries.remove(&leading) {
+            self.size -= entry.data.len();
+        }
+    }
+
+    /// Drops all cached records
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.order.clear();
+        self.size = 0;
+    }
+
+    /// Bytes of record data currently held
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Number of reads served from memory
+    pub fn hits(&self) -> u64 {
+        self.hits
+    }
+
+    /// Number of reads that went to the underlying reader
+    pub fn misses(&self) -> u64 {
+        self.misses
+    }
+
+    pub fn cursor(&mut self) -> &mut IdxCursor<R> {
+        &mut self.cursor
+    }
+
+    pub fn into_inner(self) -> IdxCursor<R> {
+        self.cursor
+    }
+}
+
+impl<R: Read + Seek> IdxCursor<R> {
+    /// Wraps cursor in an `IdxCache` holding up to `capacity` bytes of records
+    pub fn with_cache(self, capacity: usize) -> IdxCache<R> {
+        IdxCache::new(self, capacity)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::IdxCache;
+    use crate::IdxCursor;
+
+    /// Cache over a u8 file of shape [5, 4], element `[r, c]` holds `10 * r + c`
+    fn cache(capacity: usize) -> IdxCache<std::io::Cursor<Vec<u8>>> {
+        let mut data = vec![0, 0, 0x08, 2, 0, 0, 0, 5, 0, 0, 0, 4];
+        data.extend((0..5).flat_map(|r| (0..4).map(move |c| 10 * r + c)));
+        IdxCursor::from_bytes(data).unwrap().with_cache(capacity)
+    }
+
+    #[test]
+    fn least_recently_used_is_evicted() {
+        let mut cache = cache(12);
+        for record in 0..3 {
+            cache.get_record(record).unwrap();
+        }
+        // Touching record 0 leaves record 1 as the least recently used
+        cache.get_record(0).unwrap();
+        cache.get_record(3).unwrap();
+        assert_eq!((cache.hits(), cache.misses()), (1, 4));
+        for record in [0, 2, 3] {
+            cache.get_record(record).unwrap();
+        }
+        assert_eq!((cache.hits(), cache.misses()), (4, 4));
+        // Record 1 has to come from the file again and takes the place of record 0
+        assert_eq!(cache.get_record(1).unwrap().as_bytes(), &[10, 11, 12, 13]);
+        assert_eq!((cache.hits(), cache.misses()), (4, 5));
+        cache.get_record(0).unwrap();
+        assert_eq!((cache.hits(), cache.misses()), (4, 6));
+    }
+
+    #[test]
+    fn size_counts_record_bytes() {
+        let mut cache = cache(10);
+        assert_eq!(cache.size(), 0);
+        cache.get_record(0).unwrap();
+        assert_eq!(cache.size(), 4);
+        cache.get_record(1).unwrap();
+        assert_eq!(cache.size(), 8);
+        // A hit does not add bytes, a third record evicts one to stay within the capacity
+        cache.get_record(1).unwrap();
+        assert_eq!(cache.size(), 8);
+        cache.get_record(2).unwrap();
+        assert_eq!(cache.size(), 8);
+        assert_eq!(cache.capacity(), 10);
+    }
+
+    #[test]
+    fn records_larger_than_capacity() {
+        let mut cache = cache(3);
+        for _ in 0..3 {
+            assert_eq!(cache.get_record(4).unwrap().as_bytes(), &[40, 41, 42, 43]);
+        }
+        assert_eq!(cache.size(), 0);
+        assert_eq!((cache.hits(), cache.misses()), (0, 3));
+    }
+
+    #[test]
+    fn hits_and_misses() {
+        let mut cache = cache(1024);
+        assert_eq!((cache.hits(), cache.misses()), (0, 0));
+        assert_eq!(cache.get(&[2, 1]).unwrap().to_string(), "21");
+        assert_eq!(cache.get(&[2, 3]).unwrap().to_string(), "23");
+        assert_eq!(cache.get(&[3, 0]).unwrap().to_string(), "30");
+        assert_eq!((cache.hits(), cache.misses()), (1, 2));
+        // Failed reads count as misses and cache nothing
+        assert!(cache.get_record(5).is_err());
+        assert_eq!((cache.hits(), cache.misses()), (1, 3));
+        assert_eq!(cache.size(), 8);
+    }
+
+    #[test]
+    fn clear_drops_records() {
+        let mut cache = cache(1024);
+        cache.get_record(0).unwrap();
+        cache.get_record(1).unwrap();
+        cache.clear();
+        assert_eq!(cache.size(), 0);
+        // Counters are kept, the records have to be read again
+        cache.get_record(0).unwrap();
+        assert_eq!((cache.hits(), cache.misses()), (0, 3));
+        assert_eq!(cache.size(), 4);
+    }
+}
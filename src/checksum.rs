@@ -0,0 +1,260 @@
+use std::io::Read;
+use std::io::Seek;
+use std::io::SeekFrom;
+
+use crate::header_len;
+use crate::read_exact_at;
+use crate::IdxCursor;
+use crate::IdxError;
+use crate::READ_CHUNK;
+
+const CRC_TABLE: [u32; 256] = crc_table();
+
+const fn crc_table() -> [u32; 256] {
+    let mut table = [0u32; 256];
+    let mut n = 0;
+    while n < 256 {
+        let mut c = n as u32;
+        let mut k = 0;
+        while k < 8 {
+            c = if c & 1 != 0 { 0xedb8_8320 ^ (c >> 1) } else { c >> 1 };
+            k += 1;
+        }
+        table[n] = c;
+        n += 1;
+    }
+    table
+}
+
+/// CRC-32 as used by gzip and PNG
+pub(crate) fn crc32(data: &[u8]) -> u32 {
+    crc32_update(0, data)
+}
+
+/// Continues the CRC-32 of the data before `data`, starting from 0
+pub(crate) fn crc32_update(crc: u32, data: &[u8]) -> u32 {
+    !data.iter().fold(!crc, |c, b| CRC_TABLE[((c ^ *b as u32) & 0xff) as usize] ^ (c >> 8))
+}
+
+/// Adler-32 as used by zlib streams
+pub(crate) fn adler32(data: &[u8]) -> u32 {
+    let (mut a, mut b) = (1u32, 0u32);
+    for chunk in data.chunks(5552) {
+        for byte in chunk {
+            a += *byte as u32;
+            b += a;
+        }
+        a %= 65521;
+        b %= 65521;
+    }
+    (b << 16) | a
+}
+
+const SHA256_K: [u32; 64] = [
+    0x428a2f98, 0x71374491, 0xb5c0fbcf, 0xe9b5dba5, 0x3956c25b, 0x59f111f1, 0x923f82a4, 0xab1c5ed5,
+    0xd807aa98, 0x12835b01, 0x243185be, 0x550c7dc3, 0x72be5d74, 0x80deb1fe, 0x9bdc06a7, 0xc19bf174,
+    0xe49b69c1, 0xefbe4786, 0x0fc19dc6, 0x240ca1cc, 0x2de92c6f, 0x4a7484aa, 0x5cb0a9dc, 0x76f988da,
+    0x983e5152, 0xa831c66d, 0xb00327c8, 0xbf597fc7, 0xc6e00bf3, 0xd5a79147, 0x06ca6351, 0x14292967,
+    0x27b70a85, 0x2e1b2138, 0x4d2c6dfc, 0x53380d13, 0x650a7354, 0x766a0abb, 0x81c2c92e, 0x92722c85,
+    0xa2bfe8a1, 0xa81a664b, 0xc24b8b70, 0xc76c51a3, 0xd192e819, 0xd6990624, 0xf40e3585, 0x106aa070,
+    0x19a4c116, 0x1e376c08, 0x2748774c, 0x34b0bcb5, 0x391c0cb3, 0x4ed8aa4a, 0x5b9cca4f, 0x682e6ff3,
+    0x748f82ee, 0x78a5636f, 0x84c87814, 0x8cc70208, 0x90befffa, 0xa4506ceb, 0xbef9a3f7, 0xc67178f2,
+];
+
+/// Incremental SHA-256 (FIPS 180-4)
+pub(crate) struct Sha256 {
+    state: [u32; 8],
+    block: [u8; 64],
+    filled: usize,
+    len: u64,
+}
+
+impl Sha256 {
+    pub(crate) fn new() -> Sha256 {
+        Sha256 {
+            state: [
+                0x6a09e667, 0xbb67ae85, 0x3c6ef372, 0xa54ff53a, 0x510e527f, 0x9b05688c, 0x1f83d9ab, 0x5be0cd19,
+            ],
+            block: [0; 64],
+            filled: 0,
+            len: 0,
+        }
+    }
+
+    pub(crate) fn update(&mut self, mut data: &[u8]) {
+        self.len += data.len() as u64;
+        while !data.is_empty() {
+            let n = data.len().min(64 - self.filled);
+            self.block[self.filled..self.filled + n].copy_from_slice(&data[..n]);
+            self.filled += n;
+            data = &data[n..];
+            if self.filled == 64 {
+                let block = self.block;
+                self.compress(&block);
+                self.filled = 0;
+            }
+        }
+    }
+
+    pub(crate) fn finish(mut self) -> [u8; 32] {
+        let bits = self.len * 8;
+        // Padding is a one bit, zeros up to 56 bytes of the last block and the message length
+        self.update(&[0x80]);
+        while self.filled != 56 {
+            self.update(&[0]);
+        }
+        self.update(&bits.to_be_bytes());
+        let mut out = [0; 32];
+        for (chunk, word) in out.chunks_exact_mut(4).zip(self.state.iter()) {
+            chunk.copy_from_slice(&word.to_be_bytes());
+        }
+        out
+    }
+
+    fn compress(&mut self, block: &[u8; 64]) {
+        let mut w = [0u32; 64];
+        for (i, chunk) in block.chunks_exact(4).enumerate() {
+            w[i] = u32::from_be_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]);
+        }
+        for i in 16..64 {
+            let s0 = w[i - 15].rotate_right(7) ^ w[i - 15].rotate_right(18) ^ (w[i - 15] >> 3);
+            let s1 = w[i - 2].rotate_right(17) ^ w[i - 2].rotate_right(19) ^ (w[i - 2] >> 10);
+            w[i] = w[i - 16].wrapping_add(s0).wrapping_add(w[i - 7]).wrapping_add(s1);
+        }
+        let [mut a, mut b, mut c, mut d, mut e, mut f, mut g, mut h] = self.state;
+        for i in 0..64 {
+            let s1 = e.rotate_right(6) ^ e.rotate_right(11) ^ e.rotate_right(25);
+            let ch = (e & f) ^ (!e & g);
+            let t1 = h.wrapping_add(s1).wrapping_add(ch).wrapping_add(SHA256_K[i]).wrapping_add(w[i]);
+            let s0 = a.rotate_right(2) ^ a.rotate_right(13) ^ a.rotate_right(22);
+            let maj = (a & b) ^ (a & c) ^ (b & c);
+            let t2 = s0.wrapping_add(maj);
+            h = g;
+            g = f;
+            f = e;
+            e = d.wrapping_add(t1);
+            d = c;
+            c = b;
+            b = a;
+            a = t1.wrapping_add(t2);
+        }
+        for (s, v) in self.state.iter_mut().zip([a, b, c, d, e, f, g, h].iter()) {
+            *s = s.wrapping_add(*v);
+        }
+    }
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+impl<R: Read + Seek> IdxCursor<R> {
+    /// Calls `f` with consecutive chunks of `len` bytes starting at `start`, fails if the stream ends early
+    fn for_each_chunk<F: FnMut(&[u8])>(&mut self, start: u64, len: u64, mut f: F) -> Result<(), IdxError> {
+        self.reader.seek(SeekFrom::Start(start))?;
+        let mut buffer = vec![0; READ_CHUNK];
+        let (mut pos, end) = (start, start + len);
+        while pos < end {
+            let n = (end - pos).min(buffer.len() as u64) as usize;
+            read_exact_at(&mut self.reader, pos, &mut buffer[..n])?;
+            f(&buffer[..n]);
+            pos += n as u64;
+        }
+        Ok(())
+    }
+
+    /// CRC-32 of the payload bytes as stored, a truncated payload fails with `ReadFailed`
+    pub fn checksum(&mut self) -> Result<u32, IdxError> {
+        let header = self.header();
+        let mut crc = 0;
+        self.for_each_chunk(header_len(header.dimensions.len()), header.payload_bytes, |chunk| {
+            crc = crc32_update(crc, chunk);
+        })?;
+        Ok(crc)
+    }
+
+    /// Compares the payload checksum, fails with `ChecksumMismatch` if it differs
+    pub fn verify_against(&mut self, expected: u32) -> Result<(), IdxError> {
+        let actual = self.checksum()?;
+        if actual != expected {
+            return Err(IdxError::ChecksumMismatch{ expected: format!("{:08x}", expected), actual: format!("{:08x}", actual) });
+        }
+        Ok(())
+    }
+
+    /// SHA-256 of the whole stream, header and anything after the payload included, like `sha256sum`
+    pub fn sha256(&mut self) -> Result<[u8; 32], IdxError> {
+        let len = self.reader.seek(SeekFrom::End(0))?;
+        let mut hasher = Sha256::new();
+        self.for_each_chunk(0, len, |chunk| hasher.update(chunk))?;
+        Ok(hasher.finish())
+    }
+
+    /// Compares the SHA-256 of the stream with a hex string, case is ignored
+    pub fn verify_sha256(&mut self, expected: &str) -> Result<(), IdxError> {
+        let actual = hex(&self.sha256()?);
+        if !actual.eq_ignore_ascii_case(expected.trim()) {
+            return Err(IdxError::ChecksumMismatch{ expected: expected.trim().to_lowercase(), actual });
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{adler32, crc32, crc32_update, hex, Sha256};
+
+    fn sha256(data: &[u8]) -> String {
+        let mut sha = Sha256::new();
+        sha.update(data);
+        hex(&sha.finish())
+    }
+
+    // NIST FIPS 180-4 example messages
+    #[test]
+    fn sha256_vectors() {
+        assert_eq!(sha256(b""), "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855");
+        assert_eq!(sha256(b"abc"), "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad");
+        // 448 bits, the length no longer fits into the first block
+        assert_eq!(
+            sha256(b"abcdbcdecdefdefgefghfghighijhijkijkljklmklmnlmnomnopnopq"),
+            "248d6a61d20638b8e5c026930c3e6039a33ce45964ff2167f6ecedd419db06c1",
+        );
+        assert_eq!(
+            sha256(b"abcdefghbcdefghicdefghijdefghijkefghijklfghijklmghijklmnhijklmnoijklmnopjklmnopqklmnopqrlmnopqrsmnopqrstnopqrstu"),
+            "cf5b16a778af8380036ce59e7b0492370b249b11e8f07a51afac45037afee9d1",
+        );
+    }
+
+    #[test]
+    fn sha256_million_a() {
+        let expected = "cdc76e5c9914fb9281a1c7e284d73e67f1809a48a497200e046d39ccc7112cd0";
+        let data = vec![b'a'; 1_000_000];
+        assert_eq!(sha256(&data), expected);
+        // Pieces that do not line up with the 64 byte blocks
+        let mut sha = Sha256::new();
+        for chunk in data.chunks(997) {
+            sha.update(chunk);
+        }
+        assert_eq!(hex(&sha.finish()), expected);
+    }
+
+    #[test]
+    fn crc32_vectors() {
+        assert_eq!(crc32(b""), 0);
+        assert_eq!(crc32(b"123456789"), 0xcbf4_3926);
+        assert_eq!(crc32(b"Wikipedia"), 0xadaa_c02e);
+        assert_eq!(crc32(&[0xff; 100_000]), 0x68c6_cec4);
+        assert_eq!(crc32_update(crc32(b"1234"), b"56789"), 0xcbf4_3926);
+    }
+
+    #[test]
+    fn adler32_vectors() {
+        assert_eq!(adler32(b""), 1);
+        assert_eq!(adler32(b"abc"), 0x024d_0127);
+        assert_eq!(adler32(b"Wikipedia"), 0x11e6_0398);
+        assert_eq!(adler32(b"123456789"), 0x091e_01de);
+        // Largest bytes over many chunks, the sums must be reduced before they overflow
+        assert_eq!(adler32(&[0xff; 100_000]), 0x149a_302c);
+    }
+}
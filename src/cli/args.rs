@@ -0,0 +1,120 @@
+use std::collections::HashMap;
+use std::ops::Range;
+use std::str::FromStr;
+
+use super::CliError;
+
+/// Short forms of options, only accepted by subcommands that have the long option
+const SHORT: &[(&str, &str)] = &[("-o", "--out")];
+
+/// Positional arguments and `--name value` / `--name=value` options of a subcommand
+pub struct Args {
+    positional: Vec<String>,
+    options: HashMap<String, String>,
+    flags: Vec<String>,
+}
+
+impl Args {
+    /// `switches` take no value, `options` take exactly one, anything else is rejected
+    pub fn parse<I: Iterator<Item = String>>(mut raw: I, switches: &[&str], options: &[&str]) -> Result<Args, CliError> {
+        let mut args = Args { positional: Vec::new(), options: HashMap::new(), flags: Vec::new() };
+        while let Some(arg) = raw.next() {
+            let arg = match SHORT.iter().find(|(short, _)| *short == arg) {
+                Some((_, long)) => long.to_string(),
+                None => arg,
+            };
+            let name = match arg.strip_prefix("--") {
+                Some(name) => name,
+                None => {
+                    args.positional.push(arg);
+                    continue;
+                }
+            };
+            let (name, inline) = match name.find('=') {
+                Some(i) => (&name[..i], Some(name[i + 1..].to_string())),
+                None => (name, None),
+            };
+            if switches.contains(&name) && inline.is_none() {
+                args.flags.push(name.to_string());
+            } else if options.contains(&name) {
+                let value = match inline {
+                    Some(value) => value,
+                    None => raw.next().ok_or_else(|| CliError(format!("option '--{}' needs a value", name)))?,
+                };
+                args.options.insert(name.to_string(), value);
+            } else {
+                return Err(CliError(format!("unknown option '--{}'", name)));
+            }
+        }
+        Ok(args)
+    }
+
+    pub fn positionals(&self) -> &[String] {
+        &self.positional
+    }
+
+    pub fn positional(&self, i: usize, name: &str) -> Result<&str, CliError> {
+        self.positional.get(i).map(|s| s.as_str()).ok_or_else(|| CliError(format!("missing argument <{}>", name)))
+    }
+
+    pub fn flag(&self, name: &str) -> bool {
+        self.flags.iter().any(|f| f == name)
+    }
+
+    pub fn value(&self, name: &str) -> Option<&str> {
+        self.options.get(name).map(|s| s.as_str())
+    }
+
+    pub fn required(&self, name: &str) -> Result<&str, CliError> {
+        self.value(name).ok_or_else(|| CliError(format!("missing option '--{}'", name)))
+    }
+
+    /// Parses option value, falls back to the default if it was not given
+    pub fn parse_or<T: FromStr>(&self, name: &str, default: T) -> Result<T, CliError> {
+        match self.value(name) {
+            Some(value) => value.parse().map_err(|_| CliError(format!("invalid value '{}' for '--{}'", value, name))),
+            None => Ok(default),
+        }
+    }
+
+    /// Parses option as a number of bytes with an optional `K`, `M` or `G` suffix, falls back to the default if it
+    /// was not given
+    pub fn size_or(&self, name: &str, default: u64) -> Result<u64, CliError> {
+        match self.value(name) {
+            Some(value) => parse_size(value).ok_or_else(|| CliError(format!("invalid size '{}' for '--{}', expected e.g. 512M", value, name))),
+            None => Ok(default),
+        }
+    }
+
+    /// Parses option as `start..end`, either side may be left out
+    pub fn range_or(&self, name: &str, len: u32) -> Result<Range<u32>, CliError> {
+        match self.value(name) {
+            Some(value) => parse_range(value, len),
+            None => Ok(0..len),
+        }
+    }
+}
+
+/// Parses `start..end` limited to `0..len`, either side may be left out
+pub fn parse_range(s: &str, len: u32) -> Result<Range<u32>, CliError> {
+    let invalid = || CliError(format!("invalid range '{}', expected start..end", s));
+    let i = s.find("..").ok_or_else(invalid)?;
+    let (start, end) = (&s[..i], &s[i + 2..]);
+    let start = if start.is_empty() { 0 } else { start.parse().map_err(|_| invalid())? };
+    let end = if end.is_empty() { len } else { end.parse().map_err(|_| invalid())? };
+    if start > end || end > len {
+        return Err(CliError(format!("range '{}' is outside of 0..{}", s, len)));
+    }
+    Ok(start..end)
+}
+
+/// Parses a number of bytes like `4096`, `64K`, `512M` or `2G`, the suffixes are powers of 1024
+pub fn parse_size(s: &str) -> Option<u64> {
+    let (digits, shift) = match s.char_indices().last()? {
+        (i, 'K') | (i, 'k') => (&s[..i], 10),
+        (i, 'M') | (i, 'm') => (&s[..i], 20),
+        (i, 'G') | (i, 'g') => (&s[..i], 30),
+        _ => (s, 0),
+    };
+    digits.parse::<u64>().ok()?.checked_mul(1 << shift)
+}
@@ -0,0 +1,28 @@
+use idxrs::IdxFile;
+
+use super::args::Args;
+use super::file_stem;
+use super::num_records;
+use super::write_records;
+use super::CliError;
+
+pub fn run<I: Iterator<Item = String>>(raw: I) -> Result<(), CliError> {
+    let args = Args::parse(raw, &[], &["size", "out-prefix"])?;
+    args.positional(0, "file")?;
+    let size: u32 = args.parse_or("size", 0)?;
+    if size == 0 {
+        return Err(CliError("'--size' has to be at least 1".into()));
+    }
+    let prefix = args.value("out-prefix").unwrap_or("");
+    for path in args.positionals() {
+        let mut cursor = IdxFile::open(path)?;
+        let n = num_records(&cursor)?;
+        let stem = file_stem(path);
+        // Last batch holds the remaining records
+        for (k, start) in (0..n).step_by(size as usize).enumerate() {
+            let end = start.saturating_add(size).min(n);
+            write_records(&mut cursor, start..end, &format!("{}{}-{}.idx", prefix, stem, k))?;
+        }
+    }
+    Ok(())
+}
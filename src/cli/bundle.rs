@@ -0,0 +1,69 @@
+use std::fs;
+use std::fs::File;
+use std::io;
+use std::io::BufWriter;
+use std::path::Path;
+
+use idxrs::bundle::Bundle;
+use idxrs::bundle::BundleWriter;
+use idxrs::IdxSource;
+
+use super::args::Args;
+use super::dtype_name;
+use super::file_stem;
+use super::CliError;
+
+pub fn run<I: Iterator<Item = String>>(raw: I) -> Result<(), CliError> {
+    let args = Args::parse(raw, &[], &["out"])?;
+    match args.positional(0, "pack, unpack or list")? {
+        "pack"   => pack(&args),
+        "unpack" => unpack(&args),
+        "list"   => list(&args),
+        other    => Err(CliError(format!("unknown bundle command '{}', expected pack, unpack or list", other))),
+    }
+}
+
+/// Inputs are given as `name=path` or as plain paths named by their file stem
+fn pack(args: &Args) -> Result<(), CliError> {
+    let out = args.required("out")?;
+    args.positional(1, "file")?;
+    let mut bundle = BundleWriter::new(BufWriter::new(File::create(out)?))?;
+    for input in &args.positionals()[1..] {
+        let (name, path) = match input.split_once('=') {
+            Some((name, path)) => (name.to_string(), path),
+            None               => (file_stem(input), input.as_str()),
+        };
+        bundle.add(&name, IdxSource::open(path)?).map_err(|e| CliError(format!("{}: {}", path, e)))?;
+    }
+    let count = bundle.entries().len();
+    bundle.finish()?;
+    println!("{} entries written to {}", count, out);
+    Ok(())
+}
+
+/// Writes every entry to `<out>/<name>.idx`
+fn unpack(args: &Args) -> Result<(), CliError> {
+    let mut bundle = Bundle::open(args.positional(1, "bundle")?)?;
+    let out = Path::new(args.required("out")?);
+    fs::create_dir_all(out)?;
+    for entry in bundle.entries().to_vec() {
+        // Names come from the file, they must not point outside of the output directory
+        if Path::new(&entry.name).file_name() != Some(entry.name.as_ref()) {
+            return Err(CliError(format!("entry name '{}' is not a plain file name", entry.name)));
+        }
+        let path = out.join(format!("{}.idx", entry.name));
+        bundle.cursor(&entry.name)?;
+        io::copy(&mut bundle.reader(&entry.name)?, &mut BufWriter::new(File::create(&path)?))?;
+        println!("{}", path.display());
+    }
+    Ok(())
+}
+
+fn list(args: &Args) -> Result<(), CliError> {
+    let mut bundle = Bundle::open(args.positional(1, "bundle")?)?;
+    for entry in bundle.entries().to_vec() {
+        let cursor = bundle.cursor(&entry.name)?;
+        println!("{}: {} {:?}, {} bytes at offset {}", entry.name, dtype_name(cursor.dtype()), cursor.dimensions(), entry.len, entry.offset);
+    }
+    Ok(())
+}
@@ -0,0 +1,23 @@
+use std::fs::File;
+use std::io::BufWriter;
+
+use idxrs::IdxConcat;
+use idxrs::IdxFile;
+
+use super::args::Args;
+use super::CliError;
+
+pub fn run<I: Iterator<Item = String>>(raw: I) -> Result<(), CliError> {
+    let args = Args::parse(raw, &[], &["out"])?;
+    args.positional(0, "file")?;
+    let out = args.required("out")?;
+
+    let mut parts = Vec::new();
+    for path in args.positionals() {
+        parts.push(IdxFile::open(path)?);
+    }
+    let mut concat = IdxConcat::new(parts).map_err(|e| CliError(format!("cannot concatenate: {}", e)))?;
+    concat.write_to(BufWriter::new(File::create(out)?))?;
+    println!("{} records written to {}", concat.dimensions()[0], out);
+    Ok(())
+}
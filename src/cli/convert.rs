@@ -0,0 +1,316 @@
+use std::convert::TryFrom;
+use std::fs;
+use std::fs::File;
+use std::io::BufRead;
+use std::io::BufReader;
+use std::io::BufWriter;
+use std::io::Read;
+use std::io::Write;
+use std::path::Path;
+use std::path::PathBuf;
+
+use idxrs::csv;
+use idxrs::jsonl;
+use idxrs::npy;
+use idxrs::recode::recode_chunked;
+use idxrs::recode::CastPolicy;
+use idxrs::IdxCursor;
+use idxrs::IdxFile;
+use idxrs::IdxOptions;
+use idxrs::IdxSource;
+
+use super::args::Args;
+use super::parse_dtype;
+use super::progress;
+use super::CliError;
+
+/// Payload size above which JSON Lines output gets a warning, every byte becomes up to four characters
+const JSONL_WARN_BYTES: u64 = 16 << 20;
+
+/// Memory used by default, split between read buffer, conversion chunks and write buffer
+const DEFAULT_MAX_MEMORY: u64 = 64 << 20;
+
+/// Smallest `--max-memory` that leaves every buffer a useful size
+const MIN_MAX_MEMORY: u64 = 64 << 10;
+
+fn has_extension(path: &str, extension: &str) -> bool {
+    Path::new(path).extension() == Some(extension.as_ref())
+}
+
+/// Buffer sizes derived from `--max-memory`, a quarter each for reading and writing and half for conversion
+struct Budget {
+    read: usize,
+    chunk: usize,
+    write: usize,
+}
+
+impl Budget {
+    fn new(max_memory: u64) -> Result<Budget, CliError> {
+        if max_memory < MIN_MAX_MEMORY {
+            return Err(CliError(format!("--max-memory has to be at least {} bytes", MIN_MAX_MEMORY)));
+        }
+        let quarter = usize::try_from(max_memory / 4).unwrap_or(usize::MAX / 4);
+        Ok(Budget { read: quarter, chunk: 2 * quarter, write: quarter })
+    }
+
+    fn open(&self, path: &Path) -> Result<IdxFile, CliError> {
+        let options = IdxOptions { buffer_size: self.read, ..IdxOptions::default() };
+        Ok(IdxCursor::with_options(IdxSource::open(path)?, options)?)
+    }
+
+    fn create(&self, path: &Path) -> Result<BufWriter<File>, CliError> {
+        Ok(BufWriter::with_capacity(self.write, File::create(path)?))
+    }
+}
+
+/// Intermediate IDX files next to the output, so conversions never hold a whole file in memory, and the output
+/// itself until it is complete, removed when dropped
+struct TempFiles {
+    out: String,
+    paths: Vec<PathBuf>,
+}
+
+impl TempFiles {
+    fn new(out: &str) -> TempFiles {
+        TempFiles { out: out.to_string(), paths: Vec::new() }
+    }
+
+    fn next_path(&mut self) -> PathBuf {
+        let path = PathBuf::from(format!("{}.part{}", self.out, self.paths.len()));
+        self.paths.push(path.clone());
+        path
+    }
+
+    /// Path the output is written to, renamed to the output once the conversion succeeded
+    fn output(&mut self) -> PathBuf {
+        let path = PathBuf::from(format!("{}.tmp", self.out));
+        self.paths.push(path.clone());
+        path
+    }
+}
+
+impl Drop for TempFiles {
+    fn drop(&mut self) {
+        for path in &self.paths {
+            let _ = fs::remove_file(path);
+        }
+    }
+}
+
+/// Gzip is recognized like `IdxSource::open` does, by the extension or the magic bytes
+fn is_gzip(path: &str) -> Result<bool, CliError> {
+    let mut magic = Vec::with_capacity(2);
+    File::open(path)?.take(2).read_to_end(&mut magic)?;
+    Ok(has_extension(path, "gz") || magic == [0x1f, 0x8b])
+}
+
+/// Parses record shape like `28x28`
+fn parse_shape(s: &str) -> Result<Vec<u32>, CliError> {
+    s.split('x').map(|d| d.parse().map_err(|_| CliError(format!("invalid shape '{}', expected e.g. 28x28", s)))).collect()
+}
+
+/// Streams CSV rows into an IDX file, the label column is written to `--labels` if given
+fn open_csv(path: &str, args: &Args, budget: &Budget, temp: &mut TempFiles) -> Result<IdxFile, CliError> {
+    let data_type = parse_dtype(args.value("dtype").unwrap_or("u8"))?;
+    let labels = args.value("labels");
+    // Without a shape, every record is a flat row of all value columns
+    let record_shape = match args.value("shape") {
+        Some(shape) => parse_shape(shape)?,
+        None => {
+            let first = BufReader::new(File::open(path)?).lines().next().transpose()?.unwrap_or_default();
+            let columns = first.split(',').count() as u32;
+            vec![columns - labels.is_some() as u32]
+        }
+    };
+    let reader = BufReader::with_capacity(budget.read, File::open(path)?);
+    let records = temp.next_path();
+    let writer = budget.create(&records)?;
+    match labels {
+        Some(labels) => {
+            let label_file = BufWriter::new(File::create(labels)?);
+            csv::read_labeled_csv(reader, data_type, &record_shape, writer, label_file)?;
+        }
+        None => {
+            csv::read_csv(reader, data_type, &record_shape, writer)?;
+        }
+    }
+    budget.open(&records)
+}
+
+/// Opens IDX files directly, `.npy` and `.csv` files are converted into an intermediate IDX file first
+fn open(path: &str, args: &Args, budget: &Budget, temp: &mut TempFiles) -> Result<IdxFile, CliError> {
+    if has_extension(path, "npy") {
+        let converted = temp.next_path();
+        npy::read_npy(BufReader::with_capacity(budget.read, File::open(path)?), budget.create(&converted)?)?;
+        budget.open(&converted)
+    } else if has_extension(path, "csv") {
+        open_csv(path, args, budget, temp)
+    } else {
+        budget.open(path.as_ref())
+    }
+}
+
+/// Parses `--cast`, values are cast like `as` by default
+fn parse_policy(args: &Args) -> Result<CastPolicy, CliError> {
+    match args.value("cast").unwrap_or("truncate") {
+        "truncate" => Ok(CastPolicy::Truncate),
+        "round"    => Ok(CastPolicy::Round),
+        "exact"    => Ok(CastPolicy::Exact),
+        other      => Err(CliError(format!("unknown cast policy '{}', expected truncate, round or exact", other))),
+    }
+}
+
+pub fn run<I: Iterator<Item = String>>(raw: I) -> Result<(), CliError> {
+    let args = Args::parse(raw, &["progress"], &["dtype", "to", "labels", "shape", "cast", "max-memory"])?;
+    let input = args.positional(0, "in")?;
+    let out = args.positional(1, "out")?;
+    let budget = Budget::new(args.size_or("max-memory", DEFAULT_MAX_MEMORY)?)?;
+    // Gzip input is decompressed into memory as a whole, no buffer size can bound that
+    if args.value("max-memory").is_some() && is_gzip(input)? {
+        return Err(CliError(format!("--max-memory does not apply to gzip input {}, decompress it first", input)));
+    }
+    let mut temp = TempFiles::new(out);
+    let mut cursor = open(input, &args, &budget, &mut temp)?;
+    let data_type = match args.value("dtype") {
+        Some(name) => parse_dtype(name)?,
+        None => cursor.dtype(),
+    };
+    let policy = parse_policy(&args)?;
+    let inferred = ["npy", "csv", "jsonl"].iter().copied().find(|e| has_extension(out, e)).unwrap_or("idx");
+    let format = args.value("to").unwrap_or(inferred);
+    let partial = temp.output();
+    if format == "idx" {
+        let mut writer = budget.create(&partial)?;
+        recode_chunked(&mut cursor, data_type, policy, &mut writer, budget.chunk, progress(out.to_string(), args.flag("progress")))?;
+        return finish(writer, &partial, out);
+    }
+    if data_type != cursor.dtype() {
+        let recoded = temp.next_path();
+        let writer = budget.create(&recoded)?;
+        recode_chunked(&mut cursor, data_type, policy, writer, budget.chunk, progress(input.to_string(), args.flag("progress")))?;
+        cursor = budget.open(&recoded)?;
+    }
+    let mut writer = budget.create(&partial)?;
+    match format {
+        "npy" => npy::write_npy(&mut cursor, &mut writer)?,
+        // Labels are only joined when writing CSV, for CSV input they were already split off
+        "csv" => {
+            match args.value("labels").filter(|_| !has_extension(input, "csv")) {
+                Some(labels) => csv::write_labeled_csv(&mut cursor, &mut IdxFile::open(labels)?, &mut writer)?,
+                None => csv::write_csv(&mut cursor, &mut writer)?,
+            };
+        }
+        "jsonl" => {
+            if cursor.header().payload_bytes > JSONL_WARN_BYTES {
+                eprintln!("warning: {} has a payload of {} bytes, JSON Lines output will be several times larger",
+                    input, cursor.header().payload_bytes);
+            }
+            match args.value("labels") {
+                Some(labels) => jsonl::write_labeled_jsonl(&mut cursor, &mut IdxFile::open(labels)?, &mut writer)?,
+                None => jsonl::write_jsonl(&mut cursor, &mut writer)?,
+            };
+        }
+        _ => return Err(CliError(format!("unknown output format '{}', expected idx, npy, csv or jsonl", format))),
+    }
+    finish(writer, &partial, out)
+}
+
+/// Flushes the complete output and moves it into place, so a failed conversion never leaves a partial `out`
+fn finish(mut writer: BufWriter<File>, partial: &Path, out: &str) -> Result<(), CliError> {
+    writer.flush()?;
+    drop(writer);
+    fs::rename(partial, out)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::io;
+    use std::path::PathBuf;
+
+    use idxrs::npy;
+    use idxrs::recode::recode;
+    use idxrs::testing;
+    use idxrs::IdxCursor;
+    use idxrs::IdxDataType;
+
+    use super::run;
+
+    /// Path in the temp directory that no other test uses
+    fn temp_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("idxrs-convert-{}-{}", std::process::id(), name))
+    }
+
+    fn convert(args: &[&str]) -> Result<(), super::CliError> {
+        run(args.iter().map(|a| a.to_string()))
+    }
+
+    fn leftovers(out: &str) -> Vec<PathBuf> {
+        let dir = std::env::temp_dir();
+        let name = PathBuf::from(out).file_name().unwrap().to_string_lossy().into_owned();
+        fs::read_dir(&dir).unwrap()
+            .map(|e| e.unwrap().path())
+            .filter(|p| p.file_name().unwrap().to_string_lossy().starts_with(&format!("{}.", name)))
+            .collect()
+    }
+
+    #[test]
+    fn chunks_smaller_than_a_record() {
+        // Records of 180 KiB as shorts and 720 KiB as doubles, a 64 KiB budget splits every record into chunks
+        let data = testing::generate_random_idx(IdxDataType::Short, &[3, 300, 300], 5).unwrap();
+        let input = temp_path("chunks.idx");
+        fs::write(&input, &data).unwrap();
+        let input = input.to_str().unwrap();
+        let expected = recode(&mut IdxCursor::from_bytes(data).unwrap(), IdxDataType::Double, io::Cursor::new(Vec::new())).unwrap().into_inner();
+
+        for (ext, to) in &[("idx", "idx"), ("npy", "npy")] {
+            let small = temp_path(&format!("chunks-small.{}", ext));
+            let large = temp_path(&format!("chunks-large.{}", ext));
+            let (small, large) = (small.to_str().unwrap(), large.to_str().unwrap());
+            convert(&[input, small, "--dtype", "f64", "--to", to, "--max-memory", "64K"]).unwrap();
+            convert(&[input, large, "--dtype", "f64", "--to", to, "--max-memory", "1G"]).unwrap();
+            assert_eq!(fs::read(small).unwrap(), fs::read(large).unwrap(), "{} output differs", to);
+            if *to == "idx" {
+                assert_eq!(fs::read(small).unwrap(), expected);
+            } else {
+                let mut idx = Vec::new();
+                npy::read_npy(fs::File::open(small).unwrap(), io::Cursor::new(&mut idx)).unwrap();
+                assert_eq!(idx, expected);
+            }
+            assert!(leftovers(small).is_empty() && leftovers(large).is_empty());
+            fs::remove_file(small).unwrap();
+            fs::remove_file(large).unwrap();
+        }
+        fs::remove_file(input).unwrap();
+    }
+
+    #[test]
+    fn failed_conversion_keeps_output() {
+        // Only the first chunks hold whole numbers, so the exact cast fails after part of the output is written
+        let values: Vec<f32> = (0..40_000).map(|i| if i < 30_000 { 1.0 } else { 0.5 }).collect();
+        let data = idxrs::IdxWriter::write_array(io::Cursor::new(Vec::new()), &[40_000], &values).unwrap().into_inner();
+        let input = temp_path("failed.idx");
+        let out = temp_path("failed-out.idx");
+        fs::write(&input, data).unwrap();
+        fs::write(&out, b"previous").unwrap();
+        let (input, out) = (input.to_str().unwrap(), out.to_str().unwrap());
+        assert!(convert(&[input, out, "--dtype", "u8", "--cast", "exact", "--max-memory", "64K"]).is_err());
+        assert_eq!(fs::read(out).unwrap(), b"previous");
+        assert!(leftovers(out).is_empty());
+        fs::remove_file(input).unwrap();
+        fs::remove_file(out).unwrap();
+    }
+
+    #[test]
+    fn gzip_input_rejects_max_memory() {
+        let input = temp_path("gzip.idx.gz");
+        fs::write(&input, [0x1f, 0x8b, 8, 0]).unwrap();
+        let out = temp_path("gzip-out.idx");
+        let (input, out) = (input.to_str().unwrap(), out.to_str().unwrap());
+        let err = convert(&[input, out, "--max-memory", "1M"]).unwrap_err();
+        assert!(err.0.contains("gzip"), "{}", err.0);
+        assert!(fs::metadata(out).is_err());
+        fs::remove_file(input).unwrap();
+    }
+}
@@ -0,0 +1,49 @@
+use idxrs::IdxFile;
+
+use super::args::Args;
+use super::dtype_name;
+use super::CliError;
+
+pub fn run<I: Iterator<Item = String>>(raw: I) -> Result<(), CliError> {
+    let args = Args::parse(raw, &[], &["tolerance", "limit"])?;
+    let (path_a, path_b) = (args.positional(0, "file")?, args.positional(1, "second file")?);
+    let tolerance: f64 = args.parse_or("tolerance", 0.0)?;
+    let limit: usize = args.parse_or("limit", 10)?;
+    let mut a = IdxFile::open(path_a)?;
+    let mut b = IdxFile::open(path_b)?;
+
+    if a.dimensions() != b.dimensions() {
+        return Err(CliError(format!("dimensions differ: {:?} and {:?}", a.dimensions(), b.dimensions())));
+    }
+    // Values are compared as numbers, so a file and its re-encode into another data type can be compared
+    if a.dtype() != b.dtype() {
+        println!("data types differ: {} and {}", dtype_name(a.dtype()), dtype_name(b.dtype()));
+    }
+    let (mut count, mut max_diff) = (0u64, 0f64);
+    for (x, y) in a.iter().zip(b.iter()) {
+        let ((index, x), (_, y)) = (x?, y?);
+        // Both NaN counts as equal, the files hold the same value
+        let (fx, fy) = x.promote(y);
+        if x == y || (fx.is_nan() && fy.is_nan()) {
+            continue;
+        }
+        let diff = (fx - fy).abs();
+        if diff <= tolerance {
+            continue;
+        }
+        if count < limit as u64 {
+            println!("{:?} {} {}", index, x, y);
+        }
+        count += 1;
+        // NaN against a number is a difference without a size
+        if diff > max_diff {
+            max_diff = diff;
+        }
+    }
+    if count == 0 {
+        println!("files are equal");
+        return Ok(());
+    }
+    println!("{} of {} elements differ, max abs diff {}", count, a.header().element_count, max_diff);
+    Err(CliError("files differ".into()))
+}
@@ -0,0 +1,62 @@
+use std::convert::TryFrom;
+
+use idxrs::format::format_record;
+use idxrs::IdxFile;
+use idxrs::IdxSlice;
+use idxrs::IdxValue;
+
+use super::args::Args;
+use super::num_records;
+use super::CliError;
+
+/// Shades from dark to bright
+const SHADES: &[u8] = b" .:-=+*#%@";
+
+pub fn run<I: Iterator<Item = String>>(raw: I) -> Result<(), CliError> {
+    let args = Args::parse(raw, &["ascii"], &["limit", "records"])?;
+    let mut cursor = IdxFile::open(args.positional(0, "file")?)?;
+    if args.value("records").is_some() || args.flag("ascii") {
+        let range = args.range_or("records", num_records(&cursor)?)?;
+        for i in range {
+            println!("record {}", i);
+            print_record(&cursor.get_record(&[i])?, args.flag("ascii"))?;
+        }
+        return Ok(());
+    }
+    let limit: usize = args.parse_or("limit", 100)?;
+    for res in cursor.iter().take(limit) {
+        let (index, value) = res?;
+        println!("{:?} {}", index, value);
+    }
+    Ok(())
+}
+
+/// Prints one line per row of the last dimension, as right aligned values or as shades between the record's
+/// smallest and largest value
+fn print_record(record: &IdxSlice, ascii: bool) -> Result<(), CliError> {
+    let size = record.data_type().get_size() as usize;
+    let mut values = Vec::with_capacity(record.len());
+    for b in record.as_bytes().chunks_exact(size) {
+        values.push(IdxValue::try_from((record.data_type(), Box::from(b)))?);
+    }
+    let columns = record.shape().last().copied().unwrap_or(1).max(1) as usize;
+    if ascii {
+        let min = values.iter().map(|v| v.as_f64()).fold(f64::INFINITY, f64::min);
+        let max = values.iter().map(|v| v.as_f64()).fold(f64::NEG_INFINITY, f64::max);
+        for row in values.chunks(columns) {
+            let line: String = row.iter().map(|v| shade(v.as_f64(), min, max)).collect();
+            println!("{}", line);
+        }
+    } else {
+        print!("{}", format_record(&values, record.shape()));
+    }
+    Ok(())
+}
+
+/// Two characters per value, terminal cells are about twice as high as wide
+fn shade(v: f64, min: f64, max: f64) -> String {
+    let level = if max > min { (v - min) / (max - min) } else { 0.0 };
+    let i = ((level * (SHADES.len() - 1) as f64).round() as usize).min(SHADES.len() - 1);
+    let c = SHADES[i] as char;
+    format!("{}{}", c, c)
+}
@@ -0,0 +1,43 @@
+use idxrs::IdxFile;
+
+use super::args::Args;
+use super::CliError;
+
+pub fn run<I: Iterator<Item = String>>(raw: I) -> Result<(), CliError> {
+    let args = Args::parse(raw, &[], &["labels", "limit"])?;
+    let mut cursor = IdxFile::open(args.positional(0, "file")?)?;
+    let limit: usize = args.parse_or("limit", 100)?;
+    let mut labels = match args.value("labels") {
+        Some(path) => Some(IdxFile::open(path)?),
+        None => None,
+    };
+    let groups = cursor.duplicate_records()?;
+    let mut conflicts = 0;
+    for (i, group) in groups.iter().enumerate() {
+        let indices: Vec<String> = group.iter().map(|r| r.to_string()).collect();
+        let mut line = format!("{} copies: {}", group.len(), indices.join(", "));
+        if let Some(labels) = &mut labels {
+            let mut values = Vec::with_capacity(group.len());
+            for record in group {
+                values.push(labels.get(&[*record])?);
+            }
+            let names: Vec<String> = values.iter().map(|v| v.to_string()).collect();
+            line.push_str(&format!(", labels {}", names.join(", ")));
+            // Identical inputs with different labels cannot all be right
+            if values.iter().any(|v| *v != values[0]) {
+                conflicts += 1;
+                line.push_str(" (conflict)");
+            }
+        }
+        if i < limit {
+            println!("{}", line);
+        }
+    }
+    let redundant: usize = groups.iter().map(|group| group.len() - 1).sum();
+    print!("{} groups of duplicates, {} redundant records", groups.len(), redundant);
+    if labels.is_some() {
+        print!(", {} label conflicts", conflicts);
+    }
+    println!();
+    Ok(())
+}
@@ -0,0 +1,47 @@
+use std::fs;
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::Path;
+
+use idxrs::IdxFile;
+
+use super::args::Args;
+use super::num_records;
+use super::CliError;
+
+pub fn run<I: Iterator<Item = String>>(raw: I) -> Result<(), CliError> {
+    let args = Args::parse(raw, &[], &["out", "range", "format", "labels"])?;
+    let mut cursor = IdxFile::open(args.positional(0, "file")?)?;
+    let out = Path::new(args.required("out")?);
+    let n = num_records(&cursor)?;
+    let range = args.range_or("range", n)?;
+    let format = args.value("format").unwrap_or("png");
+    if format != "png" && format != "pgm" {
+        return Err(CliError(format!("unknown image format '{}', expected png or pgm", format)));
+    }
+    let mut labels = match args.value("labels") {
+        Some(path) => {
+            let labels = IdxFile::open(path)?;
+            if labels.dimensions().len() != 1 || labels.dimensions()[0] != n {
+                return Err(CliError(format!("{} has to hold one label for each of the {} records", path, n)));
+            }
+            Some(labels)
+        }
+        None => None,
+    };
+    fs::create_dir_all(out)?;
+    for i in range {
+        let image = cursor.record_image(i)?;
+        let name = match &mut labels {
+            Some(labels) => format!("{}_{}.{}", i, labels.get(&[i])?, format),
+            None => format!("{}.{}", i, format),
+        };
+        let file = BufWriter::new(File::create(out.join(name))?);
+        if format == "png" {
+            image.write_png(file)?;
+        } else {
+            image.write_pgm(file)?;
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,84 @@
+use idxrs::stats;
+use idxrs::stats::IdxStats;
+use idxrs::IdxCursor;
+use idxrs::IdxHeader;
+use idxrs::IdxSource;
+use idxrs::IdxWarning;
+
+use super::args::Args;
+use super::dtype_name;
+use super::json_string;
+use super::CliError;
+
+/// Empty files have no statistics
+fn value_stats(cursor: &mut IdxCursor<IdxSource>) -> Result<Option<IdxStats>, CliError> {
+    let stats = stats::compute(cursor)?;
+    Ok(if stats.count == 0 { None } else { Some(stats) })
+}
+
+fn warn(warning: IdxWarning) {
+    eprintln!("warning: {}", warning);
+}
+
+fn json_number(v: f64) -> String {
+    if v.is_finite() { v.to_string() } else { "null".into() }
+}
+
+fn print_text(path: &str, header: &IdxHeader, actual: u64, stats: &Option<IdxStats>) {
+    let dimensions: Vec<String> = header.dimensions.iter().map(|d| d.to_string()).collect();
+    println!("file:       {}", path);
+    println!("magic:      00 00 {:02x} {:02x}", header.data_type.get_code(), header.dimensions.len());
+    println!("data type:  {} (0x{:02x})", dtype_name(header.data_type), header.data_type.get_code());
+    println!("dimensions: {}", if dimensions.is_empty() { "none (scalar)".to_string() } else { dimensions.join(" x ") });
+    println!("elements:   {}", header.element_count);
+    let check = if actual == header.file_bytes() { "ok" } else if actual < header.file_bytes() { "truncated" } else { "trailing data" };
+    println!("size:       {} bytes, expected {} ({})", actual, header.file_bytes(), check);
+    if let Some(stats) = stats {
+        println!("min:        {}", stats.min);
+        println!("max:        {}", stats.max);
+        println!("mean:       {}", stats.mean);
+        println!("std:        {}", stats.std);
+    }
+}
+
+fn print_json(path: &str, header: &IdxHeader, actual: u64, stats: &Option<IdxStats>) {
+    let dimensions: Vec<String> = header.dimensions.iter().map(|d| d.to_string()).collect();
+    let mut fields = vec![
+        format!("\"file\":{}", json_string(path)),
+        format!("\"magic\":[0,0,{},{}]", header.data_type.get_code(), header.dimensions.len()),
+        format!("\"data_type\":{}", json_string(dtype_name(header.data_type))),
+        format!("\"dimensions\":[{}]", dimensions.join(",")),
+        format!("\"elements\":{}", header.element_count),
+        format!("\"expected_bytes\":{}", header.file_bytes()),
+        format!("\"actual_bytes\":{}", actual),
+        format!("\"size_ok\":{}", actual == header.file_bytes()),
+    ];
+    if let Some(stats) = stats {
+        fields.push(format!("\"min\":{}", json_number(stats.min)));
+        fields.push(format!("\"max\":{}", json_number(stats.max)));
+        fields.push(format!("\"mean\":{}", json_number(stats.mean)));
+        fields.push(format!("\"std\":{}", json_number(stats.std)));
+    }
+    println!("{{{}}}", fields.join(","));
+}
+
+pub fn run<I: Iterator<Item = String>>(raw: I) -> Result<(), CliError> {
+    let args = Args::parse(raw, &["stats", "json", "lenient"], &[])?;
+    args.positional(0, "file")?;
+    for path in args.positionals() {
+        let mut cursor = IdxCursor::builder(IdxSource::open(path)?)
+            .lenient(args.flag("lenient"))
+            .diagnostics(warn)
+            .open()?;
+        let header = cursor.header();
+        let actual = cursor.stream_len()?;
+        // Statistics of a truncated file would stop at the first missing element
+        let stats = if args.flag("stats") && actual >= header.file_bytes() { value_stats(&mut cursor)? } else { None };
+        if args.flag("json") {
+            print_json(path, &header, actual, &stats);
+        } else {
+            print_text(path, &header, actual, &stats);
+        }
+    }
+    Ok(())
+}
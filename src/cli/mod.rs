@@ -0,0 +1,212 @@
+pub mod args;
+pub mod batch;
+pub mod bundle;
+pub mod concat;
+pub mod convert;
+pub mod diff;
+pub mod dump;
+pub mod dupes;
+pub mod extract;
+pub mod inspect;
+pub mod quantize;
+pub mod relabel;
+pub mod split;
+pub mod stats;
+pub mod verify;
+
+use std::fmt;
+use std::fs::File;
+use std::io;
+use std::io::BufWriter;
+use std::io::Read;
+use std::io::Seek;
+use std::path::Path;
+
+use idxrs::labels;
+use idxrs::IdxCursor;
+use idxrs::IdxDataType;
+use idxrs::IdxError;
+
+pub const USAGE: &str = "\
+usage: idxrs <command> [options]
+
+commands:
+    inspect <file>... [--stats] [--json]      print header, size check and value statistics,
+            [--lenient]                       --lenient warns about non-zero magic bytes
+                                              instead of failing
+    dump <file> [--limit N]                   print elements with their indices
+    dump <file> --records A..B [--ascii]      print records as grids of values, or of shades
+                                              with --ascii
+    convert <in> <out> [--dtype TYPE] [--cast truncate|round|exact] [--to idx|npy|csv|jsonl]
+            [--labels FILE] [--shape RxC] [--max-memory SIZE] [--progress]
+                                              change data type chunk by chunk, values are
+                                              cast like `as`, rounded first or have to fit
+                                              exactly, or convert between IDX, .npy and .csv
+                                              files, or write JSON Lines with one object per
+                                              record;
+                                              --labels joins a label file into CSV or JSON Lines
+                                              output or receives the first CSV column on CSV
+                                              input;
+                                              --max-memory bounds the buffers (64M), inputs are
+                                              streamed through files next to the output, gzip
+                                              input is decompressed in memory and rejected with
+                                              --max-memory; the output is written to <out>.tmp
+                                              and renamed once complete;
+                                              --progress reports IDX output and casts
+    split <file>... --ratio R [--seed S] [--out-prefix P] [--stratify LABELS]
+                                              split records into train and val files, with a
+                                              seed the records are shuffled the same way in
+                                              every file; --stratify keeps the share of every
+                                              label of LABELS in both sets and splits LABELS too
+    extract <file> --out DIR [--range A..B] [--format png|pgm] [--labels FILE]
+                                              write records of N x rows x cols u8 files as
+                                              images named by index, and label if given
+    batch <file>... --size N [--out-prefix P] split records into files of N records each
+    verify <file>... [--sha256 HASH] [--crc32 HEX] [--progress]
+                                              check file size and checksums, sha256 covers the
+                                              whole (decompressed) file, crc32 the payload
+    concat <file>... -o OUT                   join files with the same data type and record
+                                              shape into one file
+    bundle pack <file>... --out OUT           pack files into one .idxs bundle, inputs given as
+                                              name=path or named by their file stem
+    bundle unpack <bundle> --out DIR          write every entry to DIR/<name>.idx
+    bundle list <bundle>                      print name, data type, dimensions and location
+                                              of every entry
+    diff <a> <b> [--tolerance T] [--limit N] compare dimensions and values, print the first N
+                                              (10) elements that differ by more than T (0) and
+                                              the number of differences and largest difference
+    dupes <file> [--labels FILE] [--limit N]  print groups of identical records, the first N
+                                              (100) of them, with --labels also their labels
+                                              and whether they disagree
+    quantize <in> <out> [--range MIN..MAX] [--params FILE]
+                                              store values as u8 with a scale and zero point
+                                              from the range of the values, the given range or
+                                              a sidecar, which is written to <out>.json
+    dequantize <in> <out> [--params FILE]     turn a quantized file back into f32 with the
+                                              sidecar <in>.json or FILE
+    relabel <labels> --map OLD=NEW,... --out OUT
+                                              write a label file with labels replaced, e.g.
+                                              0=1,1=0 swaps two classes and 3=5,5=5 merges them;
+                                              every label of the file needs an entry
+    stats <file>... [--classes]               print count, min, max, mean and std of the values,
+                                              --classes prints the records per label of a label
+                                              file and how imbalanced the classes are
+    help                                      print this message
+
+data types: u8, i8, i16, i32, f32, f64, with the extensions feature also u16, u32, i64, u64
+";
+
+#[derive(Debug)]
+pub struct CliError(pub String);
+
+impl fmt::Display for CliError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl From<IdxError> for CliError {
+    fn from(e: IdxError) -> CliError {
+        CliError(e.to_string())
+    }
+}
+
+impl From<io::Error> for CliError {
+    fn from(e: io::Error) -> CliError {
+        CliError(e.to_string())
+    }
+}
+
+pub fn parse_dtype(s: &str) -> Result<IdxDataType, CliError> {
+    match s {
+        "u8"  => Ok(IdxDataType::UnsignedByte),
+        "i8"  => Ok(IdxDataType::SignedByte),
+        "i16" => Ok(IdxDataType::Short),
+        "i32" => Ok(IdxDataType::Int),
+        "f32" => Ok(IdxDataType::Float),
+        "f64" => Ok(IdxDataType::Double),
+        #[cfg(feature = "extensions")]
+        "u16" => Ok(IdxDataType::UnsignedShort),
+        #[cfg(feature = "extensions")]
+        "u32" => Ok(IdxDataType::UnsignedInt),
+        #[cfg(feature = "extensions")]
+        "i64" => Ok(IdxDataType::Long),
+        #[cfg(feature = "extensions")]
+        "u64" => Ok(IdxDataType::UnsignedLong),
+        _ => Err(CliError(format!("unknown data type '{}'", s))),
+    }
+}
+
+pub fn dtype_name(data_type: IdxDataType) -> &'static str {
+    match data_type {
+        IdxDataType::UnsignedByte => "u8",
+        IdxDataType::SignedByte   => "i8",
+        IdxDataType::Short        => "i16",
+        IdxDataType::Int          => "i32",
+        IdxDataType::Float        => "f32",
+        IdxDataType::Double       => "f64",
+        #[cfg(feature = "extensions")]
+        IdxDataType::UnsignedShort => "u16",
+        #[cfg(feature = "extensions")]
+        IdxDataType::UnsignedInt  => "u32",
+        #[cfg(feature = "extensions")]
+        IdxDataType::Long         => "i64",
+        #[cfg(feature = "extensions")]
+        IdxDataType::UnsignedLong => "u64",
+    }
+}
+
+/// Callback for `IdxProgress` and long loops, prints the percentage to stderr whenever it changes
+pub fn progress(label: String, enabled: bool) -> impl FnMut(u64, u64) {
+    let mut last = None;
+    move |done, total| {
+        let percent = done * 100 / total.max(1);
+        if enabled && last != Some(percent) {
+            last = Some(percent);
+            eprint!("\r{}: {:3}%", label, percent);
+            if done >= total {
+                eprintln!();
+            }
+        }
+    }
+}
+
+/// Quotes and escapes a string for JSON output
+pub fn json_string(s: &str) -> String {
+    let mut out = String::with_capacity(s.len() + 2);
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            c if (c as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+/// File name without directory and extensions, `train-images.idx3-ubyte.gz` becomes `train-images`
+pub fn file_stem(path: &str) -> String {
+    let name = Path::new(path).file_name().map_or_else(|| path.into(), |n| n.to_string_lossy());
+    name.split('.').next().unwrap_or_default().to_string()
+}
+
+/// Number of records of a file, i.e. the size of its leading dimension
+pub fn num_records<R: Read + Seek>(cursor: &IdxCursor<R>) -> Result<u32, CliError> {
+    cursor.dimensions().first().copied().ok_or_else(|| CliError("file has no records".into()))
+}
+
+/// Copies the selected records into a new file with the same data type and record shape
+pub fn write_records<R, I>(cursor: &mut IdxCursor<R>, records: I, path: &str) -> Result<u32, CliError>
+where
+    R: Read + Seek,
+    I: IntoIterator<Item = u32>,
+{
+    let file = BufWriter::new(File::create(path)?);
+    let mut count = 0;
+    labels::write_records(cursor, records.into_iter().inspect(|_| count += 1), file)?;
+    Ok(count)
+}
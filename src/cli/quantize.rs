@@ -0,0 +1,62 @@
+use std::fs;
+use std::fs::File;
+use std::io::BufWriter;
+
+use idxrs::quantize;
+use idxrs::quantize::QuantParams;
+use idxrs::quantize::Quantization;
+use idxrs::IdxFile;
+
+use super::args::Args;
+use super::CliError;
+
+/// Sidecar with scale and zero point next to a quantized file
+fn sidecar(path: &str) -> String {
+    format!("{}.json", path)
+}
+
+/// Parses `min..max` of floats
+fn parse_range(s: &str) -> Result<(f64, f64), CliError> {
+    let invalid = || CliError(format!("invalid range '{}', expected e.g. -1..1", s));
+    let i = s.find("..").ok_or_else(invalid)?;
+    let min: f64 = s[..i].parse().map_err(|_| invalid())?;
+    let max: f64 = s[i + 2..].parse().map_err(|_| invalid())?;
+    if min.is_nan() || max.is_nan() || min > max {
+        return Err(invalid());
+    }
+    Ok((min, max))
+}
+
+fn read_params(path: &str) -> Result<Quantization, CliError> {
+    let json = fs::read_to_string(path).map_err(|e| CliError(format!("cannot read {}: {}", path, e)))?;
+    Ok(Quantization::from_json(&json)?)
+}
+
+pub fn run<I: Iterator<Item = String>>(raw: I) -> Result<(), CliError> {
+    let args = Args::parse(raw, &[], &["range", "params"])?;
+    let mut cursor = IdxFile::open(args.positional(0, "in")?)?;
+    let out = args.positional(1, "out")?;
+    let params = match (args.value("range"), args.value("params")) {
+        (Some(_), Some(_)) => return Err(CliError("--range and --params cannot be combined".into())),
+        (Some(range), None) => {
+            let (min, max) = parse_range(range)?;
+            QuantParams::Range{ min, max }
+        }
+        (None, Some(path)) => QuantParams::Fixed(read_params(path)?),
+        (None, None) => QuantParams::MinMax,
+    };
+    let (_, quantization) = quantize::quantize(&mut cursor, params, BufWriter::new(File::create(out)?))?;
+    fs::write(sidecar(out), quantization.to_json() + "\n")?;
+    println!("scale {}, zero point {}, written to {} and {}", quantization.scale, quantization.zero_point, out, sidecar(out));
+    Ok(())
+}
+
+pub fn run_dequantize<I: Iterator<Item = String>>(raw: I) -> Result<(), CliError> {
+    let args = Args::parse(raw, &[], &["params"])?;
+    let input = args.positional(0, "in")?;
+    let mut cursor = IdxFile::open(input)?;
+    let out = args.positional(1, "out")?;
+    let quantization = read_params(&args.value("params").map_or_else(|| sidecar(input), |p| p.to_string()))?;
+    quantize::dequantize(&mut cursor, quantization, BufWriter::new(File::create(out)?))?;
+    Ok(())
+}
@@ -0,0 +1,44 @@
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::BufWriter;
+
+use idxrs::labels;
+use idxrs::IdxFile;
+
+use super::args::Args;
+use super::CliError;
+
+/// Parses mapping like `0=1,1=0`, every old label may only appear once
+fn parse_map(s: &str) -> Result<BTreeMap<i64, i64>, CliError> {
+    let mut mapping = BTreeMap::new();
+    for pair in s.split(',') {
+        let invalid = || CliError(format!("invalid mapping '{}', expected e.g. 0=1,1=0", pair));
+        let i = pair.find('=').ok_or_else(invalid)?;
+        let old = pair[..i].trim().parse().map_err(|_| invalid())?;
+        let new = pair[i + 1..].trim().parse().map_err(|_| invalid())?;
+        if mapping.insert(old, new).is_some() {
+            return Err(CliError(format!("label {} is mapped more than once", old)));
+        }
+    }
+    Ok(mapping)
+}
+
+pub fn run<I: Iterator<Item = String>>(raw: I) -> Result<(), CliError> {
+    let args = Args::parse(raw, &[], &["map", "out"])?;
+    let mut cursor = IdxFile::open(args.positional(0, "labels")?)?;
+    let mapping = parse_map(args.required("map")?)?;
+    let out = args.required("out")?;
+    let counts = labels::class_counts(&mut cursor)?;
+    // Checked before the output is created, and all missing labels are named at once
+    let missing: Vec<String> = counts.keys().filter(|l| !mapping.contains_key(l)).map(|l| l.to_string()).collect();
+    if !missing.is_empty() {
+        let noun = if missing.len() == 1 { "label" } else { "labels" };
+        return Err(CliError(format!("no mapping for {} {}", noun, missing.join(", "))));
+    }
+    labels::remap(&mut cursor, &mapping, BufWriter::new(File::create(out)?))?;
+    for (old, count) in &counts {
+        println!("{:>8} -> {:<8} {:>10} records", old, mapping[old], count);
+    }
+    println!("{} labels written to {}", counts.values().sum::<u64>(), out);
+    Ok(())
+}
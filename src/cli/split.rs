@@ -0,0 +1,59 @@
+use idxrs::labels;
+use idxrs::permutation;
+use idxrs::IdxFile;
+
+use super::args::Args;
+use super::file_stem;
+use super::num_records;
+use super::write_records;
+use super::CliError;
+
+pub fn run<I: Iterator<Item = String>>(raw: I) -> Result<(), CliError> {
+    let args = Args::parse(raw, &[], &["ratio", "seed", "out-prefix", "stratify"])?;
+    args.positional(0, "file")?;
+    let ratio: f64 = args.parse_or("ratio", 0.9)?;
+    if !(0.0..=1.0).contains(&ratio) {
+        return Err(CliError(format!("ratio {} is not between 0 and 1", ratio)));
+    }
+    let seed: Option<u64> = match args.value("seed") {
+        Some(_) => Some(args.parse_or("seed", 0)?),
+        None    => None,
+    };
+    let prefix = args.value("out-prefix").unwrap_or("");
+
+    let mut cursors = Vec::new();
+    for path in args.positionals() {
+        cursors.push((path.as_str(), IdxFile::open(path)?));
+    }
+    // All inputs are split at the same record so paired files stay aligned
+    let n = num_records(&cursors[0].1)?;
+    for (path, cursor) in &cursors {
+        if num_records(cursor)? != n {
+            return Err(CliError(format!("{} has {} records, expected {}", path, num_records(cursor)?, n)));
+        }
+    }
+    let order: Vec<u32>;
+    let (train_records, val_records) = if let Some(path) = args.value("stratify") {
+        // The label file is split along with the inputs
+        let mut labels = IdxFile::open(path)?;
+        let split = labels::split_stratified(&mut cursors[0].1, &mut labels, ratio, seed)?;
+        cursors.push((path, labels));
+        order = split.train.iter().chain(&split.val).copied().collect();
+        order.split_at(split.train.len())
+    } else {
+        let train = (n as f64 * ratio).round() as usize;
+        // The same order is used for every input, so a record keeps its label after shuffling
+        order = match seed {
+            Some(seed) => permutation(n, seed),
+            None       => (0..n).collect(),
+        };
+        order.split_at(train)
+    };
+    for (path, cursor) in &mut cursors {
+        let stem = file_stem(path);
+        write_records(cursor, train_records.iter().copied(), &format!("{}train-{}.idx", prefix, stem))?;
+        write_records(cursor, val_records.iter().copied(), &format!("{}val-{}.idx", prefix, stem))?;
+    }
+    println!("{} train / {} val records", train_records.len(), val_records.len());
+    Ok(())
+}
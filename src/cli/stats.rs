@@ -0,0 +1,45 @@
+use idxrs::labels;
+use idxrs::stats;
+use idxrs::IdxFile;
+
+use super::args::Args;
+use super::CliError;
+
+fn print_classes(cursor: &mut IdxFile) -> Result<(), CliError> {
+    let counts = labels::class_counts(cursor)?;
+    let total: u64 = counts.values().sum();
+    let largest = counts.values().copied().max().unwrap_or(0);
+    let smallest = counts.values().copied().min().unwrap_or(0);
+    println!("classes:    {}", counts.len());
+    for (label, count) in &counts {
+        // Ratio of the largest class to this one, 1.00 for a balanced split
+        println!(
+            "  {:>8}: {:>10} ({:6.2}%, ratio {:.2})",
+            label, count, *count as f64 * 100.0 / total as f64, largest as f64 / *count as f64
+        );
+    }
+    if smallest > 0 {
+        println!("imbalance:  {:.2} (largest / smallest class)", largest as f64 / smallest as f64);
+    }
+    Ok(())
+}
+
+pub fn run<I: Iterator<Item = String>>(raw: I) -> Result<(), CliError> {
+    let args = Args::parse(raw, &["classes"], &[])?;
+    args.positional(0, "file")?;
+    for path in args.positionals() {
+        let mut cursor = IdxFile::open(path)?;
+        println!("file:       {}", path);
+        if args.flag("classes") {
+            print_classes(&mut cursor)?;
+        } else {
+            let stats = stats::compute(&mut cursor)?;
+            println!("count:      {}", stats.count);
+            println!("min:        {}", stats.min);
+            println!("max:        {}", stats.max);
+            println!("mean:       {}", stats.mean);
+            println!("std:        {}", stats.std);
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,40 @@
+use idxrs::IdxCursor;
+use idxrs::IdxError;
+use idxrs::IdxProgress;
+use idxrs::IdxSource;
+
+use super::args::Args;
+use super::progress;
+use super::CliError;
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+pub fn run<I: Iterator<Item = String>>(raw: I) -> Result<(), CliError> {
+    let args = Args::parse(raw, &["progress"], &["sha256", "crc32"])?;
+    args.positional(0, "file")?;
+    let expected = args.value("sha256").is_some() || args.value("crc32").is_some();
+    if expected && args.positionals().len() > 1 {
+        return Err(CliError("expected checksums can only be checked for a single file".into()));
+    }
+    for path in args.positionals() {
+        let reader = IdxProgress::new(IdxSource::open(path)?, progress(path.clone(), args.flag("progress")))?;
+        let mut cursor = IdxCursor::new(reader)?;
+        // Truncated files and trailing data are reported before any hash is computed
+        let (expected_bytes, actual) = (cursor.header().file_bytes(), cursor.stream_len()?);
+        if actual != expected_bytes {
+            let e = IdxError::LengthMismatch{ expected: expected_bytes, actual };
+            return Err(CliError(format!("{}: {}", path, e)));
+        }
+        if let Some(crc) = args.value("crc32") {
+            let crc = u32::from_str_radix(crc.trim_start_matches("0x"), 16).map_err(|_| CliError(format!("invalid crc32 '{}'", crc)))?;
+            cursor.verify_against(crc).map_err(|e| CliError(format!("{}: payload {}", path, e)))?;
+        }
+        if let Some(sha256) = args.value("sha256") {
+            cursor.verify_sha256(sha256).map_err(|e| CliError(format!("{}: {}", path, e)))?;
+        }
+        println!("{}: ok, payload crc32 {:08x}, sha256 {}", path, cursor.checksum()?, hex(&cursor.sha256()?));
+    }
+    Ok(())
+}
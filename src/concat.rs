@@ -0,0 +1,128 @@
+use std::io::Read;
+use std::io::Seek;
+use std::io::Write;
+
+use crate::IdxCursor;
+use crate::IdxDataType;
+use crate::IdxError;
+use crate::IdxShape;
+use crate::IdxSlice;
+use crate::IdxValue;
+use crate::StreamingIdxWriter;
+
+/// Several files with the same data type and record shape joined along the leading dimension
+pub struct IdxConcat<R: Read + Seek> {
+    parts: Vec<IdxCursor<R>>,
+    // Index of the first record of each part in the joined file
+    starts: Vec<u32>,
+    data_type: IdxDataType,
+    shape: IdxShape,
+}
+
+impl<R: Read + Seek> IdxConcat<R> {
+    /// Checks that all parts have at least one dimension, the same data type and the same record shape as the first
+    ///
+    /// Fails with `Overflow` if the parts have more than `u32::MAX` records together.
+    pub fn new(parts: Vec<IdxCursor<R>>) -> Result<IdxConcat<R>, IdxError> {
+        let first = parts.first().ok_or(IdxError::DimensionMismatch{ needed: 1, supplied: 0 })?.header();
+        let mut starts = Vec::with_capacity(parts.len());
+        let mut records: u32 = 0;
+        for part in &parts {
+            let header = part.header();
+            if header.dimensions.is_empty() {
+                return Err(IdxError::DimensionMismatch{ needed: 1, supplied: 0 });
+            }
+            if header.data_type != first.data_type {
+                return Err(IdxError::DataTypeMismatch{ expected: first.data_type, found: header.data_type });
+            }
+            if header.record_shape() != first.record_shape() {
+                return Err(IdxError::ShapeMismatch{ expected: first.record_shape().to_vec(), found: header.record_shape().to_vec() });
+            }
+            starts.push(records);
+            records = records.checked_add(header.dimensions[0]).ok_or(IdxError::Overflow)?;
+        }
+        let mut dimensions = vec![records];
+        dimensions.extend_from_slice(first.record_shape());
+        Ok(IdxConcat { parts, starts, data_type: first.data_type, shape: IdxShape::try_new(dimensions)? })
+    }
+
+    /// Dimensions of the joined file, the record count is the sum of all parts
+    pub fn dimensions(&self) -> &[u32] {
+        self.shape.dimensions()
+    }
+
+    pub fn data_type(&self) -> IdxDataType {
+        self.data_type
+    }
+
+    pub fn parts(&self) -> &[IdxCursor<R>] {
+        &self.parts
+    }
+
+    pub fn into_parts(self) -> Vec<IdxCursor<R>> {
+        self.parts
+    }
+
+    /// Part holding the record and the index of the record within it
+    fn locate(&self, record: u32) -> Result<(usize, u32), IdxError> {
+        let records = self.shape.dimensions()[0];
+        if record >= records {
+            return Err(IdxError::OutOfBounds{ dimension: 0, max: records, index: record });
+        }
+        // Empty parts share their start with the next part, the last of them is the one holding the record
+        let part = self.starts.partition_point(|start| *start <= record) - 1;
+        Ok((part, record - self.starts[part]))
+    }
+
+    pub fn get(&mut self, indices: &[u32]) -> Result<IdxValue, IdxError> {
+        self.shape.flat_offset(indices)?;
+        let (part, record) = self.locate(indices[0])?;
+        let mut local = indices.to_vec();
+        local[0] = record;
+        self.parts[part].get(&local)
+    }
+
+    /// Reads the block after the leading indices, the first index selects the record in the joined file
+    pub fn get_record(&mut self, leading_indices: &[u32]) -> Result<IdxSlice, IdxError> {
+        self.shape.record_offset(leading_indices)?;
+        let first = *leading_indices.first().ok_or(IdxError::DimensionMismatch{ needed: 1, supplied: 0 })?;
+        let (part, record) = self.locate(first)?;
+        let mut local = leading_indices.to_vec();
+        local[0] = record;
+        self.parts[part].get_record(&local)
+    }
+
+    /// Writes all parts as one big endian IDX file and returns the writer
+    pub fn write_to<W: Write + Seek>(&mut self, writer: W) -> Result<W, IdxError> {
+        let mut out = StreamingIdxWriter::new(writer, self.data_type, &self.shape.dimensions()[1..])?;
+        for part in &mut self.parts {
+            for record in 0..part.dimensions()[0] {
+                out.append_record_bytes(part.get_record(&[record])?.as_bytes())?;
+            }
+        }
+        out.finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::IdxConcat;
+    use crate::IdxCursor;
+    use crate::IdxError;
+
+    /// Header of a u8 file of empty records, which needs no payload for any record count
+    fn empty_records(records: u32) -> IdxCursor<std::io::Cursor<Vec<u8>>> {
+        let mut data = vec![0, 0, 0x08, 2];
+        data.extend_from_slice(&records.to_be_bytes());
+        data.extend_from_slice(&0u32.to_be_bytes());
+        IdxCursor::from_bytes(data).unwrap()
+    }
+
+    #[test]
+    fn records_beyond_u32() {
+        let res = IdxConcat::new(vec![empty_records(u32::MAX), empty_records(1)]);
+        assert!(matches!(res, Err(IdxError::Overflow)));
+        let joined = IdxConcat::new(vec![empty_records(u32::MAX - 1), empty_records(1)]).unwrap();
+        assert_eq!(joined.dimensions(), &[u32::MAX, 0]);
+    }
+}
@@ -0,0 +1,146 @@
+//! Conversion between IDX files and CSV files with one record per row
+
+use std::io;
+use std::io::BufRead;
+use std::io::Read;
+use std::io::Seek;
+use std::io::Write;
+
+use crate::IdxCursor;
+use crate::IdxDataType;
+use crate::IdxError;
+use crate::IdxValue;
+use crate::StreamingIdxWriter;
+
+fn invalid(line: usize, msg: String) -> IdxError {
+    IdxError::IoError(io::Error::new(io::ErrorKind::InvalidData, format!("line {}: {}", line, msg)))
+}
+
+fn parse_value(data_type: IdxDataType, s: &str) -> Option<IdxValue> {
+    match data_type {
+        IdxDataType::UnsignedByte => s.parse().ok().map(IdxValue::UnsignedByte),
+        IdxDataType::SignedByte   => s.parse().ok().map(IdxValue::SignedByte),
+        IdxDataType::Short        => s.parse().ok().map(IdxValue::Short),
+        IdxDataType::Int          => s.parse().ok().map(IdxValue::Int),
+        IdxDataType::Float        => s.parse().ok().map(IdxValue::Float),
+        IdxDataType::Double       => s.parse().ok().map(IdxValue::Double),
+        #[cfg(feature = "extensions")]
+        IdxDataType::UnsignedShort => s.parse().ok().map(IdxValue::UnsignedShort),
+        #[cfg(feature = "extensions")]
+        IdxDataType::UnsignedInt  => s.parse().ok().map(IdxValue::UnsignedInt),
+        #[cfg(feature = "extensions")]
+        IdxDataType::Long         => s.parse().ok().map(IdxValue::Long),
+        #[cfg(feature = "extensions")]
+        IdxDataType::UnsignedLong => s.parse().ok().map(IdxValue::UnsignedLong),
+    }
+}
+
+/// Values of one record, the leading dimension is the row and a scalar file is a single row
+fn record_fields<R: Read + Seek>(cursor: &mut IdxCursor<R>, i: u32) -> Result<Vec<String>, IdxError> {
+    let index = [i];
+    let record = cursor.get_record(&index[..cursor.dimensions().len().min(1)])?;
+    let size = record.data_type().get_size() as usize;
+    record.as_bytes()
+        .chunks_exact(size)
+        .map(|b| IdxValue::decode(record.data_type(), b).map(|value| value.to_string()))
+        .collect()
+}
+
+/// Writes one row per record with a `pixel0,pixel1,...` header
+pub fn write_csv<R: Read + Seek, W: Write>(cursor: &mut IdxCursor<R>, mut writer: W) -> Result<W, IdxError> {
+    let columns = cursor.record_len();
+    let header: Vec<String> = (0..columns).map(|i| format!("pixel{}", i)).collect();
+    writeln!(writer, "{}", header.join(","))?;
+    for i in 0..cursor.num_records() {
+        writeln!(writer, "{}", record_fields(cursor, i)?.join(","))?;
+    }
+    writer.flush()?;
+    Ok(writer)
+}
+
+/// Like `write_csv`, but starts every row with the value of a label file with one label per record
+pub fn write_labeled_csv<R, L, W>(cursor: &mut IdxCursor<R>, labels: &mut IdxCursor<L>, mut writer: W) -> Result<W, IdxError>
+where
+    R: Read + Seek,
+    L: Read + Seek,
+    W: Write,
+{
+    if labels.dimensions().len() != 1 {
+        return Err(IdxError::DimensionMismatch{ needed: 1, supplied: labels.dimensions().len() });
+    }
+    let (left, right) = (cursor.num_records(), labels.dimensions()[0]);
+    if left != right {
+        return Err(IdxError::RecordCountMismatch{ left, right });
+    }
+    let columns = cursor.record_len();
+    let mut header = vec!["label".to_string()];
+    header.extend((0..columns).map(|i| format!("pixel{}", i)));
+    writeln!(writer, "{}", header.join(","))?;
+    for i in 0..left {
+        let mut fields = vec![labels.get(&[i])?.to_string()];
+        fields.extend(record_fields(cursor, i)?);
+        writeln!(writer, "{}", fields.join(","))?;
+    }
+    writer.flush()?;
+    Ok(writer)
+}
+
+/// Reads rows of `data_type` values into records of `record_shape`, an optional first line with column names is skipped
+pub fn read_csv<R: BufRead, W: Write + Seek>(reader: R, data_type: IdxDataType, record_shape: &[u32], writer: W) -> Result<W, IdxError> {
+    let mut records = StreamingIdxWriter::new(writer, data_type, record_shape)?;
+    for_each_row(reader, data_type, record_shape, false, |_, values| records.append_record(values))?;
+    records.finish()
+}
+
+/// Like `read_csv`, but the first column holds labels that are written as unsigned bytes to a separate file
+pub fn read_labeled_csv<R, W, L>(reader: R, data_type: IdxDataType, record_shape: &[u32], writer: W, label_writer: L) -> Result<(W, L), IdxError>
+where
+    R: BufRead,
+    W: Write + Seek,
+    L: Write + Seek,
+{
+    let mut records = StreamingIdxWriter::new(writer, data_type, record_shape)?;
+    let mut labels = StreamingIdxWriter::new(label_writer, IdxDataType::UnsignedByte, &[])?;
+    for_each_row(reader, data_type, record_shape, true, |label, values| {
+        if let Some(label) = label {
+            labels.append_record(&[label])?;
+        }
+        records.append_record(values)
+    })?;
+    Ok((records.finish()?, labels.finish()?))
+}
+
+fn for_each_row<R, F>(reader: R, data_type: IdxDataType, record_shape: &[u32], labeled: bool, mut f: F) -> Result<(), IdxError>
+where
+    R: BufRead,
+    F: FnMut(Option<IdxValue>, &[IdxValue]) -> Result<(), IdxError>,
+{
+    let columns = record_shape.iter().map(|d| *d as usize).product::<usize>() + labeled as usize;
+    let mut values = Vec::with_capacity(columns);
+    for (i, line) in reader.lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let fields: Vec<&str> = line.split(',').map(|f| f.trim()).collect();
+        // Column names are only allowed in the first line
+        if i == 0 && fields[0].parse::<f64>().is_err() {
+            continue;
+        }
+        if fields.len() != columns {
+            return Err(invalid(i + 1, format!("expected {} columns, found {}", columns, fields.len())));
+        }
+        let (label, fields) = if labeled {
+            let label = fields[0].parse().map_err(|_| invalid(i + 1, format!("invalid label '{}'", fields[0])))?;
+            (Some(IdxValue::UnsignedByte(label)), &fields[1..])
+        } else {
+            (None, &fields[..])
+        };
+        values.clear();
+        for field in fields {
+            values.push(parse_value(data_type, field).ok_or_else(|| invalid(i + 1, format!("invalid value '{}'", field)))?);
+        }
+        f(label, &values)?;
+    }
+    Ok(())
+}
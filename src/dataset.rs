@@ -0,0 +1,358 @@
+use std::cell::RefCell;
+use std::io::Read;
+use std::io::Seek;
+use std::io::Write;
+use std::path::Path;
+use std::rc::Rc;
+
+use crate::rng::Rng;
+use crate::IdxConcat;
+use crate::IdxCursor;
+use crate::IdxDataType;
+use crate::IdxError;
+use crate::IdxFile;
+use crate::IdxSlice;
+use crate::IdxSource;
+use crate::IdxValue;
+use crate::StreamingIdxWriter;
+
+/// Single grayscale image with one byte per pixel, stored row by row
+pub struct Image {
+    rows: u32,
+    cols: u32,
+    pixels: Box<[u8]>,
+}
+
+impl Image {
+    /// Pixels have to hold exactly rows * cols bytes
+    pub fn new(rows: u32, cols: u32, pixels: Box<[u8]>) -> Result<Image, IdxError> {
+        let expected = rows as u64 * cols as u64;
+        if pixels.len() as u64 != expected {
+            return Err(IdxError::LengthMismatch{ expected, actual: pixels.len() as u64 });
+        }
+        Ok(Image { rows, cols, pixels })
+    }
+
+    pub fn rows(&self) -> u32 {
+        self.rows
+    }
+
+    pub fn cols(&self) -> u32 {
+        self.cols
+    }
+
+    pub fn pixels(&self) -> &[u8] {
+        &self.pixels
+    }
+
+    /// Pixel at the row and column, panics outside of the image
+    pub fn get(&self, row: u32, col: u32) -> u8 {
+        assert!(row < self.rows && col < self.cols, "pixel ({}, {}) is outside of the {}x{} image", row, col, self.rows, self.cols);
+        self.pixels[row as usize * self.cols as usize + col as usize]
+    }
+
+    pub fn into_pixels(self) -> Box<[u8]> {
+        self.pixels
+    }
+}
+
+/// MNIST style pairing of an N x rows x cols image file with an N label file
+pub struct MnistDataset<R: Read + Seek = IdxSource> {
+    images: IdxCursor<R>,
+    labels: IdxCursor<R>,
+}
+
+impl MnistDataset<IdxSource> {
+    pub fn from_paths<P: AsRef<Path>, Q: AsRef<Path>>(images: P, labels: Q) -> Result<MnistDataset, IdxError> {
+        MnistDataset::new(IdxFile::open(images)?, IdxFile::open(labels)?)
+    }
+}
+
+impl<R: Read + Seek> MnistDataset<R> {
+    /// Checks that both files hold unsigned bytes and the same number of records
+    pub fn new(images: IdxCursor<R>, labels: IdxCursor<R>) -> Result<MnistDataset<R>, IdxError> {
+        for (cursor, n) in &[(&images, 3), (&labels, 1)] {
+            if cursor.data_type != IdxDataType::UnsignedByte {
+                return Err(IdxError::DataTypeMismatch{ expected: IdxDataType::UnsignedByte, found: cursor.data_type });
+            }
+            if cursor.dimensions().len() != *n {
+                return Err(IdxError::DimensionMismatch{ needed: *n, supplied: cursor.dimensions().len() });
+            }
+        }
+        let (left, right) = (images.dimensions()[0], labels.dimensions()[0]);
+        if left != right {
+            return Err(IdxError::RecordCountMismatch{ left, right });
+        }
+        Ok(MnistDataset { images, labels })
+    }
+
+    /// Number of image/label pairs
+    pub fn len(&self) -> usize {
+        self.labels.dimensions()[0] as usize
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn get(&mut self, i: u32) -> Result<(Image, u8), IdxError> {
+        let image = self.images.record_image(i)?;
+        let label = self.labels.get_as::<u8>(&[i])?;
+        Ok((image, label))
+    }
+
+    /// Iterates over all image/label pairs in order
+    pub fn iter(&mut self) -> MnistIter<'_, R> {
+        MnistIter { dataset: self, next: 0 }
+    }
+}
+
+impl<R: Read + Seek> IdxCursor<R> {
+    /// Reads record of an N x rows x cols unsigned byte file as image
+    pub fn record_image(&mut self, i: u32) -> Result<Image, IdxError> {
+        if self.data_type != IdxDataType::UnsignedByte {
+            return Err(IdxError::DataTypeMismatch{ expected: IdxDataType::UnsignedByte, found: self.data_type });
+        }
+        if self.dimensions().len() != 3 {
+            return Err(IdxError::DimensionMismatch{ needed: 3, supplied: self.dimensions().len() });
+        }
+        let record = self.get_record(&[i])?;
+        let (rows, cols) = (record.shape()[0], record.shape()[1]);
+        Ok(Image { rows, cols, pixels: record.into_inner() })
+    }
+}
+
+/// Writes images of equal size as N x rows x cols unsigned byte file
+pub fn write_images<W, I>(writer: W, images: I) -> Result<W, IdxError>
+where
+    W: Write + Seek,
+    I: IntoIterator<Item = Image>,
+{
+    let mut images = images.into_iter().peekable();
+    let (rows, cols) = images.peek().map_or((0, 0), |image| (image.rows, image.cols));
+    let mut writer = StreamingIdxWriter::new(writer, IdxDataType::UnsignedByte, &[rows, cols])?;
+    for image in images {
+        if (image.rows, image.cols) != (rows, cols) {
+            return Err(IdxError::ShapeMismatch{ expected: vec![rows, cols], found: vec![image.rows, image.cols] });
+        }
+        let values: Vec<IdxValue> = image.pixels.iter().map(|p| IdxValue::UnsignedByte(*p)).collect();
+        writer.append_record(&values)?;
+    }
+    writer.finish()
+}
+
+pub struct MnistIter<'a, R: Read + Seek> {
+    dataset: &'a mut MnistDataset<R>,
+    next: u32,
+}
+
+impl<'a, R: Read + Seek> Iterator for MnistIter<'a, R> {
+    type Item = Result<(Image, u8), IdxError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.next as usize >= self.dataset.len() {
+            return None;
+        }
+        let res = self.dataset.get(self.next);
+        self.next += 1;
+        Some(res)
+    }
+}
+
+/// Indexed collection of samples that can be split, shuffled and subset without copying data
+pub trait Dataset {
+    type Item;
+
+    /// Number of samples
+    fn len(&self) -> usize;
+
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Reads sample `i`, fails with `OutOfBounds` if `i >= len()`
+    fn get_item(&mut self, i: usize) -> Result<Self::Item, IdxError>;
+
+    /// View of the samples at the given indices in the given order
+    fn subset(self, indices: Vec<usize>) -> DatasetView<Self> where Self: Sized {
+        DatasetView { source: Rc::new(RefCell::new(self)), indices }
+    }
+
+    /// View of all samples in an order that only depends on the seed
+    fn shuffle(self, seed: u64) -> DatasetView<Self> where Self: Sized {
+        let mut indices: Vec<usize> = (0..self.len()).collect();
+        Rng::new(seed).shuffle(&mut indices);
+        self.subset(indices)
+    }
+
+    /// Views of the first `ratio` of the samples and of the rest, ratio is clamped to `0.0..=1.0`
+    fn split(self, ratio: f64) -> (DatasetView<Self>, DatasetView<Self>) where Self: Sized {
+        let len = self.len();
+        // NaN ends up as an empty first view
+        let at = ((len as f64 * ratio.clamp(0.0, 1.0)).round() as usize).min(len);
+        let source = Rc::new(RefCell::new(self));
+        (DatasetView { source: source.clone(), indices: (0..at).collect() }, DatasetView { source, indices: (at..len).collect() })
+    }
+
+    /// Iterates over all samples in order
+    fn iter_items(&mut self) -> DatasetIter<'_, Self> where Self: Sized {
+        DatasetIter { dataset: self, next: 0 }
+    }
+}
+
+fn out_of_bounds(i: usize, len: usize) -> IdxError {
+    IdxError::OutOfBounds{ dimension: 0, max: len as u32, index: i as u32 }
+}
+
+/// Records along the leading dimension, a file without dimensions is a single record like for `num_records`
+impl<R: Read + Seek> Dataset for IdxCursor<R> {
+    type Item = IdxSlice;
+
+    fn len(&self) -> usize {
+        self.num_records() as usize
+    }
+
+    fn get_item(&mut self, i: usize) -> Result<IdxSlice, IdxError> {
+        if i >= Dataset::len(self) {
+            return Err(out_of_bounds(i, Dataset::len(self)));
+        }
+        let index = [i as u32];
+        // A scalar file is a single record without leading index
+        let leading = self.dimensions().len().min(1);
+        self.get_record(&index[..leading])
+    }
+}
+
+impl<R: Read + Seek> Dataset for IdxConcat<R> {
+    type Item = IdxSlice;
+
+    fn len(&self) -> usize {
+        self.dimensions()[0] as usize
+    }
+
+    fn get_item(&mut self, i: usize) -> Result<IdxSlice, IdxError> {
+        if i >= Dataset::len(self) {
+            return Err(out_of_bounds(i, Dataset::len(self)));
+        }
+        self.get_record(&[i as u32])
+    }
+}
+
+impl<R: Read + Seek> Dataset for MnistDataset<R> {
+    type Item = (Image, u8);
+
+    fn len(&self) -> usize {
+        MnistDataset::len(self)
+    }
+
+    fn get_item(&mut self, i: usize) -> Result<(Image, u8), IdxError> {
+        if i >= MnistDataset::len(self) {
+            return Err(out_of_bounds(i, MnistDataset::len(self)));
+        }
+        self.get(i as u32)
+    }
+}
+
+/// Samples of a shared dataset selected by index, views of the same dataset can be used side by side
+pub struct DatasetView<D: Dataset> {
+    source: Rc<RefCell<D>>,
+    indices: Vec<usize>,
+}
+
+impl<D: Dataset> DatasetView<D> {
+    /// Indices into the underlying dataset, in view order
+    pub fn indices(&self) -> &[usize] {
+        &self.indices
+    }
+}
+
+impl<D: Dataset> Clone for DatasetView<D> {
+    fn clone(&self) -> DatasetView<D> {
+        DatasetView { source: self.source.clone(), indices: self.indices.clone() }
+    }
+}
+
+impl<D: Dataset> Dataset for DatasetView<D> {
+    type Item = D::Item;
+
+    fn len(&self) -> usize {
+        self.indices.len()
+    }
+
+    fn get_item(&mut self, i: usize) -> Result<D::Item, IdxError> {
+        let index = *self.indices.get(i).ok_or_else(|| out_of_bounds(i, self.indices.len()))?;
+        self.source.borrow_mut().get_item(index)
+    }
+}
+
+pub struct DatasetIter<'a, D: Dataset> {
+    dataset: &'a mut D,
+    next: usize,
+}
+
+impl<'a, D: Dataset> Iterator for DatasetIter<'a, D> {
+    type Item = Result<D::Item, IdxError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.next >= self.dataset.len() {
+            return None;
+        }
+        let res = self.dataset.get_item(self.next);
+        self.next += 1;
+        Some(res)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Dataset;
+    use super::Image;
+    use crate::IdxCursor;
+    use crate::IdxError;
+
+    #[test]
+    fn scalar_is_one_item() {
+        // u8 file without dimensions holding the single value 5
+        let mut cursor = IdxCursor::from_bytes(vec![0, 0, 0x08, 0, 5]).unwrap();
+        assert_eq!(Dataset::len(&cursor), cursor.num_records() as usize);
+        let item = cursor.get_item(0).unwrap();
+        assert_eq!((item.as_bytes(), item.shape()), (&[5][..], &[][..]));
+        assert!(matches!(cursor.get_item(1), Err(IdxError::OutOfBounds{ dimension: 0, max: 1, index: 1 })));
+    }
+
+    #[test]
+    fn records_are_items() {
+        let mut cursor = IdxCursor::from_bytes(vec![0, 0, 0x08, 2, 0, 0, 0, 2, 0, 0, 0, 2, 1, 2, 3, 4]).unwrap();
+        assert_eq!(Dataset::len(&cursor), 2);
+        assert_eq!(cursor.get_item(1).unwrap().as_bytes(), &[3, 4]);
+        // A file with zero records has no items
+        let cursor = IdxCursor::from_bytes(vec![0, 0, 0x08, 1, 0, 0, 0, 0]).unwrap();
+        assert_eq!(Dataset::len(&cursor), 0);
+    }
+
+    fn image() -> Image {
+        Image::new(2, 3, vec![0, 1, 2, 10, 11, 12].into_boxed_slice()).unwrap()
+    }
+
+    #[test]
+    fn corners() {
+        let image = image();
+        assert_eq!(image.get(0, 0), 0);
+        assert_eq!(image.get(0, 2), 2);
+        assert_eq!(image.get(1, 0), 10);
+        assert_eq!(image.get(1, 2), 12);
+    }
+
+    #[test]
+    #[should_panic(expected = "outside of the 2x3 image")]
+    fn column_past_the_edge() {
+        // Would be pixel (1, 0) if the column was not checked
+        image().get(0, 3);
+    }
+
+    #[test]
+    #[should_panic(expected = "outside of the 2x3 image")]
+    fn row_past_the_edge() {
+        image().get(2, 0);
+    }
+}
@@ -0,0 +1,195 @@
+#[cfg(feature = "std")]
+use alloc::vec::Vec;
+use core::convert::TryFrom;
+use core::convert::TryInto;
+
+use crate::IdxDataType;
+use crate::IdxError;
+use crate::IdxValue;
+
+/// Primitive type that values of an IDX file can be converted into
+pub trait IdxElement: Sized + Copy + Send {
+    /// Data type which is stored as this primitive
+    const DATA_TYPE: IdxDataType;
+
+    /// Converts value only if it is exactly representable, fails with `CannotCast` otherwise
+    fn from_value(value: IdxValue) -> Result<Self, IdxError>;
+
+    /// Converts value the same way an `as` cast does, saturating and rounding if necessary
+    fn from_value_lossy(value: IdxValue) -> Self;
+
+    /// Reads the primitive from big endian bytes, the slice has to be exactly its size
+    fn from_be_slice(bytes: &[u8]) -> Self;
+}
+
+/// Appends the big endian `$P` elements of `$bytes` to `$out` converted to `T`
+#[cfg(feature = "std")]
+macro_rules! decode_as {
+    ($bytes:expr, $out:expr, $lossy:expr, $P:ty, $variant:ident) => {{
+        let values = $bytes
+            .chunks_exact(core::mem::size_of::<$P>())
+            .map(|b| IdxValue::$variant(<$P>::from_be_bytes(b.try_into().expect("chunk has the size of the element"))));
+        if $lossy {
+            $out.extend(values.map(T::from_value_lossy));
+        } else {
+            for value in values {
+                $out.push(T::from_value(value)?);
+            }
+        }
+    }};
+}
+
+/// Decodes big endian elements of the data type and appends them converted to `T`
+#[cfg(feature = "std")]
+pub(crate) fn decode_into<T: IdxElement>(data_type: IdxDataType, bytes: &[u8], out: &mut Vec<T>, lossy: bool) -> Result<(), IdxError> {
+    let size = data_type.get_size() as usize;
+    // Same type in file and memory only needs the byte order swapped
+    if data_type == T::DATA_TYPE {
+        out.extend(bytes.chunks_exact(size).map(T::from_be_slice));
+        return Ok(());
+    }
+    out.reserve(bytes.len() / size);
+    // Matching the data type once outside of the loops leaves a plain conversion per element for the compiler to vectorize
+    match data_type {
+        IdxDataType::UnsignedByte => decode_as!(bytes, out, lossy, u8, UnsignedByte),
+        IdxDataType::SignedByte   => decode_as!(bytes, out, lossy, i8, SignedByte),
+        IdxDataType::Short        => decode_as!(bytes, out, lossy, i16, Short),
+        IdxDataType::Int          => decode_as!(bytes, out, lossy, i32, Int),
+        IdxDataType::Float        => decode_as!(bytes, out, lossy, f32, Float),
+        IdxDataType::Double       => decode_as!(bytes, out, lossy, f64, Double),
+        #[cfg(feature = "extensions")]
+        IdxDataType::UnsignedShort => decode_as!(bytes, out, lossy, u16, UnsignedShort),
+        #[cfg(feature = "extensions")]
+        IdxDataType::UnsignedInt  => decode_as!(bytes, out, lossy, u32, UnsignedInt),
+        #[cfg(feature = "extensions")]
+        IdxDataType::Long         => decode_as!(bytes, out, lossy, i64, Long),
+        #[cfg(feature = "extensions")]
+        IdxDataType::UnsignedLong => decode_as!(bytes, out, lossy, u64, UnsignedLong),
+    }
+    Ok(())
+}
+
+macro_rules! lossy {
+    ($T:ty, $value:expr) => {
+        match $value {
+            IdxValue::UnsignedByte(v) => v as $T,
+            IdxValue::SignedByte(v)   => v as $T,
+            IdxValue::Short(v)        => v as $T,
+            IdxValue::Int(v)          => v as $T,
+            IdxValue::Float(v)        => v as $T,
+            IdxValue::Double(v)       => v as $T,
+            #[cfg(feature = "extensions")]
+            IdxValue::UnsignedShort(v) => v as $T,
+            #[cfg(feature = "extensions")]
+            IdxValue::UnsignedInt(v)  => v as $T,
+            #[cfg(feature = "extensions")]
+            IdxValue::Long(v)         => v as $T,
+            #[cfg(feature = "extensions")]
+            IdxValue::UnsignedLong(v) => v as $T,
+        }
+    };
+}
+
+macro_rules! int_element {
+    ($T:ty, $variant:ident) => {
+        impl IdxElement for $T {
+            const DATA_TYPE: IdxDataType = IdxDataType::$variant;
+
+            fn from_value(value: IdxValue) -> Result<$T, IdxError> {
+                // Integers are compared exactly, 64 bit values do not all fit into an f64
+                if let Some(i) = value.as_i128() {
+                    return <$T>::try_from(i).map_err(|_| IdxError::CannotCast);
+                }
+                let v = value.as_f64();
+                // MAX + 1 is a power of two and always exact, unlike MAX of the 64 bit types,
+                // inside the range only whole numbers survive the round trip
+                if v >= <$T>::MIN as f64 && v < <$T>::MAX as f64 + 1.0 && v as $T as f64 == v {
+                    Ok(v as $T)
+                } else {
+                    Err(IdxError::CannotCast)
+                }
+            }
+
+            fn from_value_lossy(value: IdxValue) -> $T {
+                lossy!($T, value)
+            }
+
+            fn from_be_slice(bytes: &[u8]) -> $T {
+                <$T>::from_be_bytes(bytes.try_into().expect("slice has the size of the element"))
+            }
+        }
+
+        impl TryFrom<IdxValue> for $T {
+            type Error = IdxError;
+
+            fn try_from(value: IdxValue) -> Result<$T, IdxError> {
+                <$T as IdxElement>::from_value(value)
+            }
+        }
+
+        impl From<$T> for IdxValue {
+            fn from(v: $T) -> IdxValue {
+                IdxValue::$variant(v)
+            }
+        }
+    };
+}
+
+macro_rules! float_element {
+    ($T:ty, $variant:ident) => {
+        impl IdxElement for $T {
+            const DATA_TYPE: IdxDataType = IdxDataType::$variant;
+
+            fn from_value(value: IdxValue) -> Result<$T, IdxError> {
+                if let Some(i) = value.as_i128() {
+                    let v = i as $T;
+                    return if v as i128 == i { Ok(v) } else { Err(IdxError::CannotCast) };
+                }
+                let v = value.as_f64();
+                // NaN never compares equal but is still representable
+                if v as $T as f64 == v || v.is_nan() {
+                    Ok(v as $T)
+                } else {
+                    Err(IdxError::CannotCast)
+                }
+            }
+
+            fn from_value_lossy(value: IdxValue) -> $T {
+                lossy!($T, value)
+            }
+
+            fn from_be_slice(bytes: &[u8]) -> $T {
+                <$T>::from_be_bytes(bytes.try_into().expect("slice has the size of the element"))
+            }
+        }
+
+        impl TryFrom<IdxValue> for $T {
+            type Error = IdxError;
+
+            fn try_from(value: IdxValue) -> Result<$T, IdxError> {
+                <$T as IdxElement>::from_value(value)
+            }
+        }
+
+        impl From<$T> for IdxValue {
+            fn from(v: $T) -> IdxValue {
+                IdxValue::$variant(v)
+            }
+        }
+    };
+}
+
+int_element!(u8, UnsignedByte);
+int_element!(i8, SignedByte);
+int_element!(i16, Short);
+int_element!(i32, Int);
+float_element!(f32, Float);
+float_element!(f64, Double);
+#[cfg(feature = "extensions")]
+int_element!(u16, UnsignedShort);
+#[cfg(feature = "extensions")]
+int_element!(u32, UnsignedInt);
+#[cfg(feature = "extensions")]
+int_element!(i64, Long);
+#[cfg(feature = "extensions")]
+int_element!(u64, UnsignedLong);
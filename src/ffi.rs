@@ -0,0 +1,184 @@
+//! C interface for reading IDX files from C, C++ or Python's ctypes
+//!
+//! Build a shared library with `cargo rustc --lib --release --features ffi --crate-type cdylib`. Every function
+//! returns one of the `IDX_*` codes, results are written through out pointers. Handles come from `idx_open` and
+//! have to be released with `idx_close`.
+//!
+//! ```c
+//! IdxHandle *images;
+//! uint32_t dims[8];
+//! size_t ndims;
+//! if (idx_open("train-images-idx3-ubyte", &images) == IDX_OK) {
+//!     idx_shape(images, dims, 8, &ndims);
+//!     double *record = malloc(dims[1] * dims[2] * sizeof(double));
+//!     idx_read_record(images, 0, record, dims[1] * dims[2]);
+//!     idx_close(images);
+//! }
+//! ```
+
+use std::ffi::CStr;
+use std::os::raw::c_char;
+use std::os::raw::c_int;
+use std::panic;
+use std::slice;
+
+use crate::decode_into;
+use crate::IdxError;
+use crate::IdxFile;
+
+pub const IDX_OK: c_int = 0;
+/// A pointer argument was null or the path is not UTF-8
+pub const IDX_ERR_ARGUMENT: c_int = 1;
+/// The file could not be opened or read
+pub const IDX_ERR_IO: c_int = 2;
+/// The file is no valid IDX file, e.g. wrong magic number, unknown data type or wrong length
+pub const IDX_ERR_FORMAT: c_int = 3;
+/// An index is out of bounds or the number of indices does not match the dimensions
+pub const IDX_ERR_INDEX: c_int = 4;
+/// The output buffer is too small
+pub const IDX_ERR_BUFFER: c_int = 5;
+/// Any other failure, including panics
+pub const IDX_ERR_OTHER: c_int = 6;
+
+/// Open file, only used through pointers on the C side
+pub struct IdxHandle {
+    file: IdxFile,
+}
+
+fn code(e: &IdxError) -> c_int {
+    match e {
+        IdxError::IoError(_) | IdxError::ReadFailed{ .. } => IDX_ERR_IO,
+        IdxError::WrongHeader{ .. }
+        | IdxError::UnknownDataType(_)
+        | IdxError::LengthMismatch{ .. }
+        | IdxError::Overflow
+        | IdxError::FileTooLarge{ .. } => IDX_ERR_FORMAT,
+        IdxError::OutOfBounds{ .. } | IdxError::DimensionMismatch{ .. } => IDX_ERR_INDEX,
+        _ => IDX_ERR_OTHER,
+    }
+}
+
+/// Runs `f` and turns its error or a panic into a code, panics must not unwind into C
+fn guard<F: FnOnce() -> Result<(), c_int> + panic::UnwindSafe>(f: F) -> c_int {
+    match panic::catch_unwind(f) {
+        Ok(Ok(()))    => IDX_OK,
+        Ok(Err(code)) => code,
+        Err(_)        => IDX_ERR_OTHER,
+    }
+}
+
+/// Opens the file at the UTF-8 `path` and stores a new handle in `out`
+///
+/// # Safety
+///
+/// `path` has to be a null terminated string and `out` has to be valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn idx_open(path: *const c_char, out: *mut *mut IdxHandle) -> c_int {
+    guard(|| {
+        if path.is_null() || out.is_null() {
+            return Err(IDX_ERR_ARGUMENT);
+        }
+        let path = CStr::from_ptr(path).to_str().map_err(|_| IDX_ERR_ARGUMENT)?;
+        let file = IdxFile::open(path).map_err(|e| code(&e))?;
+        *out = Box::into_raw(Box::new(IdxHandle { file }));
+        Ok(())
+    })
+}
+
+/// Writes up to `capacity` dimension sizes to `dims` and their number to `ndims`
+///
+/// Fails with `IDX_ERR_BUFFER` if `capacity` is smaller than the number of dimensions, `ndims` is set anyway so the
+/// call can be repeated with a large enough buffer.
+///
+/// # Safety
+///
+/// `handle` has to come from `idx_open`, `dims` has to be valid for `capacity` writes and `ndims` for one.
+#[no_mangle]
+pub unsafe extern "C" fn idx_shape(handle: *const IdxHandle, dims: *mut u32, capacity: usize, ndims: *mut usize) -> c_int {
+    guard(|| {
+        if handle.is_null() || ndims.is_null() || (dims.is_null() && capacity > 0) {
+            return Err(IDX_ERR_ARGUMENT);
+        }
+        let dimensions = (*handle).file.dimensions();
+        *ndims = dimensions.len();
+        if capacity < dimensions.len() {
+            return Err(IDX_ERR_BUFFER);
+        }
+        if !dimensions.is_empty() {
+            slice::from_raw_parts_mut(dims, dimensions.len()).copy_from_slice(dimensions);
+        }
+        Ok(())
+    })
+}
+
+/// Stores the data type code of the magic number in `out`, e.g. 0x08 for unsigned bytes
+///
+/// # Safety
+///
+/// `handle` has to come from `idx_open` and `out` has to be valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn idx_dtype(handle: *const IdxHandle, out: *mut u8) -> c_int {
+    guard(|| {
+        if handle.is_null() || out.is_null() {
+            return Err(IDX_ERR_ARGUMENT);
+        }
+        *out = (*handle).file.dtype().get_code();
+        Ok(())
+    })
+}
+
+/// Reads the element at `n` indices as double, 64 bit extension integers may be rounded
+///
+/// # Safety
+///
+/// `handle` has to come from `idx_open`, `indices` has to be valid for `n` reads and `out` for one write.
+#[no_mangle]
+pub unsafe extern "C" fn idx_get_f64(handle: *mut IdxHandle, indices: *const u32, n: usize, out: *mut f64) -> c_int {
+    guard(|| {
+        if handle.is_null() || out.is_null() || (indices.is_null() && n > 0) {
+            return Err(IDX_ERR_ARGUMENT);
+        }
+        let indices = if n == 0 { &[][..] } else { slice::from_raw_parts(indices, n) };
+        *out = (*handle).file.get(indices).map_err(|e| code(&e))?.as_f64();
+        Ok(())
+    })
+}
+
+/// Reads record `record` along the leading dimension into `buf` as doubles
+///
+/// `len` has to be at least the number of elements per record, the product of all dimensions after the first.
+///
+/// # Safety
+///
+/// `handle` has to come from `idx_open` and `buf` has to be valid for `len` writes.
+#[no_mangle]
+pub unsafe extern "C" fn idx_read_record(handle: *mut IdxHandle, record: u32, buf: *mut f64, len: usize) -> c_int {
+    guard(|| {
+        if handle.is_null() || (buf.is_null() && len > 0) {
+            return Err(IDX_ERR_ARGUMENT);
+        }
+        let file = &mut (*handle).file;
+        if len < file.record_len() {
+            return Err(IDX_ERR_BUFFER);
+        }
+        let slice = file.get_record(&[record]).map_err(|e| code(&e))?;
+        let mut values = Vec::with_capacity(slice.len());
+        decode_into::<f64>(slice.data_type(), slice.as_bytes(), &mut values, true).map_err(|e| code(&e))?;
+        if !values.is_empty() {
+            slice::from_raw_parts_mut(buf, values.len()).copy_from_slice(&values);
+        }
+        Ok(())
+    })
+}
+
+/// Releases the handle, null is ignored
+///
+/// # Safety
+///
+/// `handle` has to come from `idx_open` and must not be used afterwards.
+#[no_mangle]
+pub unsafe extern "C" fn idx_close(handle: *mut IdxHandle) {
+    if !handle.is_null() {
+        drop(Box::from_raw(handle));
+    }
+}
@@ -0,0 +1,77 @@
+use std::convert::TryFrom;
+use std::fs::File;
+use std::io;
+#[cfg(feature = "gzip")]
+use std::io::BufRead;
+use std::io::BufReader;
+use std::io::Read;
+use std::io::Seek;
+use std::io::SeekFrom;
+use std::path::Path;
+
+use crate::IdxCursor;
+use crate::IdxError;
+
+/// Reader behind an `IdxFile`, either the buffered file or its decompressed contents
+pub enum IdxSource {
+    File(BufReader<File>),
+    Memory(io::Cursor<Vec<u8>>),
+}
+
+impl Read for IdxSource {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match self {
+            IdxSource::File(r) => r.read(buf),
+            IdxSource::Memory(r) => r.read(buf),
+        }
+    }
+}
+
+impl Seek for IdxSource {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        match self {
+            IdxSource::File(r) => r.seek(pos),
+            IdxSource::Memory(r) => r.seek(pos),
+        }
+    }
+}
+
+impl IdxSource {
+    /// Opens file with a buffered reader, gzip files are decompressed into memory first
+    ///
+    /// Gzip is detected by the `.gz` extension or by the gzip magic bytes at the start of the file.
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<IdxSource, IdxError> {
+        let path = path.as_ref();
+        #[allow(unused_mut)]
+        let mut reader = BufReader::new(File::open(path)?);
+        #[cfg(feature = "gzip")]
+        {
+            // Peeking at the buffer leaves the reader at the start of the file
+            if path.extension() == Some("gz".as_ref()) || reader.fill_buf()?.starts_with(&[0x1f, 0x8b]) {
+                let mut data = Vec::new();
+                reader.read_to_end(&mut data)?;
+                let data = crate::gzip::decompress(&data)?;
+                return Ok(IdxSource::Memory(io::Cursor::new(data)));
+            }
+        }
+        Ok(IdxSource::File(reader))
+    }
+}
+
+/// Cursor over an IDX file opened from a path
+pub type IdxFile = IdxCursor<IdxSource>;
+
+impl IdxCursor<IdxSource> {
+    /// Opens the file like `IdxSource::open` and reads its header
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<IdxFile, IdxError> {
+        IdxCursor::new(IdxSource::open(path)?)
+    }
+}
+
+impl TryFrom<&Path> for IdxFile {
+    type Error = IdxError;
+
+    fn try_from(path: &Path) -> Result<IdxFile, IdxError> {
+        IdxFile::open(path)
+    }
+}
@@ -0,0 +1,37 @@
+//! Text layout of records for logs and debugging
+
+use alloc::format;
+use alloc::string::String;
+use alloc::string::ToString;
+use alloc::vec::Vec;
+
+use crate::IdxValue;
+
+/// Lays out the values of a record in storage order as a grid of right aligned columns
+///
+/// The last dimension runs along each line and a blank line separates every 2-D block of a record with more than
+/// two dimensions. A record without dimensions is its single value.
+///
+/// ```
+/// use idxrs::format::format_record;
+/// use idxrs::IdxValue;
+///
+/// let values: Vec<IdxValue> = [1u8, 20, 3, 255, 0, 6].iter().map(|v| IdxValue::UnsignedByte(*v)).collect();
+/// assert_eq!(format_record(&values, &[2, 3]), "  1  20   3\n255   0   6\n");
+/// ```
+pub fn format_record(values: &[IdxValue], shape: &[u32]) -> String {
+    let text: Vec<String> = values.iter().map(|v| v.to_string()).collect();
+    let width = text.iter().map(|s| s.len()).max().unwrap_or(0);
+    let columns = shape.last().copied().unwrap_or(1).max(1) as usize;
+    let rows = if shape.len() >= 2 { shape[shape.len() - 2].max(1) as usize } else { 1 };
+    let mut out = String::new();
+    for (i, row) in text.chunks(columns).enumerate() {
+        if i > 0 && i % rows == 0 {
+            out.push('\n');
+        }
+        let line: Vec<String> = row.iter().map(|s| format!("{:>1$}", s, width)).collect();
+        out.push_str(&line.join(" "));
+        out.push('\n');
+    }
+    out
+}
@@ -0,0 +1,115 @@
+use std::io::Read;
+use std::io::Seek;
+use std::io::SeekFrom;
+
+use crate::buffer_len;
+use crate::read_exact_at;
+use crate::IdxCursor;
+use crate::IdxError;
+use crate::IdxShape;
+use crate::IdxSlice;
+use crate::IdxValue;
+
+/// Bytes of one coalesced read together with the requested offsets and positions it covers
+struct Run {
+    first: u64,
+    bytes: Vec<u8>,
+    members: Vec<(u64, usize)>,
+}
+
+impl<R: Read + Seek> IdxCursor<R> {
+    /// Reads many elements at once, adjacent elements are fetched with a single read
+    pub fn get_many(&mut self, indices: &[&[u32]]) -> Result<Vec<IdxValue>, IdxError> {
+        let mut offsets = Vec::with_capacity(indices.len());
+        for (i, index) in indices.iter().enumerate() {
+            offsets.push((self.shape.flat_offset(index)?, i));
+        }
+        let size = self.element_size as usize;
+        let mut values = Vec::with_capacity(indices.len());
+        for run in self.read_runs(offsets, 1)? {
+            for (offset, i) in run.members {
+                let pos = (offset - run.first) as usize * size;
+                values.push((i, IdxValue::decode(self.data_type, &run.bytes[pos..pos + size])?));
+            }
+        }
+        // Back to the order the indices were given in
+        values.sort_by_key(|(i, _)| *i);
+        Ok(values.into_iter().map(|(_, value)| value).collect())
+    }
+
+    /// Reads many records selected by their leading index, consecutive records are fetched with a single read
+    ///
+    /// Fails with `DimensionMismatch` for a file without dimensions, which has no leading index.
+    pub fn get_records(&mut self, records: &[u32]) -> Result<Vec<IdxSlice>, IdxError> {
+        if self.shape.dimensions().is_empty() {
+            return Err(IdxError::DimensionMismatch{ needed: 1, supplied: 0 });
+        }
+        let mut offsets = Vec::with_capacity(records.len());
+        for (i, record) in records.iter().enumerate() {
+            offsets.push((self.shape.record_offset(&[*record])?, i));
+        }
+        let record_len = self.shape.record_len(1);
+        let record_bytes = buffer_len(record_len, self.element_size)?;
+        let trailing = IdxShape::new(self.shape.dimensions()[1..].to_vec());
+        let mut slices = Vec::with_capacity(records.len());
+        for run in self.read_runs(offsets, record_len)? {
+            for (offset, i) in run.members {
+                let pos = (offset - run.first) as usize * self.element_size as usize;
+                let data = run.bytes[pos..pos + record_bytes].to_vec().into_boxed_slice();
+                slices.push((i, IdxSlice::new(data, self.data_type, trailing.clone())));
+            }
+        }
+        slices.sort_by_key(|(i, _)| *i);
+        Ok(slices.into_iter().map(|(_, slice)| slice).collect())
+    }
+
+    /// Sorts blocks of `len` elements by offset and reads each run of adjacent or repeated blocks at once
+    fn read_runs(&mut self, mut offsets: Vec<(u64, usize)>, len: u64) -> Result<Vec<Run>, IdxError> {
+        offsets.sort_unstable();
+        let size = self.element_size;
+        let mut runs = Vec::new();
+        let mut i = 0;
+        while i < offsets.len() {
+            let first = offsets[i].0;
+            let mut end = first + len;
+            let mut j = i + 1;
+            while j < offsets.len() && offsets[j].0 <= end {
+                end = end.max(offsets[j].0 + len);
+                j += 1;
+            }
+            let pos = self.header_len + first * size;
+            self.reader.seek(SeekFrom::Start(pos))?;
+            let mut bytes = vec![0; buffer_len(end - first, size)?];
+            read_exact_at(&mut self.reader, pos, &mut bytes)?;
+            self.endianness.swap_be(&mut bytes, size as usize);
+            runs.push(Run { first, bytes, members: offsets[i..j].to_vec() });
+            i = j;
+        }
+        Ok(runs)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::IdxCursor;
+    use crate::IdxError;
+
+    #[test]
+    fn records_of_scalar() {
+        // u8 file without dimensions holding the single value 5
+        let mut cursor = IdxCursor::from_bytes(vec![0, 0, 0x08, 0, 5]).unwrap();
+        assert!(matches!(cursor.get_records(&[]), Err(IdxError::DimensionMismatch{ needed: 1, supplied: 0 })));
+        assert!(matches!(cursor.get_records(&[0]), Err(IdxError::DimensionMismatch{ needed: 1, supplied: 0 })));
+    }
+
+    #[test]
+    fn records_in_given_order() {
+        let mut data = vec![0, 0, 0x08, 2, 0, 0, 0, 4, 0, 0, 0, 2];
+        data.extend_from_slice(&[0, 1, 10, 11, 20, 21, 30, 31]);
+        let mut cursor = IdxCursor::from_bytes(data).unwrap();
+        let records = cursor.get_records(&[3, 1, 2, 1]).unwrap();
+        let bytes: Vec<&[u8]> = records.iter().map(|r| r.as_bytes()).collect();
+        assert_eq!(bytes, vec![&[30, 31][..], &[10, 11], &[20, 21], &[10, 11]]);
+        assert!(cursor.get_records(&[]).unwrap().is_empty());
+    }
+}
@@ -0,0 +1,373 @@
+use std::io;
+
+use crate::checksum::crc32;
+
+// Tables from RFC 1951 section 3.2.5
+const LENGTH_BASE: [u16; 29] = [
+    3, 4, 5, 6, 7, 8, 9, 10, 11, 13, 15, 17, 19, 23, 27, 31,
+    35, 43, 51, 59, 67, 83, 99, 115, 131, 163, 195, 227, 258,
+];
+const LENGTH_EXTRA: [u8; 29] = [
+    0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2,
+    3, 3, 3, 3, 4, 4, 4, 4, 5, 5, 5, 5, 0,
+];
+const DIST_BASE: [u16; 30] = [
+    1, 2, 3, 4, 5, 7, 9, 13, 17, 25, 33, 49, 65, 97, 129, 193,
+    257, 385, 513, 769, 1025, 1537, 2049, 3073, 4097, 6145, 8193, 12289, 16385, 24577,
+];
+const DIST_EXTRA: [u8; 30] = [
+    0, 0, 0, 0, 1, 1, 2, 2, 3, 3, 4, 4, 5, 5, 6, 6,
+    7, 7, 8, 8, 9, 9, 10, 10, 11, 11, 12, 12, 13, 13,
+];
+// Order in which code length code lengths are stored in dynamic blocks
+const CLEN_ORDER: [usize; 19] = [16, 17, 18, 0, 8, 7, 9, 6, 10, 5, 11, 4, 12, 3, 13, 2, 14, 1, 15];
+
+fn invalid(msg: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+/// Reads bits LSB first, one byte at a time so the byte position stays exact
+struct BitReader<'a> {
+    data: &'a [u8],
+    pos: usize,
+    buf: u32,
+    cnt: u32,
+}
+
+impl<'a> BitReader<'a> {
+    fn bits(&mut self, need: u32) -> io::Result<u32> {
+        while self.cnt < need {
+            let byte = *self.data.get(self.pos).ok_or_else(|| invalid("unexpected end of deflate stream"))?;
+            self.pos += 1;
+            self.buf |= (byte as u32) << self.cnt;
+            self.cnt += 8;
+        }
+        let val = self.buf & ((1u32 << need) - 1);
+        self.buf >>= need;
+        self.cnt -= need;
+        Ok(val)
+    }
+
+    /// Drops the remaining bits of the current byte
+    fn align(&mut self) {
+        self.buf = 0;
+        self.cnt = 0;
+    }
+}
+
+/// Canonical huffman code stored as number of codes per length and symbols ordered by code
+struct Huffman {
+    counts: [u16; 16],
+    symbols: Vec<u16>,
+}
+
+impl Huffman {
+    fn new(lengths: &[u8]) -> Huffman {
+        let mut counts = [0u16; 16];
+        for len in lengths {
+            counts[*len as usize] += 1;
+        }
+        counts[0] = 0;
+        let mut offsets = [0u16; 16];
+        for len in 1..15 {
+            offsets[len + 1] = offsets[len] + counts[len];
+        }
+        let mut symbols = vec![0; lengths.len()];
+        for (symbol, len) in lengths.iter().enumerate() {
+            if *len != 0 {
+                symbols[offsets[*len as usize] as usize] = symbol as u16;
+                offsets[*len as usize] += 1;
+            }
+        }
+        Huffman { counts, symbols }
+    }
+
+    fn decode(&self, br: &mut BitReader) -> io::Result<u16> {
+        let mut code: i32 = 0;
+        let mut first: i32 = 0;
+        let mut index: i32 = 0;
+        for len in 1..16 {
+            code |= br.bits(1)? as i32;
+            let count = self.counts[len] as i32;
+            if code - count < first {
+                return Ok(self.symbols[(index + (code - first)) as usize]);
+            }
+            index += count;
+            first += count;
+            first <<= 1;
+            code <<= 1;
+        }
+        Err(invalid("invalid huffman code"))
+    }
+}
+
+fn inflate_codes(br: &mut BitReader, out: &mut Vec<u8>, lencode: &Huffman, distcode: &Huffman) -> io::Result<()> {
+    loop {
+        let symbol = lencode.decode(br)? as usize;
+        if symbol < 256 {
+            out.push(symbol as u8);
+        } else if symbol == 256 {
+            return Ok(());
+        } else {
+            let symbol = symbol - 257;
+            if symbol >= LENGTH_BASE.len() {
+                return Err(invalid("invalid length code"));
+            }
+            let len = LENGTH_BASE[symbol] as usize + br.bits(LENGTH_EXTRA[symbol] as u32)? as usize;
+            let symbol = distcode.decode(br)? as usize;
+            if symbol >= DIST_BASE.len() {
+                return Err(invalid("invalid distance code"));
+            }
+            let dist = DIST_BASE[symbol] as usize + br.bits(DIST_EXTRA[symbol] as u32)? as usize;
+            if dist > out.len() {
+                return Err(invalid("distance too far back"));
+            }
+            // Copy byte by byte, source and destination may overlap
+            let start = out.len() - dist;
+            for i in 0..len {
+                let byte = out[start + i];
+                out.push(byte);
+            }
+        }
+    }
+}
+
+fn inflate_stored(br: &mut BitReader, out: &mut Vec<u8>) -> io::Result<()> {
+    br.align();
+    let header = br.data.get(br.pos..br.pos + 4).ok_or_else(|| invalid("unexpected end of deflate stream"))?;
+    let len = u16::from_le_bytes([header[0], header[1]]);
+    let nlen = u16::from_le_bytes([header[2], header[3]]);
+    if len != !nlen {
+        return Err(invalid("stored block length mismatch"));
+    }
+    br.pos += 4;
+    let block = br.data.get(br.pos..br.pos + len as usize).ok_or_else(|| invalid("unexpected end of deflate stream"))?;
+    out.extend_from_slice(block);
+    br.pos += len as usize;
+    Ok(())
+}
+
+fn fixed_codes() -> (Huffman, Huffman) {
+    let mut lengths = [0u8; 288];
+    for (symbol, len) in lengths.iter_mut().enumerate() {
+        *len = match symbol {
+            0..=143 => 8,
+            144..=255 => 9,
+            256..=279 => 7,
+            _ => 8,
+        };
+    }
+    (Huffman::new(&lengths), Huffman::new(&[5; 30]))
+}
+
+fn dynamic_codes(br: &mut BitReader) -> io::Result<(Huffman, Huffman)> {
+    let nlen = br.bits(5)? as usize + 257;
+    let ndist = br.bits(5)? as usize + 1;
+    let ncode = br.bits(4)? as usize + 4;
+    if nlen > 286 || ndist > 30 {
+        return Err(invalid("bad dynamic block counts"));
+    }
+    let mut lengths = [0u8; 320];
+    for &i in CLEN_ORDER.iter().take(ncode) {
+        lengths[i] = br.bits(3)? as u8;
+    }
+    let lencode = Huffman::new(&lengths[..19]);
+
+    // Literal/length and distance code lengths share one run length encoded sequence
+    let mut index = 0;
+    while index < nlen + ndist {
+        let symbol = lencode.decode(br)?;
+        let (len, repeat) = match symbol {
+            0..=15 => (symbol as u8, 1),
+            16 => {
+                if index == 0 {
+                    return Err(invalid("repeat without previous length"));
+                }
+                (lengths[index - 1], 3 + br.bits(2)? as usize)
+            }
+            17 => (0, 3 + br.bits(3)? as usize),
+            _ => (0, 11 + br.bits(7)? as usize),
+        };
+        if index + repeat > nlen + ndist {
+            return Err(invalid("too many code lengths"));
+        }
+        for l in &mut lengths[index..index + repeat] {
+            *l = len;
+        }
+        index += repeat;
+    }
+    if lengths[256] == 0 {
+        return Err(invalid("missing end of block code"));
+    }
+    Ok((Huffman::new(&lengths[..nlen]), Huffman::new(&lengths[nlen..nlen + ndist])))
+}
+
+/// Inflates one deflate stream, returns the number of input bytes consumed
+fn inflate(data: &[u8], out: &mut Vec<u8>) -> io::Result<usize> {
+    let mut br = BitReader { data, pos: 0, buf: 0, cnt: 0 };
+    loop {
+        let last = br.bits(1)? == 1;
+        match br.bits(2)? {
+            0 => inflate_stored(&mut br, out)?,
+            1 => {
+                let (lencode, distcode) = fixed_codes();
+                inflate_codes(&mut br, out, &lencode, &distcode)?;
+            }
+            2 => {
+                let (lencode, distcode) = dynamic_codes(&mut br)?;
+                inflate_codes(&mut br, out, &lencode, &distcode)?;
+            }
+            _ => return Err(invalid("invalid block type")),
+        }
+        if last {
+            return Ok(br.pos);
+        }
+    }
+}
+
+/// Decompresses all gzip members of the data
+pub(crate) fn decompress(mut data: &[u8]) -> io::Result<Vec<u8>> {
+    let mut out = Vec::new();
+    while !data.is_empty() {
+        if data.len() < 10 || data[0] != 0x1f || data[1] != 0x8b || data[2] != 8 {
+            return Err(invalid("not a gzip stream"));
+        }
+        let flags = data[3];
+        let mut pos = 10;
+        // Skip optional extra field, file name, comment and header crc
+        if flags & 0x04 != 0 {
+            let xlen = data.get(pos..pos + 2).ok_or_else(|| invalid("truncated gzip header"))?;
+            pos += 2 + u16::from_le_bytes([xlen[0], xlen[1]]) as usize;
+        }
+        for flag in &[0x08, 0x10] {
+            if flags & flag != 0 {
+                let end = data.iter().skip(pos).position(|b| *b == 0).ok_or_else(|| invalid("truncated gzip header"))?;
+                pos += end + 1;
+            }
+        }
+        if flags & 0x02 != 0 {
+            pos += 2;
+        }
+        let member = data.get(pos..).ok_or_else(|| invalid("truncated gzip header"))?;
+
+        let start = out.len();
+        let consumed = inflate(member, &mut out)?;
+        let trailer = member.get(consumed..consumed + 8).ok_or_else(|| invalid("truncated gzip trailer"))?;
+        let crc = u32::from_le_bytes([trailer[0], trailer[1], trailer[2], trailer[3]]);
+        let size = u32::from_le_bytes([trailer[4], trailer[5], trailer[6], trailer[7]]);
+        if crc != crc32(&out[start..]) || size != (out.len() - start) as u32 {
+            return Err(invalid("gzip checksum mismatch"));
+        }
+        data = &member[consumed + 8..];
+    }
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::decompress;
+
+    // Produced by zlib with mtime 0, block types are noted per vector
+    const EMPTY: &str = "1f8b080000000000000303000000000000000000";
+    // Stored block of "IDX stored"
+    const STORED: &str = "1f8b0800000000000403010a00f5ff4944582073746f726564509fa4ac0a000000";
+    // Fixed huffman block of "abcabcabcabc"
+    const FIXED: &str = "1f8b08000000000002034b4c4a4e842100342a6e5a0c000000";
+    // Dynamic huffman block of `dynamic_text()`
+    const DYNAMIC: &str = concat!(
+        "1f8b080000000000020365d2390e84401043d17c4ed147a06cd6e3cc42863452df3f20a00a829ffee8c972dfbffffe6b433bde9ffd",
+        "68c3ab5f2132440565500567708531c35861ca30559833cc15960c4b8535c35a61cbb0dd3052610d6003da0037e00d8003e20039",
+        "6016cce2be300b66c12c9805b36016cc82d9301b66f314301b66c36c980db361f6633e017e0d1113c6020000",
+    );
+
+    fn hex(s: &str) -> Vec<u8> {
+        (0..s.len()).step_by(2).map(|i| u8::from_str_radix(&s[i..i + 2], 16).unwrap()).collect()
+    }
+
+    fn dynamic_text() -> Vec<u8> {
+        (0..40).flat_map(|i| format!("record {} label {}\n", i, i % 10).into_bytes()).collect()
+    }
+
+    #[test]
+    fn empty_input() {
+        assert_eq!(decompress(&[]).unwrap(), b"");
+        assert_eq!(decompress(&hex(EMPTY)).unwrap(), b"");
+    }
+
+    #[test]
+    fn stored_block() {
+        assert_eq!(decompress(&hex(STORED)).unwrap(), b"IDX stored");
+    }
+
+    #[test]
+    fn fixed_block() {
+        assert_eq!(decompress(&hex(FIXED)).unwrap(), b"abcabcabcabc");
+    }
+
+    #[test]
+    fn dynamic_block() {
+        assert_eq!(decompress(&hex(DYNAMIC)).unwrap(), dynamic_text());
+    }
+
+    #[test]
+    fn concatenated_members() {
+        let mut data = hex(FIXED);
+        data.extend(hex(STORED));
+        data.extend(hex(EMPTY));
+        assert_eq!(decompress(&data).unwrap(), b"abcabcabcabcIDX stored");
+    }
+
+    #[test]
+    fn file_name_is_skipped() {
+        let mut data = hex(STORED);
+        data[3] = 0x08;
+        data.splice(10..10, b"a.idx\0".iter().copied());
+        assert_eq!(decompress(&data).unwrap(), b"IDX stored");
+    }
+
+    #[test]
+    fn truncated_input_fails() {
+        for vector in &[EMPTY, STORED, FIXED, DYNAMIC] {
+            let data = hex(vector);
+            for end in 1..data.len() {
+                assert!(decompress(&data[..end]).is_err(), "prefix of {} bytes was accepted", end);
+            }
+        }
+    }
+
+    #[test]
+    fn corrupt_input_does_not_panic() {
+        let data = hex(DYNAMIC);
+        for i in 10..data.len() - 8 {
+            for bit in 0..8 {
+                let mut corrupt = data.clone();
+                corrupt[i] ^= 1 << bit;
+                // Most flips are caught by the crc, the point is that none of them panics
+                let _ = decompress(&corrupt);
+            }
+        }
+    }
+
+    #[test]
+    fn bad_crc_fails() {
+        let mut data = hex(DYNAMIC);
+        let crc = data.len() - 8;
+        data[crc] ^= 1;
+        assert!(decompress(&data).is_err());
+    }
+
+    #[test]
+    fn bad_isize_fails() {
+        let mut data = hex(FIXED);
+        let size = data.len() - 4;
+        data[size] += 1;
+        assert!(decompress(&data).is_err());
+    }
+
+    #[test]
+    fn trailing_garbage_fails() {
+        let mut data = hex(STORED);
+        data.extend_from_slice(b"IDX");
+        assert!(decompress(&data).is_err());
+    }
+}
@@ -0,0 +1,87 @@
+use alloc::vec::Vec;
+
+use crate::header_len;
+use crate::IdxDataType;
+use crate::IdxError;
+
+/// Metadata stored in and derived from the header of an IDX file
+#[derive(Clone, Debug, PartialEq)]
+pub struct IdxHeader {
+    pub data_type: IdxDataType,
+    pub dimensions: Vec<u32>,
+    pub element_count: u64,
+    pub payload_bytes: u64,
+}
+
+impl IdxHeader {
+    /// Panics if the size of the file in bytes does not fit into 64 bits, see `try_new`
+    pub fn new(data_type: IdxDataType, dimensions: &[u32]) -> IdxHeader {
+        IdxHeader::try_new(data_type, dimensions).expect("file size fits into 64 bits")
+    }
+
+    /// Like `new`, but fails with `Overflow` if the number of elements, the payload or the whole file in bytes does not
+    /// fit into 64 bits
+    pub fn try_new(data_type: IdxDataType, dimensions: &[u32]) -> Result<IdxHeader, IdxError> {
+        let element_count = dimensions.iter().try_fold(1u64, |n, d| n.checked_mul(*d as u64)).ok_or(IdxError::Overflow)?;
+        let payload_bytes = element_count.checked_mul(data_type.get_size() as u64).ok_or(IdxError::Overflow)?;
+        // `file_bytes` adds the header, which has to fit as well
+        payload_bytes.checked_add(header_len(dimensions.len())).ok_or(IdxError::Overflow)?;
+        Ok(IdxHeader {
+            data_type,
+            dimensions: dimensions.to_vec(),
+            element_count,
+            payload_bytes,
+        })
+    }
+
+    /// Size of the leading dimension, a file without dimensions holds a single record
+    pub fn num_records(&self) -> u32 {
+        self.dimensions.first().copied().unwrap_or(1)
+    }
+
+    /// Dimensions of each record, i.e. all dimensions after the leading one
+    pub fn record_shape(&self) -> &[u32] {
+        self.dimensions.get(1..).unwrap_or(&[])
+    }
+
+    /// Size of magic number and dimension sizes in bytes
+    pub fn header_bytes(&self) -> u64 {
+        header_len(self.dimensions.len())
+    }
+
+    /// Expected size of the whole file in bytes
+    pub fn file_bytes(&self) -> u64 {
+        self.header_bytes() + self.payload_bytes
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::IdxHeader;
+    use crate::IdxDataType;
+    use crate::IdxError;
+
+    #[test]
+    fn sizes_beyond_u32() {
+        let header = IdxHeader::try_new(IdxDataType::Double, &[65536, 65536, 2]).unwrap();
+        assert_eq!(header.element_count, 1 << 33);
+        assert_eq!(header.payload_bytes, 1 << 36);
+        assert_eq!(header.file_bytes(), (1 << 36) + 16);
+    }
+
+    #[test]
+    fn sizes_beyond_u64() {
+        // Elements, payload bytes and the header added to the payload each overflow
+        assert!(matches!(IdxHeader::try_new(IdxDataType::UnsignedByte, &[u32::MAX; 3]), Err(IdxError::Overflow)));
+        assert!(matches!(IdxHeader::try_new(IdxDataType::Double, &[u32::MAX, u32::MAX, 1]), Err(IdxError::Overflow)));
+        // u64::MAX bytes of payload, there is no room left for the header
+        assert!(matches!(IdxHeader::try_new(IdxDataType::UnsignedByte, &[u32::MAX, 641, 6_700_417]), Err(IdxError::Overflow)));
+        assert!(IdxHeader::try_new(IdxDataType::UnsignedByte, &[u32::MAX, u32::MAX, 1]).is_ok());
+    }
+
+    #[test]
+    #[should_panic(expected = "file size fits into 64 bits")]
+    fn new_panics_on_overflow() {
+        IdxHeader::new(IdxDataType::Int, &[u32::MAX; 3]);
+    }
+}
@@ -0,0 +1,100 @@
+use std::convert::TryFrom;
+use std::io;
+use std::io::Read;
+use std::io::Seek;
+use std::io::SeekFrom;
+
+use crate::header_len;
+use crate::shape::advance;
+use crate::Endianness;
+use crate::IdxDataType;
+use crate::IdxError;
+use crate::IdxValue;
+
+/// Streams all elements of an IDX file in storage order together with their indices
+pub struct IdxIterator<R: Read + Seek> {
+    reader: R,
+    data_type: IdxDataType,
+    endianness: Endianness,
+    dimensions: Vec<u32>,
+    index: Vec<u32>,
+    remaining: u64,
+    // Payload start, seeked to lazily on the first call to next
+    start: Option<u64>,
+    buffer: Vec<u8>,
+    pos: usize,
+    len: usize,
+}
+
+impl<R: Read + Seek> IdxIterator<R> {
+    /// Reads ahead `buffer_size` bytes at a time, at least one element
+    pub(crate) fn new(
+        reader: R, data_type: IdxDataType, dimensions: Vec<u32>, endianness: Endianness, buffer_size: usize
+    ) -> IdxIterator<R> {
+        let start = header_len(dimensions.len());
+        let remaining = dimensions.iter().map(|d| *d as u64).product();
+        IdxIterator {
+            reader,
+            data_type,
+            endianness,
+            index: vec![0; dimensions.len()],
+            dimensions,
+            remaining,
+            start: Some(start),
+            buffer: vec![0; buffer_size.max(data_type.get_size() as usize)],
+            pos: 0,
+            len: 0,
+        }
+    }
+
+    fn read_next(&mut self) -> Result<(Vec<u32>, IdxValue), IdxError> {
+        if let Some(start) = self.start.take() {
+            self.reader.seek(SeekFrom::Start(start))?;
+        }
+        let size = self.data_type.get_size() as usize;
+        // Refill buffer once fewer bytes than one element are left
+        if self.len - self.pos < size {
+            self.buffer.copy_within(self.pos..self.len, 0);
+            self.len -= self.pos;
+            self.pos = 0;
+            while self.len < size {
+                let n = self.reader.read(&mut self.buffer[self.len..])?;
+                if n == 0 {
+                    // Offset of the element that could not be read completely
+                    let total: u64 = self.dimensions.iter().map(|d| *d as u64).product();
+                    let offset = header_len(self.dimensions.len()) + (total - self.remaining) * size as u64;
+                    let source = io::ErrorKind::UnexpectedEof.into();
+                    return Err(IdxError::ReadFailed{ offset, len: size as u64, source });
+                }
+                self.len += n;
+            }
+        }
+        let element = &mut self.buffer[self.pos..self.pos + size];
+        self.endianness.swap_be(element, size);
+        let value = IdxValue::decode(self.data_type, element)?;
+        self.pos += size;
+
+        let index = self.index.clone();
+        advance(&mut self.index, &self.dimensions);
+        Ok((index, value))
+    }
+}
+
+impl<R: Read + Seek> Iterator for IdxIterator<R> {
+    type Item = Result<(Vec<u32>, IdxValue), IdxError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None;
+        }
+        let res = self.read_next();
+        // Stop after the first error, the stream position is unknown from here on
+        self.remaining = if res.is_ok() { self.remaining - 1 } else { 0 };
+        Some(res)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        // An error ends iteration early, so only the upper bound is known
+        (0, usize::try_from(self.remaining).ok())
+    }
+}
@@ -0,0 +1,57 @@
+//! Export of IDX files as JSON Lines with one object per record, meant for small files and tools like jq
+
+use std::io::Read;
+use std::io::Seek;
+use std::io::Write;
+
+use crate::IdxCursor;
+use crate::IdxError;
+use crate::IdxValue;
+
+/// JSON has no NaN or infinity, those values are written as null
+fn json_value(value: IdxValue) -> String {
+    if value.as_f64().is_finite() { value.to_string() } else { "null".into() }
+}
+
+/// Values of one record as a JSON array, the leading dimension is the record and a scalar file is a single record
+fn record_array<R: Read + Seek>(cursor: &mut IdxCursor<R>, i: u32) -> Result<String, IdxError> {
+    let index = [i];
+    let record = cursor.get_record(&index[..cursor.dimensions().len().min(1)])?;
+    let size = record.data_type().get_size() as usize;
+    let values = record.as_bytes()
+        .chunks_exact(size)
+        .map(|b| IdxValue::decode(record.data_type(), b).map(json_value))
+        .collect::<Result<Vec<String>, IdxError>>()?;
+    Ok(format!("[{}]", values.join(",")))
+}
+
+/// Writes one `{"index":0,"data":[...]}` line per record with the values in storage order
+pub fn write_jsonl<R: Read + Seek, W: Write>(cursor: &mut IdxCursor<R>, mut writer: W) -> Result<W, IdxError> {
+    for i in 0..cursor.num_records() {
+        writeln!(writer, "{{\"index\":{},\"data\":{}}}", i, record_array(cursor, i)?)?;
+    }
+    writer.flush()?;
+    Ok(writer)
+}
+
+/// Like `write_jsonl`, but adds the value of a label file with one label per record as `"label"`
+pub fn write_labeled_jsonl<R, L, W>(cursor: &mut IdxCursor<R>, labels: &mut IdxCursor<L>, mut writer: W) -> Result<W, IdxError>
+where
+    R: Read + Seek,
+    L: Read + Seek,
+    W: Write,
+{
+    if labels.dimensions().len() != 1 {
+        return Err(IdxError::DimensionMismatch{ needed: 1, supplied: labels.dimensions().len() });
+    }
+    let (left, right) = (cursor.num_records(), labels.dimensions()[0]);
+    if left != right {
+        return Err(IdxError::RecordCountMismatch{ left, right });
+    }
+    for i in 0..left {
+        let label = json_value(labels.get(&[i])?);
+        writeln!(writer, "{{\"index\":{},\"label\":{},\"data\":{}}}", i, label, record_array(cursor, i)?)?;
+    }
+    writer.flush()?;
+    Ok(writer)
+}
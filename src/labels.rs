@@ -0,0 +1,292 @@
+//! Helpers for label files with one class index per record
+
+use std::collections::BTreeMap;
+use std::convert::TryFrom;
+use std::io::Read;
+use std::io::Seek;
+use std::io::Write;
+use std::marker::PhantomData;
+
+use crate::rng::Rng;
+use crate::IdxCursor;
+use crate::IdxElement;
+use crate::IdxError;
+use crate::IdxIterator;
+use crate::IdxValue;
+use crate::StreamingIdxWriter;
+
+/// Iterator over one-hot vectors of a label file, created by `one_hot_iter`
+pub struct OneHotIter<'a, R: Read + Seek, T: IdxElement> {
+    values: IdxIterator<&'a mut R>,
+    num_classes: u32,
+    index: u32,
+    failed: bool,
+    element: PhantomData<T>,
+}
+
+/// Number of records per label, the file has to be one dimensional
+pub fn class_counts<R: Read + Seek>(cursor: &mut IdxCursor<R>) -> Result<BTreeMap<i64, u64>, IdxError> {
+    if cursor.dimensions().len() != 1 {
+        return Err(IdxError::DimensionMismatch{ needed: 1, supplied: cursor.dimensions().len() });
+    }
+    let mut counts = BTreeMap::new();
+    for res in cursor.iter() {
+        let (_, value) = res?;
+        *counts.entry(label(value)?).or_insert(0) += 1;
+    }
+    Ok(counts)
+}
+
+/// Writes a label file with every label replaced by its entry in `mapping`, e.g. `{0: 1, 1: 0}` swaps two classes and
+/// `{3: 5, 5: 5}` merges class 3 into class 5
+///
+/// Fails with `UnmappedLabel` before anything is written if a label of the file has no entry, and with
+/// `ValueOutOfRange` if a new label does not fit into the data type of the file.
+///
+/// ```
+/// use std::collections::BTreeMap;
+/// use std::io;
+/// use idxrs::{labels, IdxCursor, IdxError, IdxWriter};
+///
+/// let data = IdxWriter::write_array(io::Cursor::new(Vec::new()), &[4], &[0u8, 1, 2, 1])?.into_inner();
+/// let mut cursor = IdxCursor::from_bytes(data)?;
+/// let mapping: BTreeMap<i64, i64> = vec![(0, 1), (1, 0), (2, 0)].into_iter().collect();
+/// let written = labels::remap(&mut cursor, &mapping, io::Cursor::new(Vec::new()))?.into_inner();
+/// assert_eq!(IdxCursor::from_bytes(written)?.to_vec::<u8>()?.0, vec![1, 0, 0, 0]);
+///
+/// let partial: BTreeMap<i64, i64> = vec![(0, 1), (1, 0)].into_iter().collect();
+/// let res = labels::remap(&mut cursor, &partial, io::Cursor::new(Vec::new()));
+/// assert!(matches!(res, Err(IdxError::UnmappedLabel{ index: 2, label: 2 })));
+/// # Ok::<(), IdxError>(())
+/// ```
+pub fn remap<R: Read + Seek, W: Write + Seek>(cursor: &mut IdxCursor<R>, mapping: &BTreeMap<i64, i64>, writer: W) -> Result<W, IdxError> {
+    if cursor.dimensions().len() != 1 {
+        return Err(IdxError::DimensionMismatch{ needed: 1, supplied: cursor.dimensions().len() });
+    }
+    // The whole file is checked first, so a missing entry does not leave a partly written file
+    for (index, res) in cursor.iter().enumerate() {
+        let label = label(res?.1)?;
+        if !mapping.contains_key(&label) {
+            return Err(IdxError::UnmappedLabel{ index: index as u32, label });
+        }
+    }
+    let mut out = StreamingIdxWriter::new(writer, cursor.dtype(), &[])?;
+    for res in cursor.iter() {
+        let new = mapping[&label(res?.1)?];
+        #[cfg(feature = "extensions")]
+        let value = IdxValue::Long(new);
+        // Without the 64 bit types only a double file can take labels beyond i32, exact for them up to 2^53
+        #[cfg(not(feature = "extensions"))]
+        let value = i32::try_from(new).map_or(IdxValue::Double(new as f64), IdxValue::Int);
+        out.append_record(&[value])?;
+    }
+    out.finish()
+}
+
+/// Records of the train and val set of `split_stratified`, each in random order with a seed and in file order
+/// without
+#[derive(Clone, Debug, PartialEq)]
+pub struct StratifiedSplit {
+    pub train: Vec<u32>,
+    pub val: Vec<u32>,
+}
+
+impl StratifiedSplit {
+    /// Copies the train and val records of `cursor` into two files, called once for the images and once for the labels
+    pub fn write<R: Read + Seek, W: Write + Seek>(&self, cursor: &mut IdxCursor<R>, train: W, val: W) -> Result<(W, W), IdxError> {
+        let train = write_records(cursor, self.train.iter().copied(), train)?;
+        Ok((train, write_records(cursor, self.val.iter().copied(), val)?))
+    }
+}
+
+/// Copies the records in the given order into a new file with the same data type and record shape
+pub fn write_records<R, W, I>(cursor: &mut IdxCursor<R>, records: I, out: W) -> Result<W, IdxError>
+where
+    R: Read + Seek,
+    W: Write + Seek,
+    I: IntoIterator<Item = u32>,
+{
+    let header = cursor.header();
+    let mut writer = StreamingIdxWriter::new(out, header.data_type, header.record_shape())?;
+    for record in records {
+        writer.append_record_bytes(cursor.get_record(&[record])?.as_bytes())?;
+    }
+    writer.finish()
+}
+
+/// Splits the records of `images` into a train and a val set with `ratio` of every class in the train set
+///
+/// With a seed the records of each class are shuffled before they are split, without one the first records of each
+/// class go to the train set, like an unshuffled split. The ratio is clamped to `0.0..=1.0` and the number of train
+/// records of a class is rounded, so small classes may end up entirely on one side.
+pub fn split_stratified<R1, R2>(
+    images: &mut IdxCursor<R1>, labels: &mut IdxCursor<R2>, ratio: f64, seed: Option<u64>
+) -> Result<StratifiedSplit, IdxError>
+where
+    R1: Read + Seek,
+    R2: Read + Seek,
+{
+    let (left, right) = (images.num_records(), labels.num_records());
+    if left != right {
+        return Err(IdxError::RecordCountMismatch{ left, right });
+    }
+    if labels.dimensions().len() != 1 {
+        return Err(IdxError::DimensionMismatch{ needed: 1, supplied: labels.dimensions().len() });
+    }
+    let mut classes: BTreeMap<i64, Vec<u32>> = BTreeMap::new();
+    for (record, res) in labels.iter().enumerate() {
+        let (_, value) = res?;
+        classes.entry(label(value)?).or_default().push(record as u32);
+    }
+    let mut rng = seed.map(Rng::new);
+    let mut split = StratifiedSplit { train: Vec::new(), val: Vec::new() };
+    for records in classes.values_mut() {
+        if let Some(rng) = &mut rng {
+            rng.shuffle(records);
+        }
+        // NaN sends the whole class to the val set
+        let train = ((records.len() as f64 * ratio.clamp(0.0, 1.0)).round() as usize).min(records.len());
+        split.train.extend_from_slice(&records[..train]);
+        split.val.extend_from_slice(&records[train..]);
+    }
+    // Mixes the classes, which are still in label order
+    match &mut rng {
+        Some(rng) => {
+            rng.shuffle(&mut split.train);
+            rng.shuffle(&mut split.val);
+        }
+        None => {
+            split.train.sort_unstable();
+            split.val.sort_unstable();
+        }
+    }
+    Ok(split)
+}
+
+/// Class of a label value, float labels are accepted as long as they are whole numbers
+fn label(value: IdxValue) -> Result<i64, IdxError> {
+    match value.as_i128() {
+        Some(label) => i64::try_from(label).map_err(|_| IdxError::CannotCast),
+        None        => Ok(i32::from_value(value)? as i64),
+    }
+}
+
+/// Converts all labels into one-hot vectors of length `num_classes`, concatenated in record order
+pub fn one_hot<T: IdxElement, R: Read + Seek>(cursor: &mut IdxCursor<R>, num_classes: u32) -> Result<Vec<T>, IdxError> {
+    let records = cursor.num_records() as usize;
+    let mut out = Vec::with_capacity(records * num_classes as usize);
+    for vector in one_hot_iter::<T, R>(cursor, num_classes)? {
+        out.extend(vector?);
+    }
+    Ok(out)
+}
+
+/// Iterates over the one-hot vector of each label, the file has to be one dimensional
+pub fn one_hot_iter<T: IdxElement, R: Read + Seek>(cursor: &mut IdxCursor<R>, num_classes: u32) -> Result<OneHotIter<'_, R, T>, IdxError> {
+    if cursor.dimensions().len() != 1 {
+        return Err(IdxError::DimensionMismatch{ needed: 1, supplied: cursor.dimensions().len() });
+    }
+    Ok(OneHotIter { values: cursor.iter(), num_classes, index: 0, failed: false, element: PhantomData })
+}
+
+impl<'a, R: Read + Seek, T: IdxElement> OneHotIter<'a, R, T> {
+    fn encode(&self, value: IdxValue) -> Result<Vec<T>, IdxError> {
+        // Float labels are accepted as long as they are whole numbers
+        let label = match value.as_i128() {
+            Some(label) => label,
+            None        => i32::from_value(value)? as i128,
+        };
+        if label < 0 || label >= self.num_classes as i128 {
+            return Err(IdxError::LabelOutOfRange{ index: self.index, label, num_classes: self.num_classes });
+        }
+        let mut vector = vec![T::from_value_lossy(IdxValue::UnsignedByte(0)); self.num_classes as usize];
+        vector[label as usize] = T::from_value_lossy(IdxValue::UnsignedByte(1));
+        Ok(vector)
+    }
+}
+
+impl<'a, R: Read + Seek, T: IdxElement> Iterator for OneHotIter<'a, R, T> {
+    type Item = Result<Vec<T>, IdxError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.failed {
+            return None;
+        }
+        let res = self.values.next()?.and_then(|(_, value)| self.encode(value));
+        self.index += 1;
+        self.failed = res.is_err();
+        Some(res)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io;
+
+    use super::split_stratified;
+    use crate::IdxCursor;
+    use crate::IdxWriter;
+
+    #[test]
+    fn stratified_without_seed_keeps_order() {
+        let labels = [0u8, 1, 0, 1, 0, 1, 0, 1, 1, 1];
+        let data = IdxWriter::write_array(io::Cursor::new(Vec::new()), &[10], &labels).unwrap().into_inner();
+        let mut cursor = IdxCursor::from_bytes(data.clone()).unwrap();
+        let mut images = IdxCursor::from_bytes(data).unwrap();
+        let split = split_stratified(&mut images, &mut cursor, 0.5, None).unwrap();
+        assert_eq!(split.train, vec![0, 1, 2, 3, 5]);
+        assert_eq!(split.val, vec![4, 6, 7, 8, 9]);
+        // With a seed the same classes are split, in random order
+        let mut shuffled = split_stratified(&mut images, &mut cursor, 0.5, Some(3)).unwrap();
+        shuffled.train.sort_unstable();
+        assert_eq!(shuffled.train.len(), 5);
+        assert_eq!(shuffled.train.iter().filter(|r| labels[**r as usize] == 0).count(), 2);
+    }
+
+    #[test]
+    fn one_hot_of_scalar() {
+        // A scalar counts as one record, but one-hot labels need a one dimensional file
+        let mut cursor = IdxCursor::from_bytes(vec![0, 0, 0x08, 0, 1]).unwrap();
+        let res = super::one_hot::<u8, _>(&mut cursor, 2);
+        assert!(matches!(res, Err(crate::IdxError::DimensionMismatch{ needed: 1, supplied: 0 })));
+    }
+
+    #[test]
+    fn stratified_ratio_edges() {
+        let labels = [0u8, 1, 0, 1, 1];
+        let data = IdxWriter::write_array(io::Cursor::new(Vec::new()), &[5], &labels).unwrap().into_inner();
+        let mut cursor = IdxCursor::from_bytes(data.clone()).unwrap();
+        let mut images = IdxCursor::from_bytes(data).unwrap();
+        let mut split = |ratio| split_stratified(&mut images, &mut cursor, ratio, Some(1)).unwrap();
+        for ratio in &[0.0, -0.5, f64::NAN, f64::NEG_INFINITY] {
+            let split = split(*ratio);
+            assert!(split.train.is_empty(), "ratio {}", ratio);
+            assert_eq!(split.val.len(), 5, "ratio {}", ratio);
+        }
+        for ratio in &[1.0, 1.5, f64::INFINITY] {
+            let split = split(*ratio);
+            assert_eq!(split.train.len(), 5, "ratio {}", ratio);
+            assert!(split.val.is_empty(), "ratio {}", ratio);
+        }
+    }
+
+    #[cfg(feature = "extensions")]
+    #[test]
+    fn remap_beyond_f64_precision() {
+        use std::collections::BTreeMap;
+
+        use super::remap;
+
+        // 2^53 + 1 is the first integer an f64 cannot hold
+        let large = (1i64 << 53) + 1;
+        let data = IdxWriter::write_array(io::Cursor::new(Vec::new()), &[2], &[0i64, 1]).unwrap().into_inner();
+        let mapping: BTreeMap<i64, i64> = vec![(0, large), (1, i64::MAX)].into_iter().collect();
+        let written = remap(&mut IdxCursor::from_bytes(data).unwrap(), &mapping, io::Cursor::new(Vec::new())).unwrap();
+        assert_eq!(IdxCursor::from_bytes(written.into_inner()).unwrap().to_vec::<i64>().unwrap().0, vec![large, i64::MAX]);
+
+        let data = IdxWriter::write_array(io::Cursor::new(Vec::new()), &[1], &[0u64]).unwrap().into_inner();
+        let mapping: BTreeMap<i64, i64> = vec![(0, large)].into_iter().collect();
+        let written = remap(&mut IdxCursor::from_bytes(data).unwrap(), &mapping, io::Cursor::new(Vec::new())).unwrap();
+        assert_eq!(IdxCursor::from_bytes(written.into_inner()).unwrap().to_vec::<u64>().unwrap().0, vec![large as u64]);
+    }
+}
@@ -1,21 +1,263 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+
+use alloc::boxed::Box;
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::cmp::Ordering;
+use core::convert::TryFrom;
+use core::convert::TryInto;
+use core::fmt;
+#[cfg(feature = "std")]
+use std::error;
+#[cfg(feature = "std")]
 use std::io;
+#[cfg(feature = "std")]
 use std::io::Read;
+#[cfg(feature = "std")]
 use std::io::Seek;
+#[cfg(feature = "std")]
 use std::io::SeekFrom;
-use std::convert::TryFrom;
-use std::convert::TryInto;
+#[cfg(feature = "std")]
+use std::ops::Range;
+
+#[cfg(feature = "augment")]
+pub mod augment;
+#[cfg(feature = "std")]
+mod batch;
+#[cfg(feature = "std")]
+mod buffer;
+#[cfg(feature = "std")]
+pub mod bundle;
+#[cfg(feature = "std")]
+mod builder;
+mod bytes;
+#[cfg(feature = "std")]
+mod cache;
+#[cfg(feature = "std")]
+mod checksum;
+#[cfg(feature = "std")]
+mod concat;
+#[cfg(feature = "std")]
+pub mod csv;
+#[cfg(feature = "std")]
+pub mod dataset;
+mod element;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+#[cfg(feature = "std")]
+mod file;
+pub mod format;
+#[cfg(feature = "std")]
+mod gather;
+#[cfg(feature = "gzip")]
+mod gzip;
+mod header;
+#[cfg(feature = "std")]
+mod iter;
+#[cfg(feature = "std")]
+pub mod jsonl;
+#[cfg(feature = "std")]
+pub mod labels;
+#[cfg(all(feature = "mmap", unix))]
+mod mmap;
+#[cfg(feature = "std")]
+pub mod npy;
+mod options;
+#[cfg(feature = "parallel")]
+mod parallel;
+#[cfg(feature = "threads")]
+mod prefetch;
+#[cfg(feature = "std")]
+mod png;
+#[cfg(feature = "std")]
+mod progress;
+#[cfg(feature = "std")]
+pub mod quantize;
+#[cfg(feature = "std")]
+pub mod recode;
+#[cfg(feature = "std")]
+mod records;
+#[cfg(feature = "std")]
+mod rng;
+#[cfg(feature = "std")]
+mod sample;
+#[cfg(feature = "std")]
+mod select;
+mod shape;
+mod slice;
+#[cfg(feature = "std")]
+pub mod sparse;
+#[cfg(feature = "std")]
+pub mod stats;
+#[cfg(feature = "std")]
+mod stream;
+#[cfg(feature = "std")]
+mod sub;
+#[cfg(all(feature = "std", any(unix, windows)))]
+mod sync;
+#[cfg(feature = "std")]
+pub mod testing;
+#[cfg(feature = "std")]
+pub mod transforms;
+#[cfg(feature = "std")]
+mod view;
+#[cfg(feature = "std")]
+mod writer;
+#[cfg(feature = "std")]
+mod zip;
+
+#[cfg(feature = "std")]
+pub use batch::{Batch, Batches};
+#[cfg(feature = "std")]
+use buffer::Buffered;
+#[cfg(feature = "std")]
+pub use builder::IdxCursorBuilder;
+pub use bytes::IdxBytes;
+pub use bytes::IdxBytesIter;
+#[cfg(feature = "std")]
+pub use cache::IdxCache;
+#[cfg(feature = "std")]
+pub use concat::IdxConcat;
+#[cfg(feature = "std")]
+use element::decode_into;
+pub use element::IdxElement;
+#[cfg(feature = "std")]
+pub use file::{IdxFile, IdxSource};
+pub use header::IdxHeader;
+#[cfg(feature = "std")]
+pub use iter::IdxIterator;
+#[cfg(all(feature = "mmap", unix))]
+pub use mmap::{IdxMmap, Mmap};
+pub use options::{Endianness, IdxOptions, IdxWarning};
+#[cfg(feature = "threads")]
+pub use prefetch::Prefetch;
+#[cfg(feature = "std")]
+pub use progress::IdxProgress;
+#[cfg(feature = "std")]
+pub use records::{IdxRecords, RecordOrder, RecordView, RecordViews};
+#[cfg(feature = "std")]
+pub use sample::permutation;
+#[cfg(feature = "std")]
+pub use select::IdxSelection;
+pub use shape::IdxShape;
+pub use slice::IdxSlice;
+#[cfg(feature = "std")]
+pub use stream::IdxStream;
+#[cfg(feature = "std")]
+pub use sub::SubReader;
+#[cfg(all(feature = "std", any(unix, windows)))]
+pub use sync::SyncIdxReader;
+#[cfg(feature = "std")]
+pub use view::{IdxView, IdxViewIter};
+#[cfg(feature = "std")]
+pub use writer::{IdxWriter, StreamingIdxWriter};
+#[cfg(feature = "std")]
+pub use zip::{zip, IdxZip};
 
+/// Errors of reading and writing IDX data
+///
+/// Dimension numbers are `usize` and element offsets `u64`, so neither limits the size of a tensor:
+///
+// The example reads through `IdxCursor`, which needs std
+#[cfg_attr(feature = "std", doc = r#"
+```
+use idxrs::{IdxCursor, IdxError};
+
+// Header of a u8 file with 70000 x 70000 x 3 elements, more than u32::MAX, without its payload
+let mut data = vec![0, 0, 0x08, 3];
+for d in &[70_000u32, 70_000, 3] {
+    data.extend_from_slice(&d.to_be_bytes());
+}
+let mut cursor = IdxCursor::from_bytes(data).unwrap();
+match cursor.get(&[69_999, 69_999, 2]) {
+    Err(IdxError::ReadFailed{ offset, .. }) => assert_eq!(offset, 16 + 14_699_999_999),
+    other => panic!("{:?}", other),
+}
+assert!(matches!(cursor.get(&[0; 300]), Err(IdxError::DimensionMismatch{ needed: 3, supplied: 300 })));
+```
+"#)]
 #[derive(Debug)]
 pub enum IdxError {
-    DimensionMismatch{ needed: u8 , supplied: u8 },
-    OutOfBounds{ dimension: u8, max: u32, index: u32},
-    WrongHeader,
+    DimensionMismatch{ needed: usize, supplied: usize },
+    OutOfBounds{ dimension: usize, max: u32, index: u32 },
+    WrongHeader{ found: [u8; 4] },
+    #[cfg(feature = "std")]
     IoError(io::Error),
-    UnknownDataType,
+    #[cfg(feature = "std")]
+    ReadFailed{ offset: u64, len: u64, source: io::Error },
+    UnknownDataType(u8),
     CannotCast,
+    TooManyDimensions(usize),
+    DataTypeMismatch{ expected: IdxDataType, found: IdxDataType },
+    RecordCountMismatch{ left: u32, right: u32 },
+    LengthMismatch{ expected: u64, actual: u64 },
+    ShapeMismatch{ expected: Vec<u32>, found: Vec<u32> },
+    ChecksumMismatch{ expected: String, actual: String },
+    LabelOutOfRange{ index: u32, label: i128, num_classes: u32 },
+    ValueOutOfRange{ value: IdxValue, data_type: IdxDataType },
+    /// Element count or payload size given by the dimensions does not fit into 64 bits, or a record count does not
+    /// fit into 32 bits
+    Overflow,
+    FileTooLarge{ size: u64, max: u64 },
+    /// Axes given to `IdxView::permute` are not every dimension exactly once
+    InvalidPermutation(Vec<usize>),
+    /// Label of a record has no entry in the mapping given to `labels::remap`
+    UnmappedLabel{ index: u32, label: i64 },
+    /// Operation only works on the listed data types, e.g. `Pipeline::augment` on u8 and f32 records
+    UnsupportedDataType{ supported: &'static [IdxDataType], found: IdxDataType },
+}
+
+impl fmt::Display for IdxError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            IdxError::DimensionMismatch{ needed, supplied } => write!(f, "expected {} dimensions, got {}", needed, supplied),
+            IdxError::OutOfBounds{ dimension, max, index } => write!(f, "index {} out of bounds for dimension {} of size {}", index, dimension, max),
+            IdxError::WrongHeader{ found } => write!(f, "invalid IDX magic number {:02x} {:02x} {:02x} {:02x}, the first two bytes must be 00 00", found[0], found[1], found[2], found[3]),
+            #[cfg(feature = "std")]
+            IdxError::IoError(e) => write!(f, "I/O error: {}", e),
+            #[cfg(feature = "std")]
+            IdxError::ReadFailed{ offset, len, source } => write!(f, "failed to read {} bytes at byte offset {}: {}", len, offset, source),
+            IdxError::UnknownDataType(code) => write!(f, "unknown data type code 0x{:02x}", code),
+            IdxError::CannotCast => write!(f, "value cannot be converted"),
+            IdxError::TooManyDimensions(n) => write!(f, "{} dimensions exceed the maximum of 255", n),
+            IdxError::DataTypeMismatch{ expected, found } => write!(f, "expected data type {:?}, found {:?}", expected, found),
+            IdxError::RecordCountMismatch{ left, right } => write!(f, "record counts differ: {} vs {}", left, right),
+            IdxError::LengthMismatch{ expected, actual } => write!(f, "expected {} bytes, found {}", expected, actual),
+            IdxError::ShapeMismatch{ expected, found } => write!(f, "expected shape {:?}, found {:?}", expected, found),
+            IdxError::ChecksumMismatch{ expected, actual } => write!(f, "checksum mismatch, expected {}, got {}", expected, actual),
+            IdxError::LabelOutOfRange{ index, label, num_classes } => write!(f, "label {} of record {} is not in 0..{}", label, index, num_classes),
+            IdxError::ValueOutOfRange{ value, data_type } => write!(f, "value {:?} cannot be stored as {:?}", value, data_type),
+            IdxError::Overflow => write!(f, "size of the dimensions overflows 64 bits or record count overflows 32 bits"),
+            IdxError::FileTooLarge{ size, max } => write!(f, "payload of {} bytes exceeds the limit of {} bytes", size, max),
+            IdxError::InvalidPermutation(axes) => write!(f, "axes {:?} are not a permutation of the dimensions", axes),
+            IdxError::UnmappedLabel{ index, label } => write!(f, "label {} of record {} has no mapping", label, index),
+            IdxError::UnsupportedDataType{ supported, found } => write!(f, "data type {:?} is not supported, expected one of {:?}", found, supported),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl error::Error for IdxError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            IdxError::IoError(e) => Some(e),
+            IdxError::ReadFailed{ source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl From<io::Error> for IdxError {
+    fn from(e: io::Error) -> IdxError {
+        IdxError::IoError(e)
+    }
 }
 
-#[derive(Clone, Copy, PartialEq)]
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub enum IdxDataType {
     UnsignedByte,
     SignedByte,
@@ -23,6 +265,18 @@ pub enum IdxDataType {
     Int,
     Float,
     Double,
+    /// Vendor extension with type code 0x0a
+    #[cfg(feature = "extensions")]
+    UnsignedShort,
+    /// Vendor extension with type code 0x10
+    #[cfg(feature = "extensions")]
+    UnsignedInt,
+    /// Vendor extension with type code 0x0f
+    #[cfg(feature = "extensions")]
+    Long,
+    /// Vendor extension with type code 0x11
+    #[cfg(feature = "extensions")]
+    UnsignedLong,
 }
 
 impl IdxDataType {
@@ -34,7 +288,39 @@ impl IdxDataType {
             0x0c => Ok(IdxDataType::Int),
             0x0d => Ok(IdxDataType::Float),
             0x0e => Ok(IdxDataType::Double),
-            _ => Err(IdxError::UnknownDataType),
+            #[cfg(feature = "extensions")]
+            0x0a => Ok(IdxDataType::UnsignedShort),
+            #[cfg(feature = "extensions")]
+            0x10 => Ok(IdxDataType::UnsignedInt),
+            #[cfg(feature = "extensions")]
+            0x0f => Ok(IdxDataType::Long),
+            #[cfg(feature = "extensions")]
+            0x11 => Ok(IdxDataType::UnsignedLong),
+            code => Err(IdxError::UnknownDataType(code)),
+        }
+    }
+
+    /// Whether the type is one of the vendor extensions outside of the IDX specification
+    pub fn is_extension(&self) -> bool {
+        !matches!(self.get_code(), 0x08 | 0x09 | 0x0b..=0x0e)
+    }
+
+    pub fn get_code(&self) -> u8 {
+        match self {
+            IdxDataType::UnsignedByte => 0x08,
+            IdxDataType::SignedByte   => 0x09,
+            IdxDataType::Short        => 0x0b,
+            IdxDataType::Int          => 0x0c,
+            IdxDataType::Float        => 0x0d,
+            IdxDataType::Double       => 0x0e,
+            #[cfg(feature = "extensions")]
+            IdxDataType::UnsignedShort => 0x0a,
+            #[cfg(feature = "extensions")]
+            IdxDataType::UnsignedInt  => 0x10,
+            #[cfg(feature = "extensions")]
+            IdxDataType::Long         => 0x0f,
+            #[cfg(feature = "extensions")]
+            IdxDataType::UnsignedLong => 0x11,
         }
     }
 
@@ -46,18 +332,19 @@ impl IdxDataType {
             IdxDataType::Int          => 4,
             IdxDataType::Float        => 4,
             IdxDataType::Double       => 8,
+            #[cfg(feature = "extensions")]
+            IdxDataType::UnsignedShort => 2,
+            #[cfg(feature = "extensions")]
+            IdxDataType::UnsignedInt  => 4,
+            #[cfg(feature = "extensions")]
+            IdxDataType::Long         => 8,
+            #[cfg(feature = "extensions")]
+            IdxDataType::UnsignedLong => 8,
         }
     }
 
     pub fn create_buf(&self) -> Box<[u8]> {
-        match self {
-            IdxDataType::UnsignedByte => Box::new([0; 1]),
-            IdxDataType::SignedByte   => Box::new([0; 1]),
-            IdxDataType::Short        => Box::new([0; 2]),
-            IdxDataType::Int          => Box::new([0; 4]),
-            IdxDataType::Float        => Box::new([0; 4]),
-            IdxDataType::Double       => Box::new([0; 8]),
-        }
+        vec![0; self.get_size() as usize].into_boxed_slice()
     }
 }
 
@@ -70,6 +357,7 @@ macro_rules! from_slice {
     };
 }
 
+#[derive(Clone, Copy, Debug)]
 pub enum IdxValue {
     UnsignedByte(u8),
     SignedByte(i8),
@@ -77,88 +365,774 @@ pub enum IdxValue {
     Int(i32),
     Float(f32),
     Double(f64),
+    #[cfg(feature = "extensions")]
+    UnsignedShort(u16),
+    #[cfg(feature = "extensions")]
+    UnsignedInt(u32),
+    #[cfg(feature = "extensions")]
+    Long(i64),
+    #[cfg(feature = "extensions")]
+    UnsignedLong(u64),
 }
 
-impl TryFrom<(IdxDataType, Box<[u8]>)> for IdxValue {
-    type Error = IdxError;
-    fn try_from(tuple: (IdxDataType, Box<[u8]>)) -> Result<IdxValue, Self::Error> {
-        let (idt, bytes) = tuple;
+impl IdxValue {
+    /// Data type of the variant
+    pub fn data_type(&self) -> IdxDataType {
+        match self {
+            IdxValue::UnsignedByte(_) => IdxDataType::UnsignedByte,
+            IdxValue::SignedByte(_)   => IdxDataType::SignedByte,
+            IdxValue::Short(_)        => IdxDataType::Short,
+            IdxValue::Int(_)          => IdxDataType::Int,
+            IdxValue::Float(_)        => IdxDataType::Float,
+            IdxValue::Double(_)       => IdxDataType::Double,
+            #[cfg(feature = "extensions")]
+            IdxValue::UnsignedShort(_) => IdxDataType::UnsignedShort,
+            #[cfg(feature = "extensions")]
+            IdxValue::UnsignedInt(_)  => IdxDataType::UnsignedInt,
+            #[cfg(feature = "extensions")]
+            IdxValue::Long(_)         => IdxDataType::Long,
+            #[cfg(feature = "extensions")]
+            IdxValue::UnsignedLong(_) => IdxDataType::UnsignedLong,
+        }
+    }
+
+    /// Size of the value in bytes, the same as the size of its data type
+    pub fn size(&self) -> u8 {
+        self.data_type().get_size()
+    }
+
+    /// Big endian bytes of the value as stored in an IDX file of its data type
+    pub fn to_be_bytes(&self) -> Box<[u8]> {
+        let mut bytes = Vec::with_capacity(self.size() as usize);
+        self.write_be(&mut bytes);
+        bytes.into_boxed_slice()
+    }
+
+    /// Appends the big endian bytes of the value
+    pub(crate) fn write_be(&self, out: &mut Vec<u8>) {
+        match *self {
+            IdxValue::UnsignedByte(v) => out.extend_from_slice(&v.to_be_bytes()),
+            IdxValue::SignedByte(v)   => out.extend_from_slice(&v.to_be_bytes()),
+            IdxValue::Short(v)        => out.extend_from_slice(&v.to_be_bytes()),
+            IdxValue::Int(v)          => out.extend_from_slice(&v.to_be_bytes()),
+            IdxValue::Float(v)        => out.extend_from_slice(&v.to_be_bytes()),
+            IdxValue::Double(v)       => out.extend_from_slice(&v.to_be_bytes()),
+            #[cfg(feature = "extensions")]
+            IdxValue::UnsignedShort(v) => out.extend_from_slice(&v.to_be_bytes()),
+            #[cfg(feature = "extensions")]
+            IdxValue::UnsignedInt(v)  => out.extend_from_slice(&v.to_be_bytes()),
+            #[cfg(feature = "extensions")]
+            IdxValue::Long(v)         => out.extend_from_slice(&v.to_be_bytes()),
+            #[cfg(feature = "extensions")]
+            IdxValue::UnsignedLong(v) => out.extend_from_slice(&v.to_be_bytes()),
+        }
+    }
+
+    /// Every value of the standard data types fits into an f64 without loss, 64 bit extension integers may be rounded
+    pub fn as_f64(&self) -> f64 {
+        match *self {
+            IdxValue::UnsignedByte(v) => v as f64,
+            IdxValue::SignedByte(v)   => v as f64,
+            IdxValue::Short(v)        => v as f64,
+            IdxValue::Int(v)          => v as f64,
+            IdxValue::Float(v)        => v as f64,
+            IdxValue::Double(v)       => v,
+            #[cfg(feature = "extensions")]
+            IdxValue::UnsignedShort(v) => v as f64,
+            #[cfg(feature = "extensions")]
+            IdxValue::UnsignedInt(v)  => v as f64,
+            #[cfg(feature = "extensions")]
+            IdxValue::Long(v)         => v as f64,
+            #[cfg(feature = "extensions")]
+            IdxValue::UnsignedLong(v) => v as f64,
+        }
+    }
+
+    /// Exact value of integer types, `None` for floats
+    pub(crate) fn as_i128(&self) -> Option<i128> {
+        match *self {
+            IdxValue::UnsignedByte(v) => Some(v as i128),
+            IdxValue::SignedByte(v)   => Some(v as i128),
+            IdxValue::Short(v)        => Some(v as i128),
+            IdxValue::Int(v)          => Some(v as i128),
+            IdxValue::Float(_)        => None,
+            IdxValue::Double(_)       => None,
+            #[cfg(feature = "extensions")]
+            IdxValue::UnsignedShort(v) => Some(v as i128),
+            #[cfg(feature = "extensions")]
+            IdxValue::UnsignedInt(v)  => Some(v as i128),
+            #[cfg(feature = "extensions")]
+            IdxValue::Long(v)         => Some(v as i128),
+            #[cfg(feature = "extensions")]
+            IdxValue::UnsignedLong(v) => Some(v as i128),
+        }
+    }
+
+    /// Integers are returned as is, floats are truncated towards zero and saturated
+    pub fn as_i64(&self) -> i64 {
+        match *self {
+            IdxValue::UnsignedByte(v) => v as i64,
+            IdxValue::SignedByte(v)   => v as i64,
+            IdxValue::Short(v)        => v as i64,
+            IdxValue::Int(v)          => v as i64,
+            IdxValue::Float(v)        => v as i64,
+            IdxValue::Double(v)       => v as i64,
+            #[cfg(feature = "extensions")]
+            IdxValue::UnsignedShort(v) => v as i64,
+            #[cfg(feature = "extensions")]
+            IdxValue::UnsignedInt(v)  => v as i64,
+            #[cfg(feature = "extensions")]
+            IdxValue::Long(v)         => v,
+            #[cfg(feature = "extensions")]
+            IdxValue::UnsignedLong(v) => v.min(i64::MAX as u64) as i64,
+        }
+    }
+
+    /// Both values as `f64` for arithmetic across types, exact except for 64 bit extension integers
+    pub fn promote(self, other: IdxValue) -> (f64, f64) {
+        (self.as_f64(), other.as_f64())
+    }
+
+    /// Reads value of the data type from big endian bytes
+    pub(crate) fn decode(idt: IdxDataType, bytes: &[u8]) -> Result<IdxValue, IdxError> {
         if idt.get_size() as usize != bytes.len() {
             return Err(IdxError::CannotCast);
         }
         let val = match idt {
-            IdxDataType::UnsignedByte => IdxValue::UnsignedByte(from_slice!(u8, &bytes[..])?),
-            IdxDataType::SignedByte   => IdxValue::SignedByte(from_slice!(i8, &bytes[..])?),
-            IdxDataType::Short        => IdxValue::Short(from_slice!(i16, &bytes[..])?),
-            IdxDataType::Int          => IdxValue::Int(from_slice!(i32, &bytes[..])?),
-            IdxDataType::Float        => IdxValue::Float(from_slice!(f32, &bytes[..])?),
-            IdxDataType::Double       => IdxValue::Double(from_slice!(f64, &bytes[..])?),
+            IdxDataType::UnsignedByte => IdxValue::UnsignedByte(from_slice!(u8, bytes)?),
+            IdxDataType::SignedByte   => IdxValue::SignedByte(from_slice!(i8, bytes)?),
+            IdxDataType::Short        => IdxValue::Short(from_slice!(i16, bytes)?),
+            IdxDataType::Int          => IdxValue::Int(from_slice!(i32, bytes)?),
+            IdxDataType::Float        => IdxValue::Float(from_slice!(f32, bytes)?),
+            IdxDataType::Double       => IdxValue::Double(from_slice!(f64, bytes)?),
+            #[cfg(feature = "extensions")]
+            IdxDataType::UnsignedShort => IdxValue::UnsignedShort(from_slice!(u16, bytes)?),
+            #[cfg(feature = "extensions")]
+            IdxDataType::UnsignedInt  => IdxValue::UnsignedInt(from_slice!(u32, bytes)?),
+            #[cfg(feature = "extensions")]
+            IdxDataType::Long         => IdxValue::Long(from_slice!(i64, bytes)?),
+            #[cfg(feature = "extensions")]
+            IdxDataType::UnsignedLong => IdxValue::UnsignedLong(from_slice!(u64, bytes)?),
         };
         Ok(val)
     }
 }
 
+/// Values compare by the number they hold regardless of their type, `Int(3) == Float(3.0)`
+impl PartialEq for IdxValue {
+    fn eq(&self, other: &IdxValue) -> bool {
+        self.partial_cmp(other) == Some(Ordering::Equal)
+    }
+}
+
+impl PartialOrd for IdxValue {
+    fn partial_cmp(&self, other: &IdxValue) -> Option<Ordering> {
+        // Integers are compared exactly, 64 bit values do not all fit into an f64
+        match (self.as_i128(), other.as_i128()) {
+            (Some(a), Some(b)) => Some(a.cmp(&b)),
+            _ => self.as_f64().partial_cmp(&other.as_f64()),
+        }
+    }
+}
+
+/// Formats the number alone, like the primitive it holds
+impl fmt::Display for IdxValue {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            IdxValue::UnsignedByte(v) => fmt::Display::fmt(v, f),
+            IdxValue::SignedByte(v)   => fmt::Display::fmt(v, f),
+            IdxValue::Short(v)        => fmt::Display::fmt(v, f),
+            IdxValue::Int(v)          => fmt::Display::fmt(v, f),
+            IdxValue::Float(v)        => fmt::Display::fmt(v, f),
+            IdxValue::Double(v)       => fmt::Display::fmt(v, f),
+            #[cfg(feature = "extensions")]
+            IdxValue::UnsignedShort(v) => fmt::Display::fmt(v, f),
+            #[cfg(feature = "extensions")]
+            IdxValue::UnsignedInt(v)  => fmt::Display::fmt(v, f),
+            #[cfg(feature = "extensions")]
+            IdxValue::Long(v)         => fmt::Display::fmt(v, f),
+            #[cfg(feature = "extensions")]
+            IdxValue::UnsignedLong(v) => fmt::Display::fmt(v, f),
+        }
+    }
+}
+
+impl From<IdxValue> for IdxDataType {
+    fn from(value: IdxValue) -> IdxDataType {
+        value.data_type()
+    }
+}
+
+/// Inverse of `IdxValue::to_be_bytes`
+impl TryFrom<(IdxDataType, Box<[u8]>)> for IdxValue {
+    type Error = IdxError;
+    fn try_from(tuple: (IdxDataType, Box<[u8]>)) -> Result<IdxValue, Self::Error> {
+        let (idt, bytes) = tuple;
+        IdxValue::decode(idt, &bytes)
+    }
+}
+
+#[cfg(feature = "std")]
+const READ_CHUNK: usize = 1 << 20;
+
+/// Object safe combination of `Read` and `Seek`, so cursors over different readers can share one type
+#[cfg(feature = "std")]
+pub trait ReadSeek: Read + Seek {}
+
+#[cfg(feature = "std")]
+impl<T: Read + Seek + ?Sized> ReadSeek for T {}
+
+/// Reads elements and records of an IDX file through any seekable reader
+///
+/// The reader can be borrowed or boxed to keep using it afterwards or to store cursors over different readers
+/// together:
+///
+/// ```
+/// use std::io;
+/// use idxrs::{IdxCursor, ReadSeek};
+///
+/// let data = vec![0, 0, 0x08, 1, 0, 0, 0, 2, 7, 9];
+/// let mut reader = io::Cursor::new(data.clone());
+/// assert_eq!(IdxCursor::new(&mut reader)?.get(&[1])?.as_f64(), 9.0);
+/// // A new cursor reads the header at the current position
+/// reader.set_position(0);
+///
+/// type DynCursor = IdxCursor<Box<dyn ReadSeek>>;
+/// let mut cursors = vec![
+///     DynCursor::new(Box::new(reader))?,
+///     DynCursor::new(Box::new(io::Cursor::new(data)))?,
+/// ];
+/// assert_eq!(cursors[1].get(&[0])?.as_f64(), 7.0);
+/// let reader = cursors.remove(0).into_inner();
+/// # Ok::<(), idxrs::IdxError>(())
+/// ```
+#[cfg(feature = "std")]
 pub struct IdxCursor<R: Read + Seek> {
-    reader: R,
-    pub dimensions: Vec<u32>,
+    reader: Buffered<R>,
+    shape: IdxShape,
     data_type: IdxDataType,
+    endianness: Endianness,
+    // Payload start and element size in bytes, computed once for the offset arithmetic of every read
+    header_len: u64,
+    element_size: u64,
+    // Reused by get_ref, large enough for the widest data type
+    element: [u8; 8],
+    // Record read by next_record
+    position: u32,
+}
+
+/// Shows what the file holds, not its data
+#[cfg(feature = "std")]
+impl<R: Read + Seek> fmt::Debug for IdxCursor<R> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("IdxCursor")
+            .field("data_type", &self.data_type)
+            .field("shape", &self.shape.dimensions())
+            .field("endianness", &self.endianness)
+            .finish_non_exhaustive()
+    }
 }
 
+#[cfg(feature = "std")]
 impl<R: Read + Seek> IdxCursor<R> {
     /// Looks up data type and creates cursor for the type
-    pub fn new(mut reader: R) -> Result<IdxCursor<R>, IdxError> {
-        // Read first 4 bytes to get magic number
-        let mut buf: [u8; 4] = [0; 4];
-        reader.read_exact(&mut buf).map_err(|e| IdxError::IoError(e))?;
+    pub fn new(reader: R) -> Result<IdxCursor<R>, IdxError> {
+        IdxCursor::with_options(reader, IdxOptions::default())
+    }
 
-        // First two bytes must be 0
-        if buf[0] != 0 || buf[1] != 0 {
-            return Err(IdxError::WrongHeader);
+    pub fn with_options(reader: R, options: IdxOptions) -> Result<IdxCursor<R>, IdxError> {
+        let mut reader = Buffered::new(reader, options.buffer_size);
+        let (data_type, shape) = read_header(&mut reader, &options)?;
+        if options.strict && data_type.is_extension() {
+            return Err(IdxError::UnknownDataType(data_type.get_code()));
         }
-
-        // Read data type from third byte
-        let data_type = IdxDataType::read(buf[2])?;
-
-        // Number of dimensions are stored in fourth byte
-        // Read n next numbers of dimension sizes (each 32bit)
-        let n: usize = buf[3] as usize;
-        let mut dimensions: Vec<u32> = Vec::with_capacity(n);
-        for _i in 0..n {
-            reader.read_exact(&mut buf).map_err(|e| IdxError::IoError(e))?;
-            dimensions.push(u32::from_be_bytes(buf));
+        if let Some(max) = options.max_payload_bytes {
+            let size = shape.len() * data_type.get_size() as u64;
+            if size > max {
+                return Err(IdxError::FileTooLarge{ size, max });
+            }
+        }
+        if options.validate_length {
+            let header = IdxHeader::new(data_type, shape.dimensions());
+            let payload_start = reader.stream_position()?;
+            let actual = reader.seek(SeekFrom::End(0))?;
+            if actual != header.file_bytes() {
+                return Err(IdxError::LengthMismatch{ expected: header.file_bytes(), actual });
+            }
+            reader.seek(SeekFrom::Start(payload_start))?;
         }
         // Return Cursor type
         Ok(IdxCursor {
-            reader, dimensions, data_type
+            reader,
+            header_len: header_len(shape.dimensions().len()),
+            element_size: data_type.get_size() as u64,
+            shape,
+            data_type,
+            endianness: options.endianness,
+            element: [0; 8],
+            position: 0,
         })
     }
 
+    /// Returns the reader, which may be ahead of the last element read by up to `IdxOptions::buffer_size` bytes
+    pub fn into_inner(self) -> R {
+        self.reader.into_inner()
+    }
+
+    /// Byte order the file is read in
+    pub fn endianness(&self) -> Endianness {
+        self.endianness
+    }
+
+    pub fn dimensions(&self) -> &[u32] {
+        self.shape.dimensions()
+    }
+
+    /// Dimension sizes, the same as `dimensions`
+    pub fn shape(&self) -> &[u32] {
+        self.shape.dimensions()
+    }
+
+    pub fn dtype(&self) -> IdxDataType {
+        self.data_type
+    }
+
+    /// Size of the leading dimension, a file without dimensions holds a single record
+    pub fn num_records(&self) -> u32 {
+        self.shape.dimensions().first().copied().unwrap_or(1)
+    }
+
+    /// Number of elements in each record, i.e. the product of all dimensions after the leading one
+    pub fn record_len(&self) -> usize {
+        self.shape.record_len(1) as usize
+    }
+
+    pub fn header(&self) -> IdxHeader {
+        IdxHeader::new(self.data_type, self.shape.dimensions())
+    }
+
+    /// Length of the underlying stream in bytes, header included
+    pub fn stream_len(&mut self) -> Result<u64, IdxError> {
+        Ok(self.reader.seek(SeekFrom::End(0))?)
+    }
+
+    /// Reads element at the indices, a file without dimensions holds one scalar that is read with `get(&[])`
     pub fn get(&mut self, indices: &[u32]) -> Result<IdxValue, IdxError> {
-        // Throw index error if index parameter does not fit dimension count
-        if indices.len() != self.dimensions.len() {
-            return Err(IdxError::DimensionMismatch{ needed: self.dimensions.len() as u8, supplied: indices.len() as u8 });
-        }
-        // Check indices and sizes of individual dimensions
-        for (i, (dimension, index)) in self.dimensions.iter().zip(indices.iter()).enumerate() {
-            if index >= dimension {
-                return Err(IdxError::OutOfBounds{ dimension: i as u8, max: *dimension, index: *index });
+        let size = self.element_size as usize;
+        let mut buffer = [0; 8];
+        self.get_into(indices, &mut buffer[..size])?;
+        IdxValue::decode(self.data_type, &buffer[..size])
+    }
+
+    /// Like `get`, but skips checking the number of indices and their bounds
+    ///
+    /// `indices` should hold exactly one index per dimension, each smaller than its dimension. Otherwise the
+    /// offset wraps or lands on another element, and the value read is unspecified or the read fails.
+    /// This is a safe function rather than an `unsafe fn get_unchecked`, as a wrong index only ever reads
+    /// the wrong bytes of the file and cannot cause undefined behaviour.
+    pub fn get_unvalidated(&mut self, indices: &[u32]) -> Result<IdxValue, IdxError> {
+        let size = self.element_size as usize;
+        let offset = self.shape.offset_unchecked(indices);
+        // Valid indices cannot overflow, the end of the payload was checked to fit into 64 bits
+        let pos = self.header_len.wrapping_add(offset.wrapping_mul(self.element_size));
+        let mut buffer = [0; 8];
+        self.read_element_at(pos, &mut buffer[..size])?;
+        IdxValue::decode(self.data_type, &buffer[..size])
+    }
+
+    /// Reads the element starting at byte `pos` into `buf` as big endian bytes
+    fn read_element_at(&mut self, pos: u64, buf: &mut [u8]) -> Result<(), IdxError> {
+        self.reader.seek(SeekFrom::Start(pos))?;
+        read_exact_at(&mut self.reader, pos, buf)?;
+        self.endianness.swap_be(buf, buf.len());
+        Ok(())
+    }
+
+    /// Reads big endian bytes of the element into `buf`, which has to be exactly the element size
+    pub fn get_into(&mut self, indices: &[u32], buf: &mut [u8]) -> Result<(), IdxError> {
+        let size = self.element_size as usize;
+        if buf.len() != size {
+            return Err(IdxError::LengthMismatch{ expected: size as u64, actual: buf.len() as u64 });
+        }
+        // Manipulate position by data type intervals and header size, the end of the payload fits into 64 bits
+        let pos = self.header_len + self.shape.flat_offset(indices)? * self.element_size;
+        self.read_element_at(pos, buf)
+    }
+
+    /// Like `get_into`, but reads into a buffer of the cursor that is reused on every call
+    pub fn get_ref(&mut self, indices: &[u32]) -> Result<&[u8], IdxError> {
+        let size = self.element_size as usize;
+        let mut element = self.element;
+        self.get_into(indices, &mut element[..size])?;
+        self.element = element;
+        Ok(&self.element[..size])
+    }
+
+    /// Reads value and converts it to `T`, fails if the value is not exactly representable
+    pub fn get_as<T: IdxElement>(&mut self, indices: &[u32]) -> Result<T, IdxError> {
+        T::from_value(self.get(indices)?)
+    }
+
+    /// Reads value and converts it to `T` like an `as` cast
+    pub fn get_as_lossy<T: IdxElement>(&mut self, indices: &[u32]) -> Result<T, IdxError> {
+        Ok(T::from_value_lossy(self.get(indices)?))
+    }
+
+    /// Reads the whole block spanned by the trailing dimensions after the leading indices
+    pub fn get_record(&mut self, leading_indices: &[u32]) -> Result<IdxSlice, IdxError> {
+        let offset = self.shape.record_offset(leading_indices)?;
+        let record_len = self.shape.record_len(leading_indices.len());
+        let size = self.element_size;
+        // Records are stored back to back, so the block starts at the offset of its first element
+        let pos = self.header_len + offset * size;
+        let len = buffer_len(record_len, size)?;
+        // Small records are read without the extra seek, a failed read costs no more than the buffer
+        if len > READ_CHUNK {
+            self.check_available(pos, len as u64)?;
+        }
+        self.reader.seek(SeekFrom::Start(pos))?;
+        let mut buffer = vec![0; len].into_boxed_slice();
+        read_exact_at(&mut self.reader, pos, &mut buffer)?;
+        self.endianness.swap_be(&mut buffer, size as usize);
+        let trailing = self.shape.dimensions()[leading_indices.len()..].to_vec();
+        Ok(IdxSlice::new(buffer, self.data_type, IdxShape::new(trailing)))
+    }
+
+    /// Moves the position of `next_record` to the start of record `i`, `num_records()` moves it past the last one
+    pub fn seek_record(&mut self, i: u32) -> Result<(), IdxError> {
+        if i > self.num_records() {
+            return Err(IdxError::OutOfBounds{ dimension: 0, max: self.num_records(), index: i });
+        }
+        self.position = i;
+        Ok(())
+    }
+
+    /// Moves the position of `next_record` back to the first record
+    pub fn reset(&mut self) {
+        self.position = 0;
+    }
+
+    /// Index of the record `next_record` reads next
+    pub fn record_position(&self) -> u32 {
+        self.position
+    }
+
+    /// Reads the record at the current position and moves past it, `None` after the last record
+    ///
+    /// The position is only changed by `seek_record`, `reset` and this method, so random access with `get_record`
+    /// in between does not disturb sequential reading.
+    pub fn next_record(&mut self) -> Result<Option<IdxSlice>, IdxError> {
+        if self.position >= self.num_records() {
+            return Ok(None);
+        }
+        let index = [self.position];
+        // A scalar file is a single record without leading index
+        let leading = self.shape.dimensions().len().min(1);
+        let record = self.get_record(&index[..leading])?;
+        self.position += 1;
+        Ok(Some(record))
+    }
+
+    /// Replaces the contents of `out` with all elements of record `i` converted to `T`, converting the whole record
+    /// at once instead of element by element
+    ///
+    /// Fails on values that are not exactly representable, `out` then holds the elements before the failing one.
+    pub fn cast_record_into<T: IdxElement>(&mut self, i: u32, out: &mut Vec<T>) -> Result<(), IdxError> {
+        let record = self.get_record(&[i])?;
+        out.clear();
+        decode_into(self.data_type, record.as_bytes(), out, false)
+    }
+
+    /// Reads the whole payload converted to `T`, fails on values that are not exactly representable
+    pub fn to_vec<T: IdxElement>(&mut self) -> Result<(Vec<T>, IdxShape), IdxError> {
+        self.read_all(false)
+    }
+
+    /// Reads the whole payload converted to `T` like an `as` cast
+    pub fn to_vec_lossy<T: IdxElement>(&mut self) -> Result<(Vec<T>, IdxShape), IdxError> {
+        self.read_all(true)
+    }
+
+    /// Replaces the contents of `out` with all elements of the records in `records`, in storage order
+    ///
+    /// The records are read in large chunks and converted a chunk at a time, which is much faster than reading them
+    /// one by one. Fails on values that are not exactly representable.
+    pub fn read_records_into<T: IdxElement>(&mut self, records: Range<u32>, out: &mut Vec<T>) -> Result<(), IdxError> {
+        if records.start > records.end || records.end > self.num_records() {
+            let index = if records.end > self.num_records() { records.end } else { records.start };
+            return Err(IdxError::OutOfBounds{ dimension: 0, max: self.num_records(), index });
+        }
+        let record_len = self.shape.record_len(1);
+        let start = self.header_len + records.start as u64 * record_len * self.element_size;
+        out.clear();
+        self.read_elements(start, (records.end - records.start) as u64 * record_len, out, false)
+    }
+
+    fn read_all<T: IdxElement>(&mut self, lossy: bool) -> Result<(Vec<T>, IdxShape), IdxError> {
+        let start = self.header_len;
+        let mut out = Vec::new();
+        self.read_elements(start, self.shape.len(), &mut out, lossy)?;
+        Ok((out, self.shape.clone()))
+    }
+
+    /// Appends `count` elements starting at byte `start` to `out`
+    fn read_elements<T: IdxElement>(&mut self, start: u64, count: u64, out: &mut Vec<T>, lossy: bool) -> Result<(), IdxError> {
+        let size = self.element_size;
+        let len = buffer_len(count, size)? as u64;
+        self.check_available(start, len)?;
+        self.reader.seek(SeekFrom::Start(start))?;
+        #[cfg(feature = "parallel")]
+        {
+            // Large payloads are read at once and converted on all cores
+            if parallel::use_parallel(len) {
+                let mut buffer = vec![0; len as usize];
+                read_exact_at(&mut self.reader, start, &mut buffer)?;
+                self.endianness.swap_be(&mut buffer, size as usize);
+                let mut values = parallel::decode_parallel(self.data_type, &buffer, lossy)?;
+                if out.is_empty() {
+                    *out = values;
+                } else {
+                    out.append(&mut values);
+                }
+                return Ok(());
             }
         }
-        // seek to correct spot and return data
-        let mut pos: u64 = 0;
-        let mut mult: u64 = 1;
-        for (dimension, index) in self.dimensions.iter().rev().zip(indices.iter().rev()) {
-            pos += *index as u64 * mult;
-            mult *= *dimension as u64;
+        out.reserve(count as usize);
+        // Read in large chunks of whole elements instead of element by element
+        let mut buffer = vec![0; (READ_CHUNK as u64 / size * size).min(len) as usize];
+        let (mut pos, end) = (start, start + len);
+        while pos < end {
+            let n = (end - pos).min(buffer.len() as u64) as usize;
+            read_exact_at(&mut self.reader, pos, &mut buffer[..n])?;
+            self.endianness.swap_be(&mut buffer[..n], size as usize);
+            decode_into(self.data_type, &buffer[..n], out, lossy)?;
+            pos += n as u64;
         }
-        // Manipulate position by data type intervals and header size
-        pos *= self.data_type.get_size() as u64;
-        // Header size = 4B + 4B * dimensions
-        pos += 4 + 4 * self.dimensions.len() as u64;
-        let _res = self.reader.seek(SeekFrom::Start(pos)).map_err(|e| IdxError::IoError(e))?;
-        let mut buffer = self.data_type.create_buf();
-        self.reader.read_exact(&mut buffer).map_err(|e| IdxError::IoError(e))?;
-        IdxValue::try_from((self.data_type, buffer))
+        Ok(())
+    }
+
+    /// Fails before `len` bytes at `pos` are allocated if the stream is shorter, so a corrupt header cannot
+    /// cause a huge allocation
+    fn check_available(&mut self, pos: u64, len: u64) -> Result<(), IdxError> {
+        let expected = pos.checked_add(len).ok_or(IdxError::Overflow)?;
+        usize::try_from(len).map_err(|_| IdxError::Overflow)?;
+        let actual = self.reader.seek(SeekFrom::End(0))?;
+        if actual < expected {
+            return Err(IdxError::LengthMismatch{ expected, actual });
+        }
+        Ok(())
+    }
+
+    /// Iterates over all elements in storage order with a single buffered pass
+    pub fn iter(&mut self) -> IdxIterator<&mut R> {
+        let buffer_size = self.reader.capacity();
+        IdxIterator::new(self.reader.get_mut(), self.data_type, self.shape.dimensions().to_vec(), self.endianness, buffer_size)
+    }
+
+    /// Iterates over all elements converted to `T`, fails on values that are not exactly representable
+    pub fn iter_as<T: IdxElement>(&mut self) -> impl Iterator<Item = Result<(Vec<u32>, T), IdxError>> + '_ {
+        self.iter().map(|res| res.and_then(|(index, value)| Ok((index, T::from_value(value)?))))
     }
 }
 
+#[cfg(feature = "std")]
+impl<R: Read + Seek> IntoIterator for IdxCursor<R> {
+    type Item = Result<(Vec<u32>, IdxValue), IdxError>;
+    type IntoIter = IdxIterator<R>;
+
+    fn into_iter(self) -> IdxIterator<R> {
+        let buffer_size = self.reader.capacity();
+        IdxIterator::new(self.reader.into_inner(), self.data_type, self.shape.dimensions().to_vec(), self.endianness, buffer_size)
+    }
+}
+
+/// Cursors over files that are already in memory, e.g. uploaded in a browser, no file system is needed
+#[cfg(feature = "std")]
+impl IdxCursor<io::Cursor<Vec<u8>>> {
+    /// Takes ownership of the whole file and reads its header
+    pub fn from_bytes(data: Vec<u8>) -> Result<IdxCursor<io::Cursor<Vec<u8>>>, IdxError> {
+        IdxCursor::new(io::Cursor::new(data))
+    }
+}
+
+#[cfg(feature = "std")]
+impl<'a> IdxCursor<io::Cursor<&'a [u8]>> {
+    /// Like `from_bytes`, but borrows the file
+    pub fn from_slice(data: &'a [u8]) -> Result<IdxCursor<io::Cursor<&'a [u8]>>, IdxError> {
+        IdxCursor::new(io::Cursor::new(data))
+    }
+}
+
+/// Checks the magic number and returns the data type it stores
+fn parse_magic(magic: [u8; 4], options: &IdxOptions) -> Result<IdxDataType, IdxError> {
+    // First two bytes must be 0
+    if magic[0] != 0 || magic[1] != 0 {
+        if !options.lenient {
+            return Err(IdxError::WrongHeader{ found: magic });
+        }
+        if let Some(diagnostics) = options.diagnostics {
+            diagnostics(IdxWarning::NonZeroMagic{ found: magic });
+        }
+    }
+    // Read data type from third byte
+    IdxDataType::read(magic[2])
+}
+
+/// Parses magic number and dimension sizes from the start of an in-memory file
+pub(crate) fn parse_header(data: &[u8], options: &IdxOptions) -> Result<(IdxDataType, IdxShape), IdxError> {
+    if data.len() < 4 {
+        return Err(IdxError::LengthMismatch{ expected: 4, actual: data.len() as u64 });
+    }
+    let data_type = parse_magic([data[0], data[1], data[2], data[3]], options)?;
+    let n = data[3] as usize;
+    if (data.len() as u64) < header_len(n) {
+        return Err(IdxError::LengthMismatch{ expected: header_len(n), actual: data.len() as u64 });
+    }
+    let dimensions = data[4..header_len(n) as usize]
+        .chunks_exact(4)
+        .map(|b| options.endianness.read_u32([b[0], b[1], b[2], b[3]]))
+        .collect();
+    Ok((data_type, checked_shape(data_type, dimensions)?))
+}
+
+/// Shape of the header, fails with `Overflow` unless the payload size in bytes fits into 64 bits
+fn checked_shape(data_type: IdxDataType, dimensions: Vec<u32>) -> Result<IdxShape, IdxError> {
+    let shape = IdxShape::try_new(dimensions)?;
+    // Every byte offset in the file fits into 64 bits once the end of the payload does
+    byte_offset(shape.dimensions().len(), shape.len(), data_type.get_size() as u64)?;
+    Ok(shape)
+}
+
+/// Reads magic number and dimension sizes, leaves the reader at the start of the payload
+#[cfg(feature = "std")]
+pub(crate) fn read_header<R: Read>(reader: &mut R, options: &IdxOptions) -> Result<(IdxDataType, IdxShape), IdxError> {
+    // Read first 4 bytes to get magic number
+    let mut buf: [u8; 4] = [0; 4];
+    read_exact_at(reader, 0, &mut buf)?;
+    let data_type = parse_magic(buf, options)?;
+
+    // Number of dimensions are stored in fourth byte
+    // Read n next numbers of dimension sizes (each 32bit)
+    let n: usize = buf[3] as usize;
+    let mut dimensions: Vec<u32> = Vec::with_capacity(n);
+    for i in 0..n {
+        read_exact_at(reader, header_len(i), &mut buf)?;
+        dimensions.push(options.endianness.read_u32(buf));
+    }
+    Ok((data_type, checked_shape(data_type, dimensions)?))
+}
+
+/// Fills the buffer, failures report the stream offset the read started at
+#[cfg(feature = "std")]
+pub(crate) fn read_exact_at<R: Read>(reader: &mut R, offset: u64, buf: &mut [u8]) -> Result<(), IdxError> {
+    reader.read_exact(buf).map_err(|source| IdxError::ReadFailed{ offset, len: buf.len() as u64, source })
+}
+
+/// Header size = 4B + 4B * dimensions
+pub(crate) fn header_len(dimensions: usize) -> u64 {
+    4 + 4 * dimensions as u64
+}
+
+/// Byte position of element `element` in a file with `dimensions` dimensions, fails with `Overflow` past 64 bits
+pub(crate) fn byte_offset(dimensions: usize, element: u64, size: u64) -> Result<u64, IdxError> {
+    element.checked_mul(size).and_then(|bytes| bytes.checked_add(header_len(dimensions))).ok_or(IdxError::Overflow)
+}
+
+/// Length in bytes of `count` elements, fails with `Overflow` if a buffer this large cannot be addressed
+#[cfg(feature = "std")]
+pub(crate) fn buffer_len(count: u64, size: u64) -> Result<usize, IdxError> {
+    count.checked_mul(size).and_then(|bytes| usize::try_from(bytes).ok()).ok_or(IdxError::Overflow)
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::{buffer_len, byte_offset, Endianness, IdxCursor, IdxDataType, IdxElement, IdxError, IdxOptions};
+
+    /// Header of a big endian file without payload
+    fn header(data_type: IdxDataType, dimensions: &[u32]) -> Vec<u8> {
+        let mut data = vec![0, 0, data_type.get_code(), dimensions.len() as u8];
+        for d in dimensions {
+            data.extend_from_slice(&d.to_be_bytes());
+        }
+        data
+    }
+
+    #[test]
+    fn offset_beyond_u32() {
+        // Last double of [65536, 65536, 2] starts 64 GiB into the file
+        let last = (1u64 << 33) - 1;
+        let offset = byte_offset(3, last, 8).unwrap();
+        assert_eq!(offset, last * 8 + 16);
+        assert!(offset > u32::MAX as u64);
+    }
+
+    #[test]
+    fn offset_beyond_u64() {
+        assert!(matches!(byte_offset(1, u64::MAX, 2), Err(IdxError::Overflow)));
+        // Only adding the header overflows
+        assert!(matches!(byte_offset(255, u64::MAX / 8, 8), Err(IdxError::Overflow)));
+    }
+
+    #[test]
+    fn buffer_beyond_usize() {
+        assert!(matches!(buffer_len(u64::MAX, 2), Err(IdxError::Overflow)));
+        assert!(matches!(buffer_len(1 << 62, 8), Err(IdxError::Overflow)));
+        #[cfg(target_pointer_width = "64")]
+        assert_eq!(buffer_len(1 << 33, 8).unwrap(), 1 << 36);
+        #[cfg(target_pointer_width = "32")]
+        assert!(matches!(buffer_len(1 << 33, 8), Err(IdxError::Overflow)));
+    }
+
+    #[test]
+    fn header_beyond_u64() {
+        let res = IdxCursor::from_bytes(header(IdxDataType::Double, &[u32::MAX; 3]));
+        assert!(matches!(res, Err(IdxError::Overflow)));
+        // Elements fit into 64 bits, their bytes do not
+        let res = IdxCursor::from_bytes(header(IdxDataType::Double, &[u32::MAX, u32::MAX, 1]));
+        assert!(matches!(res, Err(IdxError::Overflow)));
+    }
+
+    #[test]
+    fn unvalidated_matches_get() {
+        // Big endian shorts of shape [2, 3, 4] holding their flat index
+        let mut data = header(IdxDataType::Short, &[2, 3, 4]);
+        for i in 0..24i16 {
+            data.extend_from_slice(&(i * 100 - 1000).to_be_bytes());
+        }
+        let mut cursor = IdxCursor::from_bytes(data).unwrap();
+        for i in 0..24u32 {
+            let indices = [i / 12, i / 4 % 3, i % 4];
+            let value = i16::from_value(cursor.get(&indices).unwrap()).unwrap();
+            assert_eq!(value, i as i16 * 100 - 1000);
+            assert_eq!(i16::from_value(cursor.get_unvalidated(&indices).unwrap()).unwrap(), value);
+        }
+
+        // Little endian ints of shape [3, 2]
+        let mut data = vec![0, 0, IdxDataType::Int.get_code(), 2];
+        data.extend_from_slice(&3u32.to_le_bytes());
+        data.extend_from_slice(&2u32.to_le_bytes());
+        for i in 0..6i32 {
+            data.extend_from_slice(&(i - 3).wrapping_mul(0x0102_0304).to_le_bytes());
+        }
+        let options = IdxOptions { endianness: Endianness::Little, ..IdxOptions::default() };
+        let mut cursor = IdxCursor::with_options(std::io::Cursor::new(data), options).unwrap();
+        for i in 0..6u32 {
+            let indices = [i / 2, i % 2];
+            let value = i32::from_value(cursor.get(&indices).unwrap()).unwrap();
+            assert_eq!(value, (i as i32 - 3).wrapping_mul(0x0102_0304));
+            assert_eq!(i32::from_value(cursor.get_unvalidated(&indices).unwrap()).unwrap(), value);
+        }
+    }
+
+    #[test]
+    fn payload_limit_beyond_u32() {
+        let options = IdxOptions { max_payload_bytes: Some(1 << 20), ..IdxOptions::default() };
+        let data = header(IdxDataType::Double, &[65536, 65536, 2]);
+        let res = IdxCursor::with_options(std::io::Cursor::new(data.clone()), options);
+        assert!(matches!(res, Err(IdxError::FileTooLarge{ size, .. }) if size == 1 << 36));
+        // Without a limit the header is accepted and reading past the end fails instead of panicking
+        let mut cursor = IdxCursor::from_bytes(data).unwrap();
+        assert_eq!(cursor.header().element_count, 1 << 33);
+        assert!(cursor.get(&[65535, 65535, 1]).is_err());
+    }
+}
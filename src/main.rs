@@ -0,0 +1,37 @@
+mod cli;
+
+use std::env;
+use std::process;
+
+use cli::CliError;
+
+fn main() {
+    let mut args = env::args().skip(1);
+    let command = args.next();
+    let res = match command.as_deref() {
+        Some("inspect")    => cli::inspect::run(args),
+        Some("dump")       => cli::dump::run(args),
+        Some("convert")    => cli::convert::run(args),
+        Some("split")      => cli::split::run(args),
+        Some("extract")    => cli::extract::run(args),
+        Some("batch")      => cli::batch::run(args),
+        Some("verify")     => cli::verify::run(args),
+        Some("concat")     => cli::concat::run(args),
+        Some("bundle")     => cli::bundle::run(args),
+        Some("stats")      => cli::stats::run(args),
+        Some("diff")       => cli::diff::run(args),
+        Some("dupes")      => cli::dupes::run(args),
+        Some("relabel")    => cli::relabel::run(args),
+        Some("quantize")   => cli::quantize::run(args),
+        Some("dequantize") => cli::quantize::run_dequantize(args),
+        Some("help") | Some("--help") | Some("-h") | None => {
+            print!("{}", cli::USAGE);
+            Ok(())
+        }
+        Some(other) => Err(CliError(format!("unknown command '{}', see 'idxrs help'", other))),
+    };
+    if let Err(e) = res {
+        eprintln!("error: {}", e);
+        process::exit(1);
+    }
+}
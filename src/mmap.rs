@@ -0,0 +1,102 @@
+use std::convert::TryFrom;
+use std::fs::File;
+use std::io;
+use std::ops::Deref;
+use std::os::raw::c_int;
+use std::os::raw::c_void;
+use std::os::unix::io::AsRawFd;
+use std::path::Path;
+use std::ptr;
+use std::slice;
+
+use crate::IdxBytes;
+use crate::IdxError;
+
+// off_t of the C library, 32-bit Linux only gets 64-bit offsets through mmap64
+#[cfg(all(target_os = "linux", target_pointer_width = "32"))]
+#[allow(non_camel_case_types)]
+type off_t = i32;
+#[cfg(not(all(target_os = "linux", target_pointer_width = "32")))]
+#[allow(non_camel_case_types)]
+type off_t = i64;
+
+extern "C" {
+    fn mmap(addr: *mut c_void, len: usize, prot: c_int, flags: c_int, fd: c_int, offset: off_t) -> *mut c_void;
+    fn munmap(addr: *mut c_void, len: usize) -> c_int;
+}
+
+const PROT_READ: c_int = 1;
+const MAP_PRIVATE: c_int = 2;
+
+/// Read-only memory map of a whole file
+pub struct Mmap {
+    ptr: *mut c_void,
+    len: usize,
+}
+
+// The mapping is private and never written to, so it can be shared between threads
+unsafe impl Send for Mmap {}
+unsafe impl Sync for Mmap {}
+
+impl Mmap {
+    /// Maps the whole file, fails with `FileTooLarge` if it does not fit into the address space
+    ///
+    /// # Safety
+    ///
+    /// The file must not be truncated or written to while the map is alive. The map reads the file directly, so a
+    /// truncated file makes reads fail with SIGBUS and writes change the contents of the borrowed `&[u8]`.
+    pub unsafe fn map(file: &File) -> Result<Mmap, IdxError> {
+        let size = file.metadata()?.len();
+        let len = usize::try_from(size).map_err(|_| IdxError::FileTooLarge{ size, max: usize::MAX as u64 })?;
+        // Empty mappings are rejected by the OS, nothing has to be mapped for them anyway
+        if len == 0 {
+            return Ok(Mmap { ptr: ptr::null_mut(), len });
+        }
+        let ptr = mmap(ptr::null_mut(), len, PROT_READ, MAP_PRIVATE, file.as_raw_fd(), 0);
+        // MAP_FAILED is defined as (void *) -1
+        if ptr as isize == -1 {
+            return Err(io::Error::last_os_error().into());
+        }
+        Ok(Mmap { ptr, len })
+    }
+}
+
+impl Deref for Mmap {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        if self.len == 0 {
+            return &[];
+        }
+        unsafe { slice::from_raw_parts(self.ptr as *const u8, self.len) }
+    }
+}
+
+impl AsRef<[u8]> for Mmap {
+    fn as_ref(&self) -> &[u8] {
+        self
+    }
+}
+
+impl Drop for Mmap {
+    fn drop(&mut self) {
+        if self.len != 0 {
+            unsafe { munmap(self.ptr, self.len) };
+        }
+    }
+}
+
+/// IDX file read through a memory map, records borrowed with `get_record` or `raw_record_bytes` live as long as the map
+pub type IdxMmap = IdxBytes<Mmap>;
+
+impl IdxBytes<Mmap> {
+    /// Memory-maps the file and parses its header
+    ///
+    /// # Safety
+    ///
+    /// Same as `Mmap::map`, the file must not be truncated or written to while the `IdxMmap` is alive.
+    pub unsafe fn open<P: AsRef<Path>>(path: P) -> Result<IdxMmap, IdxError> {
+        let file = File::open(path)?;
+        IdxBytes::new(Mmap::map(&file)?)
+    }
+}
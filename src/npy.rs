@@ -0,0 +1,229 @@
+//! Conversion between IDX files and NumPy `.npy` files
+
+use std::io;
+use std::io::Read;
+use std::io::Seek;
+use std::io::SeekFrom;
+use std::io::Write;
+
+use crate::header_len;
+use crate::writer::write_header;
+use crate::Endianness;
+use crate::IdxCursor;
+use crate::IdxDataType;
+use crate::IdxError;
+use crate::IdxShape;
+
+const MAGIC: &[u8; 6] = b"\x93NUMPY";
+
+/// Longest header `read_npy` accepts, NumPy's own default limit for reading
+const MAX_HEADER_LEN: usize = 10_000;
+
+fn invalid(msg: &str) -> IdxError {
+    IdxError::IoError(io::Error::new(io::ErrorKind::InvalidData, msg.to_string()))
+}
+
+/// NumPy type string of the data type in the byte order of the file
+fn descr(data_type: IdxDataType, endianness: Endianness) -> String {
+    let kind = match data_type {
+        IdxDataType::UnsignedByte => return "|u1".into(),
+        IdxDataType::SignedByte   => return "|i1".into(),
+        IdxDataType::Short        => "i2",
+        IdxDataType::Int          => "i4",
+        IdxDataType::Float        => "f4",
+        IdxDataType::Double       => "f8",
+        #[cfg(feature = "extensions")]
+        IdxDataType::UnsignedShort => "u2",
+        #[cfg(feature = "extensions")]
+        IdxDataType::UnsignedInt  => "u4",
+        #[cfg(feature = "extensions")]
+        IdxDataType::Long         => "i8",
+        #[cfg(feature = "extensions")]
+        IdxDataType::UnsignedLong => "u8",
+    };
+    let order = if endianness == Endianness::Little { '<' } else { '>' };
+    format!("{}{}", order, kind)
+}
+
+/// Writes the whole payload of the cursor as `.npy` with matching dtype and shape
+///
+/// Fails with an `InvalidInput` error if the header does not fit into the 65535 bytes of a version 1 file.
+pub fn write_npy<R: Read + Seek, W: Write>(cursor: &mut IdxCursor<R>, mut writer: W) -> Result<(), IdxError> {
+    let dimensions = cursor.dimensions();
+    let shape = match dimensions.len() {
+        1 => format!("({},)", dimensions[0]),
+        _ => format!("({})", dimensions.iter().map(|d| d.to_string()).collect::<Vec<_>>().join(", ")),
+    };
+    let mut header = format!("{{'descr': '{}', 'fortran_order': False, 'shape': {}, }}", descr(cursor.data_type, cursor.endianness), shape);
+    // Header is padded with spaces and ends in a newline so the data starts 64 byte aligned
+    let total = MAGIC.len() + 4 + header.len() + 1;
+    header.push_str(&" ".repeat((64 - total % 64) % 64));
+    header.push('\n');
+    if header.len() > u16::MAX as usize {
+        let msg = format!("npy header of {} bytes exceeds the limit of {} bytes", header.len(), u16::MAX);
+        return Err(IdxError::IoError(io::Error::new(io::ErrorKind::InvalidInput, msg)));
+    }
+
+    writer.write_all(MAGIC)?;
+    writer.write_all(&[1, 0])?;
+    writer.write_all(&(header.len() as u16).to_le_bytes())?;
+    writer.write_all(header.as_bytes())?;
+    // Payload is copied as is since the dtype declares the byte order of the file
+    let payload = cursor.header().payload_bytes;
+    cursor.reader.seek(SeekFrom::Start(header_len(dimensions.len())))?;
+    let copied = io::copy(&mut (&mut cursor.reader).take(payload), &mut writer)?;
+    if copied != payload {
+        return Err(IdxError::LengthMismatch{ expected: payload, actual: copied });
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+/// Value of a key in the header dictionary, up to the next top level comma or closing brace
+fn dict_value<'a>(header: &'a str, key: &str) -> Result<&'a str, IdxError> {
+    let pattern = format!("'{}':", key);
+    let start = header.find(&pattern).ok_or_else(|| invalid("npy header is missing a key"))? + pattern.len();
+    let rest = header[start..].trim_start();
+    let end = if rest.starts_with('(') {
+        rest.find(')').map(|i| i + 1)
+    } else {
+        rest.find([',', '}'])
+    };
+    Ok(rest[..end.ok_or_else(|| invalid("malformed npy header"))?].trim())
+}
+
+/// Reads a C ordered `.npy` file and writes it as IDX file
+pub fn read_npy<R: Read, W: Write + Seek>(mut reader: R, mut writer: W) -> Result<W, IdxError> {
+    let mut magic = [0; 8];
+    reader.read_exact(&mut magic)?;
+    if &magic[..6] != MAGIC {
+        return Err(invalid("not a npy file"));
+    }
+    // Version 1 stores the header length in 2 bytes, later versions in 4
+    let len = if magic[6] == 1 {
+        let mut buf = [0; 2];
+        reader.read_exact(&mut buf)?;
+        u16::from_le_bytes(buf) as usize
+    } else {
+        let mut buf = [0; 4];
+        reader.read_exact(&mut buf)?;
+        u32::from_le_bytes(buf) as usize
+    };
+    if len > MAX_HEADER_LEN {
+        return Err(invalid(&format!("npy header of {} bytes exceeds the limit of {} bytes", len, MAX_HEADER_LEN)));
+    }
+    // The buffer only grows with the bytes that actually arrive
+    let mut header = Vec::new();
+    (&mut reader).take(len as u64).read_to_end(&mut header)?;
+    if header.len() < len {
+        return Err(invalid("truncated npy header"));
+    }
+    let header = String::from_utf8(header).map_err(|_| invalid("npy header is not valid text"))?;
+
+    if dict_value(&header, "fortran_order")? != "False" {
+        return Err(invalid("fortran ordered npy files are not supported"));
+    }
+    let descr = dict_value(&header, "descr")?.trim_matches(['\'', '"']);
+    let (order, kind) = descr.split_at(1.min(descr.len()));
+    let data_type = match kind {
+        "u1" => IdxDataType::UnsignedByte,
+        "i1" => IdxDataType::SignedByte,
+        "i2" => IdxDataType::Short,
+        "i4" => IdxDataType::Int,
+        "f4" => IdxDataType::Float,
+        "f8" => IdxDataType::Double,
+        #[cfg(feature = "extensions")]
+        "u2" => IdxDataType::UnsignedShort,
+        #[cfg(feature = "extensions")]
+        "u4" => IdxDataType::UnsignedInt,
+        #[cfg(feature = "extensions")]
+        "i8" => IdxDataType::Long,
+        #[cfg(feature = "extensions")]
+        "u8" => IdxDataType::UnsignedLong,
+        _ => return Err(invalid(&format!("unsupported npy dtype '{}'", descr))),
+    };
+    let little_endian = match order {
+        "<" => true,
+        ">" | "|" => false,
+        "=" => cfg!(target_endian = "little"),
+        _ => return Err(invalid("malformed npy dtype")),
+    };
+    let mut dimensions = Vec::new();
+    for d in dict_value(&header, "shape")?.trim_matches(['(', ')']).split(',') {
+        let d = d.trim();
+        if !d.is_empty() {
+            dimensions.push(d.parse::<u32>().map_err(|_| invalid("npy dimension does not fit into 32 bits"))?);
+        }
+    }
+
+    let shape = IdxShape::try_new(dimensions)?;
+    let size = data_type.get_size() as usize;
+    let mut remaining = shape.len().checked_mul(size as u64).ok_or(IdxError::Overflow)?;
+    write_header(&mut writer, data_type, shape.dimensions(), Endianness::Big)?;
+    let mut buffer = vec![0; (1 << 16) / size * size];
+    while remaining > 0 {
+        let n = remaining.min(buffer.len() as u64) as usize;
+        reader.read_exact(&mut buffer[..n])?;
+        if little_endian {
+            for element in buffer[..n].chunks_exact_mut(size) {
+                element.reverse();
+            }
+        }
+        writer.write_all(&buffer[..n])?;
+        remaining -= n as u64;
+    }
+    writer.flush()?;
+    Ok(writer)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io;
+
+    use super::read_npy;
+    use crate::IdxError;
+
+    /// Version 1 file with the header, padded like NumPy does, and no payload
+    fn npy(header: &str) -> Vec<u8> {
+        let mut header = header.to_string();
+        header.push_str(&" ".repeat((64 - (10 + header.len() + 1) % 64) % 64));
+        header.push('\n');
+        let mut data = b"\x93NUMPY\x01\x00".to_vec();
+        data.extend_from_slice(&(header.len() as u16).to_le_bytes());
+        data.extend_from_slice(header.as_bytes());
+        data
+    }
+
+    #[test]
+    fn shape_beyond_u64() {
+        let data = npy("{'descr': '<f8', 'fortran_order': False, 'shape': (4294967295, 4294967295, 4294967295), }");
+        let res = read_npy(&data[..], io::Cursor::new(Vec::new()));
+        assert!(matches!(res, Err(IdxError::Overflow)));
+        // Elements fit into 64 bits, their bytes do not
+        let data = npy("{'descr': '<f8', 'fortran_order': False, 'shape': (4294967295, 4294967295, 1), }");
+        let res = read_npy(&data[..], io::Cursor::new(Vec::new()));
+        assert!(matches!(res, Err(IdxError::Overflow)));
+    }
+
+    #[test]
+    fn header_length_limit() {
+        // Version 2 header announcing 4 GiB of header
+        let mut data = b"\x93NUMPY\x02\x00".to_vec();
+        data.extend_from_slice(&u32::MAX.to_le_bytes());
+        data.extend_from_slice(b"{'descr': '<f8', ");
+        let err = read_npy(&data[..], io::Cursor::new(Vec::new())).unwrap_err();
+        assert!(err.to_string().contains("exceeds the limit"), "{}", err);
+        // Header shorter than its announced length
+        let mut data = b"\x93NUMPY\x02\x00".to_vec();
+        data.extend_from_slice(&100u32.to_le_bytes());
+        data.extend_from_slice(b"{'descr': '<f8', ");
+        let err = read_npy(&data[..], io::Cursor::new(Vec::new())).unwrap_err();
+        assert!(err.to_string().contains("truncated"), "{}", err);
+    }
+
+    #[test]
+    fn truncated_payload() {
+        let data = npy("{'descr': '<i2', 'fortran_order': False, 'shape': (3, 2), }");
+        assert!(read_npy(&data[..], io::Cursor::new(Vec::new())).is_err());
+    }
+}
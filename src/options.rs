@@ -0,0 +1,86 @@
+use core::fmt;
+
+/// Byte order of dimension sizes and elements, the IDX format itself is big endian
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum Endianness {
+    #[default]
+    Big,
+    /// Non standard little endian variant, only the magic number is unchanged
+    Little,
+}
+
+impl Endianness {
+    pub(crate) fn read_u32(self, bytes: [u8; 4]) -> u32 {
+        match self {
+            Endianness::Big    => u32::from_be_bytes(bytes),
+            Endianness::Little => u32::from_le_bytes(bytes),
+        }
+    }
+
+    #[cfg(feature = "std")]
+    pub(crate) fn u32_bytes(self, value: u32) -> [u8; 4] {
+        match self {
+            Endianness::Big    => value.to_be_bytes(),
+            Endianness::Little => value.to_le_bytes(),
+        }
+    }
+
+    /// Converts elements of `size` bytes between this byte order and big endian, in either direction
+    #[cfg(feature = "std")]
+    pub(crate) fn swap_be(self, bytes: &mut [u8], size: usize) {
+        if self == Endianness::Little && size > 1 {
+            for element in bytes.chunks_exact_mut(size) {
+                element.reverse();
+            }
+        }
+    }
+}
+
+/// Problem in a file that lenient parsing accepts instead of failing, see `IdxOptions::lenient`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum IdxWarning {
+    /// First two bytes of the magic number are not zero
+    NonZeroMagic{ found: [u8; 4] },
+}
+
+impl fmt::Display for IdxWarning {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            IdxWarning::NonZeroMagic{ found } => write!(f, "magic number {:02x} {:02x} {:02x} {:02x} does not start with 00 00", found[0], found[1], found[2], found[3]),
+        }
+    }
+}
+
+/// Settings for opening an IDX file
+#[derive(Clone, Debug)]
+pub struct IdxOptions {
+    /// Checks that the stream length is exactly header size plus payload size
+    pub validate_length: bool,
+    /// Byte order the file was written in
+    pub endianness: Endianness,
+    /// Only accepts the data types of the IDX specification, rejecting vendor extensions
+    pub strict: bool,
+    /// Rejects files whose header announces a larger payload with `FileTooLarge`
+    pub max_payload_bytes: Option<u64>,
+    /// Ignores the first two bytes of the magic number instead of failing with `WrongHeader` if they are not zero
+    pub lenient: bool,
+    /// Receives every problem that lenient parsing accepted
+    pub diagnostics: Option<fn(IdxWarning)>,
+    /// Size of the read buffer of the cursor in bytes, 0 reads straight from the reader for sources that are in
+    /// memory or buffered already
+    pub buffer_size: usize,
+}
+
+impl Default for IdxOptions {
+    fn default() -> IdxOptions {
+        IdxOptions {
+            validate_length: false,
+            endianness: Endianness::Big,
+            strict: false,
+            max_payload_bytes: None,
+            lenient: false,
+            diagnostics: None,
+            buffer_size: 64 * 1024,
+        }
+    }
+}
@@ -0,0 +1,43 @@
+use std::thread;
+
+use crate::decode_into;
+use crate::IdxDataType;
+use crate::IdxElement;
+use crate::IdxError;
+
+/// Payloads below this size are decoded on the calling thread
+const PARALLEL_MIN: u64 = 1 << 20;
+
+fn threads() -> usize {
+    thread::available_parallelism().map_or(1, |n| n.get())
+}
+
+/// Parallel decoding only pays off for large payloads and more than one core
+pub(crate) fn use_parallel(payload_bytes: u64) -> bool {
+    payload_bytes >= PARALLEL_MIN && threads() > 1
+}
+
+/// Splits payload into one chunk of whole elements per thread and decodes the chunks in parallel
+pub(crate) fn decode_parallel<T: IdxElement>(data_type: IdxDataType, bytes: &[u8], lossy: bool) -> Result<Vec<T>, IdxError> {
+    let size = data_type.get_size() as usize;
+    let threads = threads();
+    let elements = bytes.len() / size;
+    let chunk = elements.div_ceil(threads).max(1) * size;
+    let parts = thread::scope(|scope| {
+        let handles: Vec<_> = bytes
+            .chunks(chunk)
+            .map(|part| {
+                scope.spawn(move || {
+                    let mut out: Vec<T> = Vec::with_capacity(part.len() / size);
+                    decode_into(data_type, part, &mut out, lossy).map(|_| out)
+                })
+            })
+            .collect();
+        handles.into_iter().map(|h| h.join().expect("decoder thread panicked")).collect::<Vec<_>>()
+    });
+    let mut out = Vec::with_capacity(elements);
+    for part in parts {
+        out.extend(part?);
+    }
+    Ok(out)
+}
@@ -0,0 +1,67 @@
+use std::io;
+use std::io::Write;
+
+use crate::checksum::adler32;
+use crate::checksum::crc32;
+use crate::dataset::Image;
+
+const SIGNATURE: [u8; 8] = [0x89, b'P', b'N', b'G', b'\r', b'\n', 0x1a, b'\n'];
+// Largest payload of an uncompressed deflate block
+const STORED_BLOCK: usize = 65535;
+
+fn write_chunk<W: Write>(writer: &mut W, kind: &[u8; 4], data: &[u8]) -> io::Result<()> {
+    writer.write_all(&(data.len() as u32).to_be_bytes())?;
+    let mut crc_data = Vec::with_capacity(4 + data.len());
+    crc_data.extend_from_slice(kind);
+    crc_data.extend_from_slice(data);
+    writer.write_all(&crc_data)?;
+    writer.write_all(&crc32(&crc_data).to_be_bytes())
+}
+
+/// Wraps data into a zlib stream made of uncompressed deflate blocks
+fn zlib_stored(data: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(data.len() + data.len() / STORED_BLOCK * 5 + 11);
+    out.extend_from_slice(&[0x78, 0x01]);
+    let mut blocks = data.chunks(STORED_BLOCK).peekable();
+    if blocks.peek().is_none() {
+        out.extend_from_slice(&[1, 0, 0, 0xff, 0xff]);
+    }
+    while let Some(block) = blocks.next() {
+        let last = blocks.peek().is_none();
+        let len = block.len() as u16;
+        out.push(last as u8);
+        out.extend_from_slice(&len.to_le_bytes());
+        out.extend_from_slice(&(!len).to_le_bytes());
+        out.extend_from_slice(block);
+    }
+    out.extend_from_slice(&adler32(data).to_be_bytes());
+    out
+}
+
+impl Image {
+    /// Encodes image as 8 bit grayscale PNG, pixel data is stored uncompressed
+    pub fn write_png<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        writer.write_all(&SIGNATURE)?;
+        let mut ihdr = Vec::with_capacity(13);
+        ihdr.extend_from_slice(&self.cols().to_be_bytes());
+        ihdr.extend_from_slice(&self.rows().to_be_bytes());
+        // Bit depth 8, grayscale, deflate, adaptive filtering, no interlace
+        ihdr.extend_from_slice(&[8, 0, 0, 0, 0]);
+        write_chunk(&mut writer, b"IHDR", &ihdr)?;
+
+        // Every scanline starts with its filter type, 0 leaves the pixels unfiltered
+        let mut raw = Vec::with_capacity(self.pixels().len() + self.rows() as usize);
+        for row in self.pixels().chunks(self.cols().max(1) as usize) {
+            raw.push(0);
+            raw.extend_from_slice(row);
+        }
+        write_chunk(&mut writer, b"IDAT", &zlib_stored(&raw))?;
+        write_chunk(&mut writer, b"IEND", &[])
+    }
+
+    /// Encodes image as binary 8 bit PGM (P5)
+    pub fn write_pgm<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        write!(writer, "P5\n{} {}\n255\n", self.cols(), self.rows())?;
+        writer.write_all(self.pixels())
+    }
+}
@@ -0,0 +1,66 @@
+use std::io::Read;
+use std::io::Seek;
+use std::sync::mpsc;
+use std::sync::mpsc::Receiver;
+use std::thread;
+
+use crate::IdxCursor;
+use crate::IdxError;
+use crate::IdxSlice;
+
+/// Records read ahead on a background thread, created by `IdxCursor::prefetching`
+///
+/// Dropping it stops the reader thread once its current record is read.
+pub struct Prefetch {
+    receiver: Receiver<Result<IdxSlice, IdxError>>,
+    remaining: usize,
+}
+
+impl<R: Read + Seek + Send + 'static> IdxCursor<R> {
+    /// Moves the cursor to a reader thread that reads the records from the position of `next_record` on, holding
+    /// up to `queue_depth` records that are not yet consumed, so reading overlaps with the work on earlier records
+    ///
+    /// ```
+    /// use idxrs::{testing, IdxCursor, IdxDataType};
+    ///
+    /// let mut cursor = IdxCursor::from_bytes(testing::generate_random_idx(IdxDataType::Float, &[100, 8, 8], 5)?)?;
+    /// let expected = cursor.get_record(&[99])?.as_bytes().to_vec();
+    /// let last = cursor.prefetching(16)?.last().expect("file has records")?;
+    /// assert_eq!(last.as_bytes(), &expected[..]);
+    /// # Ok::<(), idxrs::IdxError>(())
+    /// ```
+    pub fn prefetching(mut self, queue_depth: usize) -> Result<Prefetch, IdxError> {
+        let remaining = (self.num_records() - self.record_position()) as usize;
+        let (sender, receiver) = mpsc::sync_channel(queue_depth);
+        thread::Builder::new().name("idxrs-prefetch".into()).spawn(move || {
+            loop {
+                let res = match self.next_record() {
+                    Ok(Some(record)) => Ok(record),
+                    Ok(None) => break,
+                    Err(e) => Err(e),
+                };
+                // Stop after the first error like the other iterators, or once the receiver is gone
+                let failed = res.is_err();
+                if sender.send(res).is_err() || failed {
+                    break;
+                }
+            }
+        })?;
+        Ok(Prefetch { receiver, remaining })
+    }
+}
+
+impl Iterator for Prefetch {
+    type Item = Result<IdxSlice, IdxError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let res = self.receiver.recv().ok()?;
+        self.remaining = self.remaining.saturating_sub(1);
+        Some(res)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        // An error ends iteration early, so only the upper bound is known
+        (0, Some(self.remaining))
+    }
+}
@@ -0,0 +1,65 @@
+use std::io;
+use std::io::Read;
+use std::io::Seek;
+use std::io::SeekFrom;
+
+/// Reader that reports its position after every read, for progress bars over long operations
+///
+/// Any cursor operation can be observed by opening the cursor on this reader:
+///
+/// ```no_run
+/// use idxrs::IdxCursor;
+/// use idxrs::IdxProgress;
+/// use std::fs::File;
+///
+/// let reader = IdxProgress::new(File::open("train-images.idx3-ubyte")?, |pos, len| {
+///     eprint!("\r{:3}%", pos * 100 / len.max(1));
+/// })?;
+/// let (pixels, _) = IdxCursor::new(reader)?.to_vec::<u8>()?;
+/// # Ok::<(), idxrs::IdxError>(())
+/// ```
+pub struct IdxProgress<R: Read + Seek, F: FnMut(u64, u64)> {
+    reader: R,
+    callback: F,
+    pos: u64,
+    len: u64,
+}
+
+impl<R: Read + Seek, F: FnMut(u64, u64)> IdxProgress<R, F> {
+    /// Wraps reader, `callback` receives the position after each read and the stream length
+    pub fn new(mut reader: R, callback: F) -> io::Result<IdxProgress<R, F>> {
+        let pos = reader.stream_position()?;
+        let len = reader.seek(SeekFrom::End(0))?;
+        reader.seek(SeekFrom::Start(pos))?;
+        Ok(IdxProgress { reader, callback, pos, len })
+    }
+
+    /// Length of the stream when it was wrapped
+    pub fn len(&self) -> u64 {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+}
+
+impl<R: Read + Seek, F: FnMut(u64, u64)> Read for IdxProgress<R, F> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = self.reader.read(buf)?;
+        self.pos += n as u64;
+        (self.callback)(self.pos, self.len);
+        Ok(n)
+    }
+}
+
+impl<R: Read + Seek, F: FnMut(u64, u64)> Seek for IdxProgress<R, F> {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        self.pos = self.reader.seek(pos)?;
+        Ok(self.pos)
+    }
+}
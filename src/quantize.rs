@@ -0,0 +1,157 @@
+//! Affine quantization of float files to u8 and back, `value = scale * (q - zero_point)`
+//!
+//! ```
+//! use std::io;
+//! use idxrs::quantize::{self, QuantParams};
+//! use idxrs::{IdxCursor, IdxWriter};
+//!
+//! let values = [-1.0f32, 0.0, 0.5, 1.0];
+//! let data = IdxWriter::write_array(io::Cursor::new(Vec::new()), &[4], &values)?.into_inner();
+//! let mut cursor = IdxCursor::from_bytes(data)?;
+//! let (bytes, quantization) = quantize::quantize(&mut cursor, QuantParams::MinMax, io::Cursor::new(Vec::new()))?;
+//! assert_eq!(quantization.zero_point, 128);
+//!
+//! let mut bytes = IdxCursor::from_bytes(bytes.into_inner())?;
+//! let restored = quantize::dequantize(&mut bytes, quantization, io::Cursor::new(Vec::new()))?;
+//! let (restored, _) = IdxCursor::from_bytes(restored.into_inner())?.to_vec::<f32>()?;
+//! for (a, b) in values.iter().zip(&restored) {
+//!     assert!((a - b).abs() <= quantization.scale as f32);
+//! }
+//! # Ok::<(), idxrs::IdxError>(())
+//! ```
+
+use std::io;
+use std::io::Read;
+use std::io::Seek;
+use std::io::Write;
+
+use crate::writer::write_header;
+use crate::Endianness;
+use crate::IdxCursor;
+use crate::IdxDataType;
+use crate::IdxError;
+
+/// Bytes collected before they are passed on to the writer
+const WRITE_CHUNK: usize = 1 << 16;
+
+/// How `quantize` chooses the range of values that is spread over 0..=255
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum QuantParams {
+    /// From the smallest to the largest finite value of the file, found in a first pass
+    MinMax,
+    /// From `min` to `max`, values outside are clamped
+    Range{ min: f64, max: f64 },
+    /// Uses a known scale and zero point, e.g. the ones of the training set
+    Fixed(Quantization),
+}
+
+/// Scale and zero point of a quantized file, saved next to it as JSON
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Quantization {
+    pub scale: f64,
+    /// Byte that stands for 0.0
+    pub zero_point: u8,
+}
+
+impl Quantization {
+    /// Spreads `min..=max` over 0..=255, the range is widened to include 0 so that 0.0 is stored exactly
+    pub fn from_range(min: f64, max: f64) -> Quantization {
+        let (min, max) = (min.min(0.0), max.max(0.0));
+        // A range of only 0 has no width, any scale stores it exactly
+        let scale = if max > min { (max - min) / 255.0 } else { 1.0 };
+        let zero_point = (-min / scale).round().clamp(0.0, 255.0) as u8;
+        Quantization { scale, zero_point }
+    }
+
+    /// Nearest byte, NaN becomes the zero point
+    pub fn quantize(&self, value: f64) -> u8 {
+        if value.is_nan() {
+            return self.zero_point;
+        }
+        (value / self.scale + self.zero_point as f64).round().clamp(0.0, 255.0) as u8
+    }
+
+    pub fn dequantize(&self, q: u8) -> f64 {
+        self.scale * (q as f64 - self.zero_point as f64)
+    }
+
+    /// Sidecar like `{"scale":0.00392156862745098,"zero_point":0}`, the scale reads back exactly
+    pub fn to_json(&self) -> String {
+        format!("{{\"scale\":{:?},\"zero_point\":{}}}", self.scale, self.zero_point)
+    }
+
+    /// Reads the sidecar written by `to_json`, other keys are ignored
+    pub fn from_json(json: &str) -> Result<Quantization, IdxError> {
+        let scale: f64 = json_number(json, "scale")?.parse().map_err(|_| invalid("scale is not a number"))?;
+        let zero_point = json_number(json, "zero_point")?.parse().map_err(|_| invalid("zero_point is not a byte"))?;
+        if !(scale.is_finite() && scale > 0.0) {
+            return Err(invalid("scale has to be positive"));
+        }
+        Ok(Quantization { scale, zero_point })
+    }
+}
+
+fn invalid(msg: &str) -> IdxError {
+    IdxError::IoError(io::Error::new(io::ErrorKind::InvalidData, msg.to_string()))
+}
+
+/// Text of the value of a top level key, up to the next comma or closing brace
+fn json_number<'a>(json: &'a str, key: &str) -> Result<&'a str, IdxError> {
+    let pattern = format!("\"{}\"", key);
+    let start = json.find(&pattern).ok_or_else(|| invalid(&format!("quantization is missing \"{}\"", key)))? + pattern.len();
+    let rest = json[start..].trim_start().strip_prefix(':').ok_or_else(|| invalid("malformed quantization"))?;
+    let end = rest.find([',', '}']).ok_or_else(|| invalid("malformed quantization"))?;
+    Ok(rest[..end].trim())
+}
+
+/// Writes every value as a byte of a big endian u8 file with the same dimensions, returns the writer and the
+/// quantization needed to read the values back
+pub fn quantize<R: Read + Seek, W: Write + Seek>(cursor: &mut IdxCursor<R>, params: QuantParams, mut writer: W) -> Result<(W, Quantization), IdxError> {
+    let quantization = match params {
+        QuantParams::Fixed(quantization) => quantization,
+        QuantParams::Range{ min, max } => Quantization::from_range(min, max),
+        QuantParams::MinMax => {
+            let (mut min, mut max) = (f64::INFINITY, f64::NEG_INFINITY);
+            for res in cursor.iter() {
+                let v = res?.1.as_f64();
+                if v.is_finite() {
+                    min = min.min(v);
+                    max = max.max(v);
+                }
+            }
+            // A file without finite values only holds zeros
+            if min > max { Quantization::from_range(0.0, 0.0) } else { Quantization::from_range(min, max) }
+        }
+    };
+    write_header(&mut writer, IdxDataType::UnsignedByte, cursor.dimensions(), Endianness::Big)?;
+    let mut bytes = Vec::with_capacity(WRITE_CHUNK);
+    for res in cursor.iter() {
+        bytes.push(quantization.quantize(res?.1.as_f64()));
+        if bytes.len() == WRITE_CHUNK {
+            writer.write_all(&bytes)?;
+            bytes.clear();
+        }
+    }
+    writer.write_all(&bytes)?;
+    writer.flush()?;
+    Ok((writer, quantization))
+}
+
+/// Writes the bytes of a u8 file as a big endian f32 file with the same dimensions
+pub fn dequantize<R: Read + Seek, W: Write + Seek>(cursor: &mut IdxCursor<R>, quantization: Quantization, mut writer: W) -> Result<W, IdxError> {
+    if cursor.dtype() != IdxDataType::UnsignedByte {
+        return Err(IdxError::DataTypeMismatch{ expected: IdxDataType::UnsignedByte, found: cursor.dtype() });
+    }
+    write_header(&mut writer, IdxDataType::Float, cursor.dimensions(), Endianness::Big)?;
+    let mut bytes = Vec::with_capacity(WRITE_CHUNK);
+    for res in cursor.iter_as::<u8>() {
+        bytes.extend_from_slice(&(quantization.dequantize(res?.1) as f32).to_be_bytes());
+        if bytes.len() >= WRITE_CHUNK {
+            writer.write_all(&bytes)?;
+            bytes.clear();
+        }
+    }
+    writer.write_all(&bytes)?;
+    writer.flush()?;
+    Ok(writer)
+}
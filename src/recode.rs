@@ -0,0 +1,138 @@
+use std::io::Read;
+use std::io::Seek;
+use std::io::SeekFrom;
+use std::io::Write;
+
+use crate::buffer_len;
+use crate::read_exact_at;
+use crate::writer::write_header;
+use crate::Endianness;
+use crate::IdxCursor;
+use crate::IdxDataType;
+use crate::IdxElement;
+use crate::IdxError;
+use crate::IdxValue;
+
+/// Bytes of read and converted values `recode_with` holds at a time
+const DEFAULT_CHUNK: usize = 1 << 20;
+
+/// How `recode_with` converts values that the target data type cannot hold exactly
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum CastPolicy {
+    /// Like an `as` cast, floats are truncated towards zero, out of range values saturate and NaN becomes 0
+    #[default]
+    Truncate,
+    /// Like `Truncate`, but floats are rounded to the nearest integer first, halfway cases away from zero
+    Round,
+    /// Fails with `ValueOutOfRange` unless the value is exactly representable
+    Exact,
+}
+
+macro_rules! cast_to {
+    ($T:ty, $value:expr, $exact:expr, $data_type:expr) => {
+        if $exact {
+            let value = $value;
+            IdxValue::from(<$T>::from_value(value).map_err(|_| IdxError::ValueOutOfRange{ value, data_type: $data_type })?)
+        } else {
+            IdxValue::from(<$T>::from_value_lossy($value))
+        }
+    };
+}
+
+/// Converts value to the data type according to the policy
+pub(crate) fn cast(value: IdxValue, data_type: IdxDataType, policy: CastPolicy) -> Result<IdxValue, IdxError> {
+    let to_float = data_type == IdxDataType::Float || data_type == IdxDataType::Double;
+    let value = match (policy, value) {
+        (CastPolicy::Round, IdxValue::Float(v)) if !to_float  => IdxValue::Float(v.round()),
+        (CastPolicy::Round, IdxValue::Double(v)) if !to_float => IdxValue::Double(v.round()),
+        _ => value,
+    };
+    let exact = policy == CastPolicy::Exact;
+    let value = match data_type {
+        IdxDataType::UnsignedByte => cast_to!(u8, value, exact, data_type),
+        IdxDataType::SignedByte   => cast_to!(i8, value, exact, data_type),
+        IdxDataType::Short        => cast_to!(i16, value, exact, data_type),
+        IdxDataType::Int          => cast_to!(i32, value, exact, data_type),
+        IdxDataType::Float        => cast_to!(f32, value, exact, data_type),
+        IdxDataType::Double       => cast_to!(f64, value, exact, data_type),
+        #[cfg(feature = "extensions")]
+        IdxDataType::UnsignedShort => cast_to!(u16, value, exact, data_type),
+        #[cfg(feature = "extensions")]
+        IdxDataType::UnsignedInt  => cast_to!(u32, value, exact, data_type),
+        #[cfg(feature = "extensions")]
+        IdxDataType::Long         => cast_to!(i64, value, exact, data_type),
+        #[cfg(feature = "extensions")]
+        IdxDataType::UnsignedLong => cast_to!(u64, value, exact, data_type),
+    };
+    Ok(value)
+}
+
+/// Writes the file as big endian IDX file of another data type with the same dimensions, values are cast like `as`
+pub fn recode<R: Read + Seek, W: Write + Seek>(cursor: &mut IdxCursor<R>, data_type: IdxDataType, writer: W) -> Result<W, IdxError> {
+    recode_with(cursor, data_type, CastPolicy::Truncate, writer, |_, _| {})
+}
+
+/// Like `recode`, converting by `policy`, `progress` receives the written and the total number of records
+///
+/// The payload is converted in chunks of 1 MiB, so files larger than memory can be converted.
+pub fn recode_with<R, W, F>(cursor: &mut IdxCursor<R>, data_type: IdxDataType, policy: CastPolicy, writer: W, progress: F) -> Result<W, IdxError>
+where
+    R: Read + Seek,
+    W: Write + Seek,
+    F: FnMut(u64, u64),
+{
+    recode_chunked(cursor, data_type, policy, writer, DEFAULT_CHUNK, progress)
+}
+
+/// Like `recode_with`, but holds at most `chunk_bytes` of read and converted values at a time, and never less than
+/// a single element
+///
+/// Chunks do not follow record boundaries, so records larger than the chunk are converted in parts.
+///
+/// ```
+/// use std::io;
+/// use idxrs::recode::{recode, recode_chunked, CastPolicy};
+/// use idxrs::{testing, IdxCursor, IdxDataType};
+///
+/// let mut cursor = IdxCursor::from_bytes(testing::generate_random_idx(IdxDataType::Short, &[5, 7, 3], 1)?)?;
+/// let whole = recode(&mut cursor, IdxDataType::Double, io::Cursor::new(Vec::new()))?;
+/// let chunked = recode_chunked(&mut cursor, IdxDataType::Double, CastPolicy::Truncate, io::Cursor::new(Vec::new()), 20, |_, _| {})?;
+/// assert_eq!(whole.into_inner(), chunked.into_inner());
+/// # Ok::<(), idxrs::IdxError>(())
+/// ```
+pub fn recode_chunked<R, W, F>(cursor: &mut IdxCursor<R>, data_type: IdxDataType, policy: CastPolicy, mut writer: W, chunk_bytes: usize, mut progress: F) -> Result<W, IdxError>
+where
+    R: Read + Seek,
+    W: Write + Seek,
+    F: FnMut(u64, u64),
+{
+    let dimensions = cursor.dimensions().to_vec();
+    write_header(&mut writer, data_type, &dimensions, Endianness::Big)?;
+    let source = cursor.data_type;
+    let size = cursor.element_size;
+    let elements = cursor.shape.len();
+    // A scalar file is a single record of one element
+    let record_len = cursor.shape.record_len(dimensions.len().min(1)).max(1);
+    let total = cursor.num_records() as u64;
+    let per_chunk = (chunk_bytes as u64 / (size + data_type.get_size() as u64)).clamp(1, elements.max(1));
+    let mut buffer = vec![0; buffer_len(per_chunk, size)?];
+    let mut bytes = Vec::with_capacity(buffer_len(per_chunk, data_type.get_size() as u64)?);
+    let mut done = 0;
+    while done < elements {
+        let n = per_chunk.min(elements - done);
+        let chunk = &mut buffer[..(n * size) as usize];
+        let pos = cursor.header_len + done * size;
+        cursor.reader.seek(SeekFrom::Start(pos))?;
+        read_exact_at(&mut cursor.reader, pos, chunk)?;
+        cursor.endianness.swap_be(chunk, size as usize);
+        bytes.clear();
+        for b in chunk.chunks_exact(size as usize) {
+            cast(IdxValue::decode(source, b)?, data_type, policy)?.write_be(&mut bytes);
+        }
+        writer.write_all(&bytes)?;
+        done += n;
+        progress(done / record_len, total);
+    }
+    writer.flush()?;
+    Ok(writer)
+}
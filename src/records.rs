@@ -0,0 +1,249 @@
+use std::any::Any;
+use std::io::Read;
+use std::io::Seek;
+use std::io::SeekFrom;
+use std::ops::Range;
+
+use crate::buffer_len;
+use crate::decode_into;
+use crate::read_exact_at;
+use crate::IdxCursor;
+use crate::IdxDataType;
+use crate::IdxElement;
+use crate::IdxError;
+use crate::IdxShape;
+use crate::IdxSlice;
+use crate::IdxValue;
+
+/// Order in which `IdxCursor::records` visits the records of a file
+#[derive(Clone, Debug, PartialEq)]
+pub enum RecordOrder {
+    Forward,
+    Reverse,
+    /// Leading indices in the order they are visited, records may be left out or repeated
+    Permutation(Vec<u32>),
+}
+
+/// Iterator over whole records, created by `IdxCursor::records`, `IdxCursor::iter_rev` and `IdxCursor::iter_lossy`
+///
+/// Only the permutation of `RecordOrder::Permutation` is kept in memory, records are read one at a time.
+pub struct IdxRecords<'a, R: Read + Seek> {
+    cursor: &'a mut IdxCursor<R>,
+    order: RecordOrder,
+    // Positions in the order that are still to be visited, from the front and from the back
+    remaining: Range<usize>,
+    failed: bool,
+    skip_errors: bool,
+}
+
+impl<R: Read + Seek> IdxCursor<R> {
+    /// Iterates over the records along the leading dimension in the given order, a scalar file is a single record
+    ///
+    /// Fails with `OutOfBounds` if the permutation holds an index past the last record.
+    pub fn records(&mut self, order: RecordOrder) -> Result<IdxRecords<'_, R>, IdxError> {
+        let len = match &order {
+            RecordOrder::Permutation(indices) => {
+                if let Some(index) = indices.iter().find(|i| **i >= self.num_records()) {
+                    return Err(IdxError::OutOfBounds{ dimension: 0, max: self.num_records(), index: *index });
+                }
+                indices.len()
+            }
+            _ => self.num_records() as usize,
+        };
+        Ok(IdxRecords { cursor: self, order, remaining: 0..len, failed: false, skip_errors: false })
+    }
+
+    /// Iterates over the records from the last to the first
+    pub fn iter_rev(&mut self) -> IdxRecords<'_, R> {
+        let len = self.num_records() as usize;
+        IdxRecords { cursor: self, order: RecordOrder::Reverse, remaining: 0..len, failed: false, skip_errors: false }
+    }
+
+    /// Iterates over the records in file order and goes on after records that cannot be read, so the intact
+    /// records of a damaged or truncated file can still be recovered
+    pub fn iter_lossy(&mut self) -> IdxRecords<'_, R> {
+        let len = self.num_records() as usize;
+        IdxRecords { cursor: self, order: RecordOrder::Forward, remaining: 0..len, failed: false, skip_errors: true }
+    }
+
+    /// Reads the records in file order into a single buffer that is reused for every record
+    pub fn record_views(&mut self) -> RecordViews<'_, R> {
+        let leading = self.dimensions().len().min(1);
+        let shape = IdxShape::new(self.dimensions()[leading..].to_vec());
+        RecordViews { cursor: self, shape, next: 0, bytes: Vec::new(), typed: None, failed: false }
+    }
+}
+
+/// Records in file order that borrow one reused buffer, created by `IdxCursor::record_views`
+///
+/// A view lives until the next call of `next`, so this is not an `Iterator` and is used with `while let`. Nothing is
+/// allocated per record once the buffers have grown to the record size.
+///
+/// ```
+/// use idxrs::{testing, IdxCursor, IdxDataType};
+///
+/// let mut cursor = IdxCursor::from_bytes(testing::generate_random_idx(IdxDataType::UnsignedByte, &[60, 28, 28], 3)?)?;
+/// let mut views = cursor.record_views();
+/// let mut sum = 0u64;
+/// while let Some(res) = views.next() {
+///     let (_, mut record) = res?;
+///     sum += record.as_slice::<u8>()?.iter().map(|v| *v as u64).sum::<u64>();
+/// }
+/// assert_eq!(sum, cursor.to_vec::<u8>()?.0.iter().map(|v| *v as u64).sum::<u64>());
+/// # Ok::<(), idxrs::IdxError>(())
+/// ```
+pub struct RecordViews<'a, R: Read + Seek> {
+    cursor: &'a mut IdxCursor<R>,
+    shape: IdxShape,
+    next: u32,
+    bytes: Vec<u8>,
+    // Values of the last `as_slice` call, kept to reuse its allocation for the next record
+    typed: Option<Box<dyn Any + Send>>,
+    failed: bool,
+}
+
+impl<'a, R: Read + Seek> RecordViews<'a, R> {
+    /// Reads the next record with its leading index, `None` after the last record or the first error
+    #[allow(clippy::should_implement_trait)]
+    pub fn next(&mut self) -> Option<Result<(u32, RecordView<'_>), IdxError>> {
+        if self.failed || self.next >= self.cursor.num_records() {
+            return None;
+        }
+        let i = self.next;
+        if let Err(e) = self.read(i) {
+            self.failed = true;
+            return Some(Err(e));
+        }
+        self.next += 1;
+        let view = RecordView {
+            bytes: &self.bytes,
+            data_type: self.cursor.data_type,
+            shape: &self.shape,
+            typed: &mut self.typed,
+        };
+        Some(Ok((i, view)))
+    }
+
+    /// Replaces the buffer with the big endian bytes of record `i`
+    fn read(&mut self, i: u32) -> Result<(), IdxError> {
+        let size = self.cursor.element_size;
+        let len = buffer_len(self.shape.len(), size)?;
+        let pos = self.cursor.header_len + i as u64 * len as u64;
+        self.bytes.resize(len, 0);
+        self.cursor.reader.seek(SeekFrom::Start(pos))?;
+        read_exact_at(&mut self.cursor.reader, pos, &mut self.bytes)?;
+        self.cursor.endianness.swap_be(&mut self.bytes, size as usize);
+        Ok(())
+    }
+}
+
+/// Record borrowed from the buffer of `RecordViews`
+pub struct RecordView<'a> {
+    bytes: &'a [u8],
+    data_type: IdxDataType,
+    shape: &'a IdxShape,
+    typed: &'a mut Option<Box<dyn Any + Send>>,
+}
+
+impl<'a> RecordView<'a> {
+    /// Trailing dimensions of the file
+    pub fn shape(&self) -> &[u32] {
+        self.shape.dimensions()
+    }
+
+    pub fn data_type(&self) -> IdxDataType {
+        self.data_type
+    }
+
+    /// Number of elements in the record
+    pub fn len(&self) -> usize {
+        self.bytes.len() / self.data_type.get_size() as usize
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Raw big endian bytes of all elements
+    pub fn as_bytes(&self) -> &[u8] {
+        self.bytes
+    }
+
+    /// Reads element at indices relative to the record
+    pub fn get(&self, indices: &[u32]) -> Result<IdxValue, IdxError> {
+        let size = self.data_type.get_size() as usize;
+        let pos = self.shape.flat_offset(indices)? as usize * size;
+        IdxValue::decode(self.data_type, &self.bytes[pos..pos + size])
+    }
+
+    /// All elements converted to `T` in storage order, fails on values that are not exactly representable
+    ///
+    /// The values are converted into a buffer that is reused for the next record as long as `T` stays the same.
+    pub fn as_slice<T: IdxElement + 'static>(&mut self) -> Result<&[T], IdxError> {
+        if !self.typed.as_ref().is_some_and(|typed| typed.is::<Vec<T>>()) {
+            *self.typed = Some(Box::new(Vec::<T>::new()));
+        }
+        let values = self.typed.as_mut().and_then(|typed| typed.downcast_mut::<Vec<T>>()).expect("buffer holds values of T");
+        values.clear();
+        decode_into(self.data_type, self.bytes, values, false)?;
+        Ok(values)
+    }
+
+    /// Copies the record into an owned slice that outlives the view
+    pub fn to_slice(&self) -> IdxSlice {
+        IdxSlice::new(self.bytes.into(), self.data_type, self.shape.clone())
+    }
+}
+
+impl<'a, R: Read + Seek> IdxRecords<'a, R> {
+    /// Yields an error for every record that cannot be read and goes on with the next one instead of stopping
+    pub fn skip_errors(mut self) -> IdxRecords<'a, R> {
+        self.skip_errors = true;
+        self
+    }
+
+    /// Leading index of the record at position `i` of the order
+    fn record(&self, i: usize) -> u32 {
+        match &self.order {
+            RecordOrder::Forward              => i as u32,
+            RecordOrder::Reverse              => self.cursor.num_records() - 1 - i as u32,
+            RecordOrder::Permutation(indices) => indices[i],
+        }
+    }
+
+    fn read(&mut self, i: usize) -> Result<IdxSlice, IdxError> {
+        let index = [self.record(i)];
+        let leading = self.cursor.dimensions().len().min(1);
+        let res = self.cursor.get_record(&index[..leading]);
+        // Stop after the first error like the other iterators, unless errors are skipped
+        self.failed = res.is_err() && !self.skip_errors;
+        res
+    }
+}
+
+impl<'a, R: Read + Seek> Iterator for IdxRecords<'a, R> {
+    type Item = Result<IdxSlice, IdxError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.failed {
+            return None;
+        }
+        let i = self.remaining.next()?;
+        Some(self.read(i))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        // An error ends iteration early, so only the upper bound is known
+        (0, Some(self.remaining.len()))
+    }
+}
+
+impl<'a, R: Read + Seek> DoubleEndedIterator for IdxRecords<'a, R> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.failed {
+            return None;
+        }
+        let i = self.remaining.next_back()?;
+        Some(self.read(i))
+    }
+}
@@ -0,0 +1,41 @@
+use std::io::Read;
+use std::io::Seek;
+
+use crate::rng::Rng;
+use crate::IdxCursor;
+use crate::IdxError;
+use crate::IdxSlice;
+
+/// Numbers `0..n` in random order, the same order `Dataset::shuffle` visits the records of `n` record files in
+pub fn permutation(n: u32, seed: u64) -> Vec<u32> {
+    let mut indices: Vec<u32> = (0..n).collect();
+    Rng::new(seed).shuffle(&mut indices);
+    indices
+}
+
+impl<R: Read + Seek> IdxCursor<R> {
+    /// Reads `n` distinct records picked at random, the same seed always picks the same records in the same order
+    pub fn sample(&mut self, n: u32, seed: u64) -> Result<Vec<IdxSlice>, IdxError> {
+        let records = self.sample_population()?;
+        if n > records {
+            return Err(IdxError::OutOfBounds{ dimension: 0, max: records, index: n });
+        }
+        let indices = Rng::new(seed).choose(records, n as usize);
+        self.get_records(&indices)
+    }
+
+    /// Like `sample`, but records may be picked more than once, so `n` can exceed the record count
+    pub fn sample_with_replacement(&mut self, n: u32, seed: u64) -> Result<Vec<IdxSlice>, IdxError> {
+        let records = self.sample_population()?;
+        if records == 0 && n > 0 {
+            return Err(IdxError::OutOfBounds{ dimension: 0, max: 0, index: 0 });
+        }
+        let mut rng = Rng::new(seed);
+        let indices: Vec<u32> = (0..n).map(|_| rng.below(records as u64) as u32).collect();
+        self.get_records(&indices)
+    }
+
+    fn sample_population(&self) -> Result<u32, IdxError> {
+        self.dimensions().first().copied().ok_or(IdxError::DimensionMismatch{ needed: 1, supplied: 0 })
+    }
+}
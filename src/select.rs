@@ -0,0 +1,122 @@
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::hash::Hasher;
+use std::io::Read;
+use std::io::Seek;
+use std::io::Write;
+
+use crate::IdxCursor;
+use crate::IdxDataType;
+use crate::IdxError;
+use crate::IdxSlice;
+use crate::StreamingIdxWriter;
+
+/// Records of a file picked by index, created by `IdxCursor::filter_records` and `IdxCursor::dedup_records`
+pub struct IdxSelection<'a, R: Read + Seek> {
+    cursor: &'a mut IdxCursor<R>,
+    records: Vec<u32>,
+}
+
+impl<R: Read + Seek> IdxCursor<R> {
+    /// Selects the records for which `keep` returns true, e.g. to drop blank images
+    ///
+    /// ```no_run
+    /// use idxrs::IdxFile;
+    ///
+    /// let mut images = IdxFile::open("train-images-idx3-ubyte")?;
+    /// let out = std::fs::File::create("train-images-nonblank.idx")?;
+    /// images.filter_records(|record| record.as_bytes().iter().any(|b| *b != 0))?.write_to(out)?;
+    /// # Ok::<(), idxrs::IdxError>(())
+    /// ```
+    pub fn filter_records<F: FnMut(&IdxSlice) -> bool>(&mut self, mut keep: F) -> Result<IdxSelection<'_, R>, IdxError> {
+        let mut records = Vec::new();
+        for i in 0..self.selection_population()? {
+            if keep(&self.get_record(&[i])?) {
+                records.push(i);
+            }
+        }
+        Ok(IdxSelection { cursor: self, records })
+    }
+
+    /// Selects the first occurrence of every distinct record
+    ///
+    /// Only the hashes of the records are kept in memory, records with equal hashes are read again and compared.
+    pub fn dedup_records(&mut self) -> Result<IdxSelection<'_, R>, IdxError> {
+        let records = self.record_groups()?.into_iter().map(|group| group[0]).collect();
+        Ok(IdxSelection { cursor: self, records })
+    }
+
+    /// Groups of identical records with more than one member, each in file order and ordered by their first record
+    pub fn duplicate_records(&mut self) -> Result<Vec<Vec<u32>>, IdxError> {
+        Ok(self.record_groups()?.into_iter().filter(|group| group.len() > 1).collect())
+    }
+
+    /// Every distinct record with the indices of all its occurrences, in order of the first occurrence
+    fn record_groups(&mut self) -> Result<Vec<Vec<u32>>, IdxError> {
+        // Hash to the positions in groups of the distinct records with that hash
+        let mut seen: HashMap<u64, Vec<usize>> = HashMap::new();
+        let mut groups: Vec<Vec<u32>> = Vec::new();
+        for i in 0..self.selection_population()? {
+            let record = self.get_record(&[i])?;
+            let mut hasher = DefaultHasher::new();
+            record.as_bytes().hash(&mut hasher);
+            let hash = hasher.finish();
+            let mut group = None;
+            for candidate in seen.get(&hash).into_iter().flatten() {
+                if self.get_record(&[groups[*candidate][0]])?.as_bytes() == record.as_bytes() {
+                    group = Some(*candidate);
+                    break;
+                }
+            }
+            match group {
+                Some(group) => groups[group].push(i),
+                None => {
+                    seen.entry(hash).or_default().push(groups.len());
+                    groups.push(vec![i]);
+                }
+            }
+        }
+        Ok(groups)
+    }
+
+    fn selection_population(&self) -> Result<u32, IdxError> {
+        self.dimensions().first().copied().ok_or(IdxError::DimensionMismatch{ needed: 1, supplied: 0 })
+    }
+}
+
+impl<'a, R: Read + Seek> IdxSelection<'a, R> {
+    /// Indices of the selected records in the file, in file order
+    pub fn records(&self) -> &[u32] {
+        &self.records
+    }
+
+    /// Number of selected records
+    pub fn len(&self) -> usize {
+        self.records.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.records.is_empty()
+    }
+
+    pub fn data_type(&self) -> IdxDataType {
+        self.cursor.data_type
+    }
+
+    /// Reads the `i`-th selected record
+    pub fn get_record(&mut self, i: u32) -> Result<IdxSlice, IdxError> {
+        let record = *self.records.get(i as usize).ok_or(IdxError::OutOfBounds{ dimension: 0, max: self.records.len() as u32, index: i })?;
+        self.cursor.get_record(&[record])
+    }
+
+    /// Writes the selected records as a new big endian IDX file and returns the writer
+    pub fn write_to<W: Write + Seek>(&mut self, writer: W) -> Result<W, IdxError> {
+        let header = self.cursor.header();
+        let mut writer = StreamingIdxWriter::new(writer, header.data_type, header.record_shape())?;
+        for record in &self.records {
+            writer.append_record_bytes(self.cursor.get_record(&[*record])?.as_bytes())?;
+        }
+        writer.finish()
+    }
+}
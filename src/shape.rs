@@ -0,0 +1,136 @@
+use alloc::vec;
+use alloc::vec::Vec;
+
+use crate::IdxError;
+
+/// Dimension sizes of a tensor together with the strides of its row-major storage
+///
+/// ```
+/// use idxrs::IdxShape;
+///
+/// let shape = IdxShape::new(vec![2, 3, 4]);
+/// assert_eq!(shape.strides(), &[12, 4, 1]);
+/// assert_eq!(shape.len(), 24);
+/// assert_eq!(shape.flat_offset(&[1, 2, 3]).unwrap(), 23);
+/// assert!(shape.flat_offset(&[0, 3, 0]).is_err());
+///
+/// // Element counts and offsets are 64 bit, past the range of the u32 dimensions
+/// let shape = IdxShape::new(vec![70_000, 70_000, 3]);
+/// assert_eq!(shape.len(), 14_700_000_000);
+/// assert_eq!(shape.flat_offset(&[69_999, 69_999, 2]).unwrap(), 14_699_999_999);
+/// assert!(IdxShape::try_new(vec![u32::MAX; 3]).is_err());
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct IdxShape {
+    dimensions: Vec<u32>,
+    strides: Vec<u64>,
+}
+
+impl IdxShape {
+    /// Panics if the number of elements does not fit into 64 bits, see `try_new`
+    pub fn new(dimensions: Vec<u32>) -> IdxShape {
+        IdxShape::try_new(dimensions).expect("number of elements fits into 64 bits")
+    }
+
+    /// Like `new`, but fails with `Overflow` if the number of elements or a stride does not fit into 64 bits
+    pub fn try_new(dimensions: Vec<u32>) -> Result<IdxShape, IdxError> {
+        // Stride of a dimension is the product of all dimensions after it
+        let mut strides = vec![1u64; dimensions.len()];
+        for i in (0..dimensions.len().saturating_sub(1)).rev() {
+            strides[i] = strides[i + 1].checked_mul(dimensions[i + 1] as u64).ok_or(IdxError::Overflow)?;
+        }
+        if let (Some(stride), Some(dimension)) = (strides.first(), dimensions.first()) {
+            stride.checked_mul(*dimension as u64).ok_or(IdxError::Overflow)?;
+        }
+        Ok(IdxShape { dimensions, strides })
+    }
+
+    pub fn dimensions(&self) -> &[u32] {
+        &self.dimensions
+    }
+
+    /// Number of elements between two consecutive indices of each dimension
+    pub fn strides(&self) -> &[u64] {
+        &self.strides
+    }
+
+    /// Total number of elements
+    pub fn len(&self) -> u64 {
+        self.dimensions.iter().map(|d| *d as u64).product()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Checks indices against the dimensions and returns the element position in storage order
+    pub fn flat_offset(&self, indices: &[u32]) -> Result<u64, IdxError> {
+        // Throw index error if index parameter does not fit dimension count
+        if indices.len() != self.dimensions.len() {
+            return Err(IdxError::DimensionMismatch{ needed: self.dimensions.len(), supplied: indices.len() });
+        }
+        self.record_offset(indices)
+    }
+
+    /// Position of the first element of the block selected by the leading indices
+    pub fn record_offset(&self, leading_indices: &[u32]) -> Result<u64, IdxError> {
+        if leading_indices.len() > self.dimensions.len() {
+            return Err(IdxError::DimensionMismatch{ needed: self.dimensions.len(), supplied: leading_indices.len() });
+        }
+        let mut pos: u64 = 0;
+        // Check indices and sizes of individual dimensions
+        for (i, (dimension, index)) in self.dimensions.iter().zip(leading_indices.iter()).enumerate() {
+            if index >= dimension {
+                return Err(IdxError::OutOfBounds{ dimension: i, max: *dimension, index: *index });
+            }
+            pos += *index as u64 * self.strides[i];
+        }
+        Ok(pos)
+    }
+
+    /// Element position of the indices without any checks, excess indices are ignored
+    #[cfg(feature = "std")]
+    pub(crate) fn offset_unchecked(&self, indices: &[u32]) -> u64 {
+        indices.iter().zip(&self.strides).fold(0u64, |pos, (index, stride)| pos.wrapping_add((*index as u64).wrapping_mul(*stride)))
+    }
+
+    /// Number of elements in each block selected by the given number of leading indices
+    pub fn record_len(&self, leading: usize) -> u64 {
+        self.dimensions.iter().skip(leading).map(|d| *d as u64).product()
+    }
+}
+
+/// Advances index to the next element in storage order like an odometer, last dimension first
+pub(crate) fn advance(index: &mut [u32], dimensions: &[u32]) {
+    for (i, dimension) in index.iter_mut().zip(dimensions.iter()).rev() {
+        *i += 1;
+        if *i < *dimension {
+            break;
+        }
+        *i = 0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec;
+
+    use super::IdxShape;
+    use crate::IdxError;
+
+    #[test]
+    fn elements_beyond_u32() {
+        let shape = IdxShape::try_new(vec![65536, 65536, 2]).unwrap();
+        assert_eq!(shape.len(), 1 << 33);
+        assert_eq!(shape.strides(), &[1 << 17, 2, 1]);
+        assert_eq!(shape.flat_offset(&[65535, 65535, 1]).unwrap(), (1 << 33) - 1);
+    }
+
+    #[test]
+    fn elements_beyond_u64() {
+        assert!(matches!(IdxShape::try_new(vec![u32::MAX; 3]), Err(IdxError::Overflow)));
+        // The strides still fit, only the number of elements does not
+        assert!(matches!(IdxShape::try_new(vec![u32::MAX, u32::MAX, 2]), Err(IdxError::Overflow)));
+        assert!(IdxShape::try_new(vec![u32::MAX, u32::MAX, 1]).is_ok());
+    }
+}
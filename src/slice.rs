@@ -0,0 +1,58 @@
+use alloc::boxed::Box;
+
+use crate::IdxDataType;
+use crate::IdxElement;
+use crate::IdxError;
+use crate::IdxShape;
+use crate::IdxValue;
+
+/// Contiguous block of elements with the trailing dimensions it was read with
+pub struct IdxSlice<B: AsRef<[u8]> = Box<[u8]>> {
+    data: B,
+    data_type: IdxDataType,
+    shape: IdxShape,
+}
+
+impl<B: AsRef<[u8]>> IdxSlice<B> {
+    pub(crate) fn new(data: B, data_type: IdxDataType, shape: IdxShape) -> IdxSlice<B> {
+        IdxSlice { data, data_type, shape }
+    }
+
+    pub fn shape(&self) -> &[u32] {
+        self.shape.dimensions()
+    }
+
+    pub fn data_type(&self) -> IdxDataType {
+        self.data_type
+    }
+
+    /// Number of elements in the slice
+    pub fn len(&self) -> usize {
+        self.data.as_ref().len() / self.data_type.get_size() as usize
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Raw big endian bytes of all elements
+    pub fn as_bytes(&self) -> &[u8] {
+        self.data.as_ref()
+    }
+
+    pub fn into_inner(self) -> B {
+        self.data
+    }
+
+    /// Reads element at indices relative to the slice
+    pub fn get(&self, indices: &[u32]) -> Result<IdxValue, IdxError> {
+        let size = self.data_type.get_size() as usize;
+        let pos = self.shape.flat_offset(indices)? as usize * size;
+        IdxValue::decode(self.data_type, &self.as_bytes()[pos..pos + size])
+    }
+
+    /// Reads element and converts it to `T`, fails if the value is not exactly representable
+    pub fn get_as<T: IdxElement>(&self, indices: &[u32]) -> Result<T, IdxError> {
+        T::from_value(self.get(indices)?)
+    }
+}
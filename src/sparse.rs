@@ -0,0 +1,158 @@
+//! Records that only keep their non-zero elements, for mostly empty images like MNIST digits
+//!
+//! ```
+//! use idxrs::{IdxCursor, IdxWriter};
+//!
+//! let pixels = [0u8, 0, 0, 7, 0, 0, 255, 0, 0];
+//! let data = IdxWriter::write_array(std::io::Cursor::new(Vec::new()), &[1, 3, 3], &pixels)?.into_inner();
+//! let mut cursor = IdxCursor::from_bytes(data)?;
+//! let sparse = cursor.get_record(&[0])?.to_sparse();
+//! assert_eq!(sparse.indices(), &[3, 6]);
+//! assert_eq!(sparse.density(), 2.0 / 9.0);
+//! assert_eq!(sparse.to_dense().as_bytes(), &pixels[..]);
+//! # Ok::<(), idxrs::IdxError>(())
+//! ```
+
+use std::convert::TryFrom;
+use std::io::Read;
+use std::io::Seek;
+use std::io::Write;
+
+use crate::IdxCursor;
+use crate::IdxDataType;
+use crate::IdxError;
+use crate::IdxShape;
+use crate::IdxSlice;
+use crate::IdxValue;
+use crate::RecordView;
+use crate::StreamingIdxWriter;
+
+/// Non-zero elements of a record with their positions in storage order
+///
+/// Floats are kept unless all their bits are zero, so `-0.0` is stored and the dense record comes back unchanged.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SparseRecord {
+    data_type: IdxDataType,
+    shape: IdxShape,
+    indices: Vec<u32>,
+    // Big endian bytes of the stored elements
+    values: Vec<u8>,
+}
+
+impl SparseRecord {
+    /// Keeps the non-zero elements of big endian `bytes`
+    ///
+    /// Panics if the record has more than `u32::MAX` elements.
+    fn from_dense(bytes: &[u8], data_type: IdxDataType, shape: IdxShape) -> SparseRecord {
+        let size = data_type.get_size() as usize;
+        let mut indices = Vec::new();
+        let mut values = Vec::new();
+        for (i, element) in bytes.chunks_exact(size).enumerate() {
+            if element.iter().any(|b| *b != 0) {
+                indices.push(u32::try_from(i).expect("record has at most u32::MAX elements"));
+                values.extend_from_slice(element);
+            }
+        }
+        SparseRecord { data_type, shape, indices, values }
+    }
+
+    /// Dimensions of the dense record
+    pub fn shape(&self) -> &[u32] {
+        self.shape.dimensions()
+    }
+
+    pub fn data_type(&self) -> IdxDataType {
+        self.data_type
+    }
+
+    /// Positions of the stored elements in storage order of the dense record, in ascending order
+    pub fn indices(&self) -> &[u32] {
+        &self.indices
+    }
+
+    /// Raw big endian bytes of the stored elements
+    pub fn values_as_bytes(&self) -> &[u8] {
+        &self.values
+    }
+
+    /// Number of stored elements
+    pub fn len(&self) -> usize {
+        self.indices.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.indices.is_empty()
+    }
+
+    /// Share of non-zero elements of the dense record, 0 for a record without elements
+    pub fn density(&self) -> f64 {
+        self.indices.len() as f64 / self.shape.len().max(1) as f64
+    }
+
+    /// Iterates over the stored elements as pairs of position and value
+    pub fn iter(&self) -> impl Iterator<Item = (u32, IdxValue)> + '_ {
+        let size = self.data_type.get_size() as usize;
+        self.indices.iter().zip(self.values.chunks_exact(size)).map(move |(i, bytes)| {
+            // Cannot fail, the chunk always has the size of one element
+            (*i, IdxValue::decode(self.data_type, bytes).expect("chunk has the size of the element"))
+        })
+    }
+
+    /// Fills in the zeros again
+    pub fn to_dense(&self) -> IdxSlice {
+        let size = self.data_type.get_size() as usize;
+        let mut bytes = vec![0; self.shape.len() as usize * size];
+        for (i, value) in self.indices.iter().zip(self.values.chunks_exact(size)) {
+            let pos = *i as usize * size;
+            bytes[pos..pos + size].copy_from_slice(value);
+        }
+        IdxSlice::new(bytes.into_boxed_slice(), self.data_type, self.shape.clone())
+    }
+}
+
+impl<B: AsRef<[u8]>> IdxSlice<B> {
+    /// Keeps only the non-zero elements, panics if the slice has more than `u32::MAX` elements
+    pub fn to_sparse(&self) -> SparseRecord {
+        SparseRecord::from_dense(self.as_bytes(), self.data_type(), IdxShape::new(self.shape().to_vec()))
+    }
+}
+
+impl<'a> RecordView<'a> {
+    /// Keeps only the non-zero elements, panics if the record has more than `u32::MAX` elements
+    pub fn to_sparse(&self) -> SparseRecord {
+        SparseRecord::from_dense(self.as_bytes(), self.data_type(), IdxShape::new(self.shape().to_vec()))
+    }
+}
+
+/// Writes the file as a matrix of one row per record in compressed sparse row format, as three 1-D files
+///
+/// `values` gets the non-zero elements in the data type of the file, `indices` their column as i32, and `indptr`
+/// the i32 position in `values` where each row starts, followed by the total. These are the arrays of
+/// `scipy.sparse.csr_matrix((values, indices, indptr))`. Fails with `ValueOutOfRange` if there are more elements than
+/// an i32 can count.
+pub fn write_csr<R: Read + Seek, W: Write + Seek>(cursor: &mut IdxCursor<R>, indptr: W, indices: W, values: W) -> Result<(W, W, W), IdxError> {
+    let mut indptr = StreamingIdxWriter::new(indptr, IdxDataType::Int, &[])?;
+    let mut indices = StreamingIdxWriter::new(indices, IdxDataType::Int, &[])?;
+    let mut values = StreamingIdxWriter::new(values, cursor.dtype(), &[])?;
+    let mut count = 0u64;
+    indptr.append_record_bytes(&int(count)?.to_be_bytes())?;
+    let mut columns = Vec::new();
+    let mut views = cursor.record_views();
+    while let Some(res) = views.next() {
+        let (_, record) = res?;
+        let sparse = record.to_sparse();
+        columns.clear();
+        for i in sparse.indices() {
+            columns.extend_from_slice(&int(*i as u64)?.to_be_bytes());
+        }
+        indices.append_record_bytes(&columns)?;
+        values.append_record_bytes(sparse.values_as_bytes())?;
+        count += sparse.len() as u64;
+        indptr.append_record_bytes(&int(count)?.to_be_bytes())?;
+    }
+    Ok((indptr.finish()?, indices.finish()?, values.finish()?))
+}
+
+fn int(n: u64) -> Result<i32, IdxError> {
+    i32::try_from(n).map_err(|_| IdxError::ValueOutOfRange{ value: IdxValue::Double(n as f64), data_type: IdxDataType::Int })
+}
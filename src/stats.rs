@@ -0,0 +1,157 @@
+//! Value statistics and normalization of whole files
+
+use std::io::Read;
+use std::io::Seek;
+
+use crate::IdxCursor;
+use crate::IdxDataType;
+use crate::IdxError;
+use crate::IdxShape;
+
+/// Counts of values in equally wide bins, values outside of the bins are counted separately
+#[derive(Clone, Debug, PartialEq)]
+pub struct Histogram {
+    pub start: f64,
+    pub width: f64,
+    pub counts: Vec<u64>,
+    pub below: u64,
+    pub above: u64,
+}
+
+impl Histogram {
+    fn new(start: f64, end: f64, bins: usize) -> Histogram {
+        Histogram { start, width: (end - start) / bins as f64, counts: vec![0; bins], below: 0, above: 0 }
+    }
+
+    fn add(&mut self, v: f64) {
+        let bin = ((v - self.start) / self.width).floor();
+        if bin < 0.0 {
+            self.below += 1;
+        } else if bin < self.counts.len() as f64 {
+            self.counts[bin as usize] += 1;
+        } else {
+            // NaN ends up here as well
+            self.above += 1;
+        }
+    }
+
+    /// Lower edge of bin `i`
+    pub fn bin_start(&self, i: usize) -> f64 {
+        self.start + i as f64 * self.width
+    }
+}
+
+/// Summary of all values of a file, mean and std are NaN for files without values
+#[derive(Clone, Debug, PartialEq)]
+pub struct IdxStats {
+    pub count: u64,
+    pub min: f64,
+    pub max: f64,
+    pub mean: f64,
+    /// Population standard deviation
+    pub std: f64,
+    pub histogram: Histogram,
+}
+
+/// Histogram range covering every value of the integer types, floats are expected to be in `0.0..=1.0`
+/// with the end nudged up so 1.0 still falls into the last bin
+fn default_range(data_type: IdxDataType) -> (f64, f64) {
+    match data_type {
+        IdxDataType::UnsignedByte => (0.0, 256.0),
+        IdxDataType::SignedByte   => (-128.0, 128.0),
+        IdxDataType::Short        => (-32768.0, 32768.0),
+        IdxDataType::Int          => (i32::MIN as f64, -(i32::MIN as f64)),
+        IdxDataType::Float        => (0.0, 1.0 + f64::EPSILON),
+        IdxDataType::Double       => (0.0, 1.0 + f64::EPSILON),
+        #[cfg(feature = "extensions")]
+        IdxDataType::UnsignedShort => (0.0, 65536.0),
+        #[cfg(feature = "extensions")]
+        IdxDataType::UnsignedInt  => (0.0, 4294967296.0),
+        #[cfg(feature = "extensions")]
+        IdxDataType::Long         => (i64::MIN as f64, -(i64::MIN as f64)),
+        #[cfg(feature = "extensions")]
+        IdxDataType::UnsignedLong => (0.0, 18446744073709551616.0),
+    }
+}
+
+/// Computes statistics in a single pass, with 256 histogram bins over the range of the data type
+pub fn compute<R: Read + Seek>(cursor: &mut IdxCursor<R>) -> Result<IdxStats, IdxError> {
+    let (start, end) = default_range(cursor.data_type);
+    compute_with(cursor, 256, start, end)
+}
+
+/// Like `compute`, with `bins` histogram bins over `start..end`
+pub fn compute_with<R: Read + Seek>(cursor: &mut IdxCursor<R>, bins: usize, start: f64, end: f64) -> Result<IdxStats, IdxError> {
+    let mut acc = Accumulator::new();
+    let mut histogram = Histogram::new(start, end, bins);
+    for res in cursor.iter() {
+        let v = res?.1.as_f64();
+        acc.add(v);
+        histogram.add(v);
+    }
+    Ok(acc.finish(histogram))
+}
+
+/// Running min, max and Welford mean and variance
+struct Accumulator {
+    count: u64,
+    min: f64,
+    max: f64,
+    mean: f64,
+    m2: f64,
+}
+
+impl Accumulator {
+    fn new() -> Accumulator {
+        Accumulator { count: 0, min: f64::INFINITY, max: f64::NEG_INFINITY, mean: 0.0, m2: 0.0 }
+    }
+
+    fn add(&mut self, v: f64) {
+        self.count += 1;
+        self.min = self.min.min(v);
+        self.max = self.max.max(v);
+        let delta = v - self.mean;
+        self.mean += delta / self.count as f64;
+        self.m2 += delta * (v - self.mean);
+    }
+
+    fn finish(self, histogram: Histogram) -> IdxStats {
+        let (mean, std) = if self.count == 0 { (f64::NAN, f64::NAN) } else { (self.mean, (self.m2 / self.count as f64).sqrt()) };
+        IdxStats { count: self.count, min: self.min, max: self.max, mean, std, histogram }
+    }
+}
+
+/// How values are rescaled by `normalize`
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Normalization {
+    /// Maps the smallest value to 0 and the largest to 1
+    MinMax,
+    /// Shifts to mean 0 and scales to standard deviation 1
+    Standardize,
+    /// Maps `min` to 0 and `max` to 1, e.g. 0 and 255 for bytes
+    Range{ min: f64, max: f64 },
+    /// Uses a known mean and standard deviation, e.g. the ones of the training set
+    MeanStd{ mean: f64, std: f64 },
+}
+
+/// Reads the whole payload and returns it rescaled as `f32`, constant data becomes all zeros
+pub fn normalize<R: Read + Seek>(cursor: &mut IdxCursor<R>, normalization: Normalization) -> Result<(Vec<f32>, IdxShape), IdxError> {
+    let (mut values, shape) = cursor.to_vec_lossy::<f64>()?;
+    let (offset, scale) = match normalization {
+        Normalization::Range{ min, max } => (min, max - min),
+        Normalization::MeanStd{ mean, std } => (mean, std),
+        Normalization::MinMax | Normalization::Standardize => {
+            let mut acc = Accumulator::new();
+            values.iter().for_each(|v| acc.add(*v));
+            if normalization == Normalization::MinMax {
+                (acc.min, acc.max - acc.min)
+            } else {
+                (acc.mean, (acc.m2 / acc.count as f64).sqrt())
+            }
+        }
+    };
+    for v in values.iter_mut() {
+        *v = if scale == 0.0 { 0.0 } else { (*v - offset) / scale };
+    }
+    Ok((values.into_iter().map(|v| v as f32).collect(), shape))
+}
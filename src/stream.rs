@@ -0,0 +1,149 @@
+use std::io;
+use std::io::Read;
+
+use crate::header_len;
+use crate::read_exact_at;
+use crate::read_header;
+use crate::Endianness;
+use crate::IdxDataType;
+use crate::IdxError;
+use crate::IdxHeader;
+use crate::IdxOptions;
+use crate::IdxShape;
+use crate::IdxSlice;
+use crate::READ_CHUNK;
+
+/// Forward only reader for sources without `Seek`, like sockets or stdin
+pub struct IdxStream<R: Read> {
+    reader: R,
+    header: IdxHeader,
+    record_shape: IdxShape,
+    endianness: Endianness,
+    next: u32,
+    failed: bool,
+}
+
+impl<R: Read> IdxStream<R> {
+    /// Reads the header, the reader is left at the start of the first record
+    pub fn new(reader: R) -> Result<IdxStream<R>, IdxError> {
+        IdxStream::with_options(reader, IdxOptions::default())
+    }
+
+    /// Like `new`, but reads the file as described by `options`
+    ///
+    /// The length of the stream is not known up front, so `validate_length` is ignored and a short stream only
+    /// fails once its last record is read. `buffer_size` is ignored as well, records are read whole.
+    ///
+    /// ```
+    /// use std::io;
+    /// use idxrs::{Endianness, IdxDataType, IdxOptions, IdxStream, IdxValue, StreamingIdxWriter};
+    ///
+    /// let mut writer = StreamingIdxWriter::with_endianness(io::Cursor::new(Vec::new()), IdxDataType::Short, &[2], Endianness::Little)?;
+    /// writer.append_record(&[IdxValue::Short(1), IdxValue::Short(-2)])?;
+    /// let data = writer.finish()?.into_inner();
+    /// let options = IdxOptions { endianness: Endianness::Little, ..IdxOptions::default() };
+    /// let mut stream = IdxStream::with_options(&data[..], options)?;
+    /// assert_eq!(stream.next_record()?.expect("one record").as_bytes(), &[0, 1, 0xff, 0xfe]);
+    /// # Ok::<(), idxrs::IdxError>(())
+    /// ```
+    pub fn with_options(mut reader: R, options: IdxOptions) -> Result<IdxStream<R>, IdxError> {
+        let (data_type, shape) = read_header(&mut reader, &options)?;
+        if options.strict && data_type.is_extension() {
+            return Err(IdxError::UnknownDataType(data_type.get_code()));
+        }
+        let header = IdxHeader::new(data_type, shape.dimensions());
+        if let Some(max) = options.max_payload_bytes {
+            if header.payload_bytes > max {
+                return Err(IdxError::FileTooLarge{ size: header.payload_bytes, max });
+            }
+        }
+        let record_shape = IdxShape::new(header.record_shape().to_vec());
+        Ok(IdxStream { reader, header, record_shape, endianness: options.endianness, next: 0, failed: false })
+    }
+
+    pub fn dimensions(&self) -> &[u32] {
+        &self.header.dimensions
+    }
+
+    pub fn data_type(&self) -> IdxDataType {
+        self.header.data_type
+    }
+
+    pub fn header(&self) -> &IdxHeader {
+        &self.header
+    }
+
+    /// Number of records that have not been read yet
+    pub fn remaining(&self) -> u32 {
+        self.header.num_records() - self.next
+    }
+
+    /// Reads the next record, `None` once all records were read
+    pub fn next_record(&mut self) -> Result<Option<IdxSlice>, IdxError> {
+        if self.remaining() == 0 {
+            return Ok(None);
+        }
+        let bytes = self.record_shape.len() * self.header.data_type.get_size() as u64;
+        let offset = header_len(self.header.dimensions.len()) + self.next as u64 * bytes;
+        let mut buffer = if bytes > READ_CHUNK as u64 {
+            // Large records grow with the data that actually arrives instead of trusting the header
+            let mut data = Vec::new();
+            let eof = |source| IdxError::ReadFailed{ offset, len: bytes, source };
+            (&mut self.reader).take(bytes).read_to_end(&mut data).map_err(eof)?;
+            if (data.len() as u64) < bytes {
+                return Err(eof(io::ErrorKind::UnexpectedEof.into()));
+            }
+            data.into_boxed_slice()
+        } else {
+            let mut buffer = vec![0; bytes as usize].into_boxed_slice();
+            read_exact_at(&mut self.reader, offset, &mut buffer)?;
+            buffer
+        };
+        // Records are handed out in big endian like the ones of `IdxCursor`
+        self.endianness.swap_be(&mut buffer, self.header.data_type.get_size() as usize);
+        self.next += 1;
+        Ok(Some(IdxSlice::new(buffer, self.header.data_type, self.record_shape.clone())))
+    }
+
+    /// Returns the reader, positioned after the last record that was read
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+}
+
+/// Yields all remaining records in order and stops after the first error
+impl<R: Read> Iterator for IdxStream<R> {
+    type Item = Result<IdxSlice, IdxError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.failed {
+            return None;
+        }
+        let res = self.next_record();
+        self.failed = res.is_err();
+        res.transpose()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = if self.failed { 0 } else { self.remaining() as usize };
+        (0, Some(remaining))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::IdxStream;
+    use crate::IdxError;
+    use crate::IdxOptions;
+
+    #[test]
+    fn lenient_magic() {
+        // Two bytes of u8 records, the magic number does not start with zeros
+        let data = [0xab, 0xcd, 0x08, 0x02, 0, 0, 0, 1, 0, 0, 0, 2, 7, 9];
+        assert!(matches!(IdxStream::new(&data[..]), Err(IdxError::WrongHeader{ .. })));
+        let options = IdxOptions { lenient: true, ..IdxOptions::default() };
+        let mut stream = IdxStream::with_options(&data[..], options).unwrap();
+        assert_eq!(stream.next_record().unwrap().unwrap().as_bytes(), &[7, 9]);
+        assert!(stream.next_record().unwrap().is_none());
+    }
+}
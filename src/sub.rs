@@ -0,0 +1,93 @@
+use std::io;
+use std::io::Read;
+use std::io::Seek;
+use std::io::SeekFrom;
+
+use crate::IdxCursor;
+use crate::IdxError;
+
+/// Window into a larger stream that starts at a base offset, for IDX files packed into a container file
+///
+/// Positions are relative to the start of the window, so a cursor opened on it reads the header at the base offset:
+///
+/// ```no_run
+/// use idxrs::IdxCursor;
+/// use idxrs::SubReader;
+/// use std::fs::File;
+///
+/// // Labels stored after 47040016 bytes of images
+/// let reader = SubReader::with_len(File::open("mnist.pack")?, 47040016, 60008)?;
+/// let mut labels = IdxCursor::new(reader)?;
+/// # Ok::<(), idxrs::IdxError>(())
+/// ```
+pub struct SubReader<R: Read + Seek> {
+    reader: R,
+    offset: u64,
+    len: u64,
+    pos: u64,
+}
+
+impl<R: Read + Seek> SubReader<R> {
+    /// Window from `offset` to the end of the stream
+    pub fn new(mut reader: R, offset: u64) -> io::Result<SubReader<R>> {
+        let end = reader.seek(SeekFrom::End(0))?;
+        SubReader::with_len(reader, offset, end.saturating_sub(offset))
+    }
+
+    /// Window of `len` bytes from `offset`, reads stop at its end and seeking from the end starts there
+    pub fn with_len(mut reader: R, offset: u64, len: u64) -> io::Result<SubReader<R>> {
+        reader.seek(SeekFrom::Start(offset))?;
+        Ok(SubReader { reader, offset, len, pos: 0 })
+    }
+
+    /// Position of the window in the underlying stream
+    pub fn offset(&self) -> u64 {
+        self.offset
+    }
+
+    /// Size of the window
+    pub fn len(&self) -> u64 {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+}
+
+impl<R: Read + Seek> IdxCursor<SubReader<R>> {
+    /// Opens the IDX file that starts at `offset` of the stream and runs to its end
+    pub fn at_offset(reader: R, offset: u64) -> Result<IdxCursor<SubReader<R>>, IdxError> {
+        IdxCursor::new(SubReader::new(reader, offset)?)
+    }
+}
+
+impl<R: Read + Seek> Read for SubReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let remaining = self.len.saturating_sub(self.pos);
+        let n = (buf.len() as u64).min(remaining) as usize;
+        let n = self.reader.read(&mut buf[..n])?;
+        self.pos += n as u64;
+        Ok(n)
+    }
+}
+
+impl<R: Read + Seek> Seek for SubReader<R> {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        let (base, delta) = match pos {
+            SeekFrom::Start(p)   => (p, 0),
+            SeekFrom::End(d)     => (self.len, d),
+            SeekFrom::Current(d) => (self.pos, d),
+        };
+        let pos = if delta < 0 { base.checked_sub(delta.unsigned_abs()) } else { base.checked_add(delta as u64) };
+        let pos = pos.ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "seek before the start of the window"))?;
+        let absolute = self.offset.checked_add(pos).ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "seek position overflows"))?;
+        self.reader.seek(SeekFrom::Start(absolute))?;
+        self.pos = pos;
+        Ok(pos)
+    }
+}
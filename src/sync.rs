@@ -0,0 +1,101 @@
+use std::fs::File;
+use std::io;
+use std::path::Path;
+use std::sync::Arc;
+
+use crate::buffer_len;
+use crate::byte_offset;
+use crate::Endianness;
+use crate::IdxCursor;
+use crate::IdxDataType;
+use crate::IdxError;
+use crate::IdxHeader;
+use crate::IdxOptions;
+use crate::IdxShape;
+use crate::IdxSlice;
+use crate::IdxValue;
+
+/// Reader that can be shared between threads, every read is positional so `get` only needs `&self`
+///
+/// Clones share the open file. Compressed files are not supported, decompress them first.
+#[derive(Clone)]
+pub struct SyncIdxReader {
+    file: Arc<File>,
+    shape: IdxShape,
+    data_type: IdxDataType,
+    endianness: Endianness,
+}
+
+#[cfg(unix)]
+fn read_at(file: &File, buf: &mut [u8], offset: u64) -> io::Result<()> {
+    use std::os::unix::fs::FileExt;
+    file.read_exact_at(buf, offset)
+}
+
+#[cfg(windows)]
+fn read_at(file: &File, mut buf: &mut [u8], mut offset: u64) -> io::Result<()> {
+    use std::os::windows::fs::FileExt;
+    while !buf.is_empty() {
+        match file.seek_read(buf, offset)? {
+            0 => return Err(io::ErrorKind::UnexpectedEof.into()),
+            n => {
+                buf = &mut buf[n..];
+                offset += n as u64;
+            }
+        }
+    }
+    Ok(())
+}
+
+impl SyncIdxReader {
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<SyncIdxReader, IdxError> {
+        SyncIdxReader::open_with_options(path, IdxOptions::default())
+    }
+
+    /// Like `open`, with the same checks as `IdxCursor::with_options`
+    pub fn open_with_options<P: AsRef<Path>>(path: P, options: IdxOptions) -> Result<SyncIdxReader, IdxError> {
+        let file = File::open(path)?;
+        let endianness = options.endianness;
+        let cursor = IdxCursor::with_options(&file, options)?;
+        let (shape, data_type) = (cursor.shape.clone(), cursor.data_type);
+        Ok(SyncIdxReader { file: Arc::new(file), shape, data_type, endianness })
+    }
+
+    pub fn dimensions(&self) -> &[u32] {
+        self.shape.dimensions()
+    }
+
+    pub fn dtype(&self) -> IdxDataType {
+        self.data_type
+    }
+
+    pub fn header(&self) -> IdxHeader {
+        IdxHeader::new(self.data_type, self.shape.dimensions())
+    }
+
+    /// Reads `buf.len()` bytes starting at element `offset` and converts them to big endian
+    fn read_elements(&self, offset: u64, buf: &mut [u8]) -> Result<(), IdxError> {
+        let size = self.data_type.get_size() as u64;
+        let pos = byte_offset(self.shape.dimensions().len(), offset, size)?;
+        read_at(&self.file, buf, pos).map_err(|source| IdxError::ReadFailed{ offset: pos, len: buf.len() as u64, source })?;
+        self.endianness.swap_be(buf, size as usize);
+        Ok(())
+    }
+
+    pub fn get(&self, indices: &[u32]) -> Result<IdxValue, IdxError> {
+        let size = self.data_type.get_size() as usize;
+        let mut buffer = [0; 8];
+        self.read_elements(self.shape.flat_offset(indices)?, &mut buffer[..size])?;
+        IdxValue::decode(self.data_type, &buffer[..size])
+    }
+
+    /// Reads the whole block spanned by the trailing dimensions after the leading indices
+    pub fn get_record(&self, leading_indices: &[u32]) -> Result<IdxSlice, IdxError> {
+        let offset = self.shape.record_offset(leading_indices)?;
+        let record_len = self.shape.record_len(leading_indices.len());
+        let mut buffer = vec![0; buffer_len(record_len, self.data_type.get_size() as u64)?].into_boxed_slice();
+        self.read_elements(offset, &mut buffer)?;
+        let trailing = self.shape.dimensions()[leading_indices.len()..].to_vec();
+        Ok(IdxSlice::new(buffer, self.data_type, IdxShape::new(trailing)))
+    }
+}
@@ -0,0 +1,162 @@
+//! Helpers for checking that files read back exactly as they were written, in every data type and byte order
+//!
+//! ```
+//! use std::io;
+//! use idxrs::testing;
+//! use idxrs::{IdxCursor, IdxDataType};
+//!
+//! for data_type in &[IdxDataType::UnsignedByte, IdxDataType::Short, IdxDataType::Double] {
+//!     let data = testing::generate_random_idx(*data_type, &[3, 4, 5], 42)?;
+//!     let mut cursor = IdxCursor::from_bytes(data)?;
+//!     testing::assert_roundtrip(&mut cursor, io::Cursor::new(Vec::new()));
+//! }
+//! # Ok::<(), idxrs::IdxError>(())
+//! ```
+
+use std::io;
+use std::io::Read;
+use std::io::Seek;
+use std::io::SeekFrom;
+use std::io::Write;
+
+use crate::rng::Rng;
+use crate::IdxCursor;
+use crate::IdxDataType;
+use crate::IdxError;
+use crate::IdxOptions;
+use crate::IdxValue;
+use crate::IdxWriter;
+
+/// Complete big endian IDX file of the shape with random values, the same seed always gives the same file
+///
+/// Every bit pattern of the data type may occur, including infinities and subnormal floats, except NaN, which
+/// would not compare equal to itself.
+pub fn generate_random_idx(data_type: IdxDataType, shape: &[u32], seed: u64) -> Result<Vec<u8>, IdxError> {
+    let mut writer = IdxWriter::new(io::Cursor::new(Vec::new()), data_type, shape)?;
+    let mut rng = Rng::new(seed);
+    let size = data_type.get_size() as usize;
+    let count: u64 = shape.iter().map(|d| *d as u64).product();
+    for _ in 0..count {
+        let value = loop {
+            let bytes = rng.next_u64().to_be_bytes();
+            let value = IdxValue::decode(data_type, &bytes[..size])?;
+            if !value.as_f64().is_nan() {
+                break value;
+            }
+        };
+        writer.append(value)?;
+    }
+    Ok(writer.finish()?.into_inner())
+}
+
+/// Writes every value of the cursor into `writer` with the data type, dimensions and byte order of the cursor,
+/// and reads the result back
+///
+/// Panics with the indices of the first value that does not read back unchanged.
+pub fn assert_roundtrip<R: Read + Seek, W: Read + Write + Seek>(cursor: &mut IdxCursor<R>, writer: W) {
+    let endianness = cursor.endianness();
+    let mut out = IdxWriter::with_endianness(writer, cursor.dtype(), cursor.dimensions(), endianness)
+        .expect("header can be written");
+    for res in cursor.iter() {
+        let (index, value) = res.expect("source can be read");
+        out.append(value).unwrap_or_else(|e| panic!("value {} at {:?} cannot be written: {}", value, index, e));
+    }
+    let mut writer = out.finish().expect("payload can be written");
+    writer.seek(SeekFrom::Start(0)).expect("written file can be seeked");
+
+    let options = IdxOptions { endianness, ..IdxOptions::default() };
+    let mut written = IdxCursor::with_options(writer, options).expect("written header can be read");
+    assert_eq!(written.dtype(), cursor.dtype(), "data type changed");
+    assert_eq!(written.dimensions(), cursor.dimensions(), "dimensions changed");
+    for (expected, actual) in cursor.iter().zip(written.iter()) {
+        let (index, expected) = expected.expect("source can be read");
+        let (_, actual) = actual.expect("written file can be read");
+        // NaN payloads are not compared, any NaN reads back as a NaN
+        let both_nan = expected.as_f64().is_nan() && actual.as_f64().is_nan();
+        assert!(expected == actual || both_nan, "value at {:?} was {} and reads back as {}", index, expected, actual);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io;
+
+    use super::{assert_roundtrip, generate_random_idx};
+    use crate::Endianness;
+    use crate::IdxCursor;
+    use crate::IdxDataType;
+    use crate::IdxOptions;
+    use crate::IdxValue;
+    use crate::IdxWriter;
+    use crate::StreamingIdxWriter;
+
+    const SHAPE: [u32; 3] = [4, 3, 5];
+
+    fn data_types() -> Vec<IdxDataType> {
+        #[allow(unused_mut)]
+        let mut data_types = vec![
+            IdxDataType::UnsignedByte, IdxDataType::SignedByte, IdxDataType::Short,
+            IdxDataType::Int, IdxDataType::Float, IdxDataType::Double,
+        ];
+        #[cfg(feature = "extensions")]
+        data_types.extend_from_slice(&[
+            IdxDataType::UnsignedShort, IdxDataType::UnsignedInt, IdxDataType::Long, IdxDataType::UnsignedLong,
+        ]);
+        data_types
+    }
+
+    /// Random file of the data type in the byte order, opened with the matching options
+    fn random_cursor(data_type: IdxDataType, endianness: Endianness, seed: u64) -> IdxCursor<io::Cursor<Vec<u8>>> {
+        let mut big = IdxCursor::from_bytes(generate_random_idx(data_type, &SHAPE, seed).unwrap()).unwrap();
+        let mut writer = IdxWriter::with_endianness(io::Cursor::new(Vec::new()), data_type, &SHAPE, endianness).unwrap();
+        for res in big.iter() {
+            writer.append(res.unwrap().1).unwrap();
+        }
+        let options = IdxOptions { endianness, ..IdxOptions::default() };
+        IdxCursor::with_options(io::Cursor::new(writer.finish().unwrap().into_inner()), options).unwrap()
+    }
+
+    fn values<R: io::Read + io::Seek>(cursor: &mut IdxCursor<R>) -> Vec<IdxValue> {
+        cursor.iter().map(|res| res.unwrap().1).collect()
+    }
+
+    #[test]
+    fn roundtrip_idx_writer() {
+        for data_type in data_types() {
+            for endianness in &[Endianness::Big, Endianness::Little] {
+                let mut cursor = random_cursor(data_type, *endianness, 7);
+                assert_roundtrip(&mut cursor, io::Cursor::new(Vec::new()));
+            }
+        }
+    }
+
+    #[test]
+    fn roundtrip_streaming_writer() {
+        for data_type in data_types() {
+            for endianness in &[Endianness::Big, Endianness::Little] {
+                let mut cursor = random_cursor(data_type, *endianness, 11);
+                let expected = values(&mut cursor);
+                let mut writer = StreamingIdxWriter::with_endianness(io::Cursor::new(Vec::new()), data_type, &SHAPE[1..], *endianness).unwrap();
+                for record in expected.chunks(writer.record_len()) {
+                    writer.append_record(record).unwrap();
+                }
+                let data = writer.finish().unwrap().into_inner();
+                let options = IdxOptions { endianness: *endianness, ..IdxOptions::default() };
+                let mut written = IdxCursor::with_options(io::Cursor::new(data), options).unwrap();
+                assert_eq!(written.dtype(), data_type);
+                assert_eq!(written.dimensions(), &SHAPE);
+                assert_eq!(values(&mut written), expected, "{:?} {:?}", data_type, endianness);
+            }
+        }
+    }
+
+    #[test]
+    fn little_endian_is_swapped() {
+        for data_type in data_types().into_iter().filter(|t| t.get_size() > 1) {
+            let big = random_cursor(data_type, Endianness::Big, 3).reader.into_inner().into_inner();
+            let little = random_cursor(data_type, Endianness::Little, 3).reader.into_inner().into_inner();
+            assert_eq!(big.len(), little.len());
+            assert_ne!(big[4..], little[4..], "{:?}", data_type);
+        }
+    }
+}
@@ -0,0 +1,219 @@
+//! Lazy transforms that are applied to records while they are read from a cursor
+//!
+//! ```no_run
+//! use idxrs::transforms;
+//! use idxrs::IdxFile;
+//!
+//! let mut images = IdxFile::open("train-images-idx3-ubyte")?;
+//! let out = std::fs::File::create("train-images-cropped.idx")?;
+//! transforms::records(&mut images)
+//!     .crop(&[4..24, 4..24])?
+//!     .scale(1.0 / 255.0)
+//!     .cast::<f32>()
+//!     .collect_to_idx(out)?;
+//! # Ok::<(), idxrs::IdxError>(())
+//! ```
+
+use std::io::Read;
+use std::io::Seek;
+use std::io::Write;
+use std::ops::Range;
+
+use crate::shape::advance;
+use crate::IdxCursor;
+use crate::IdxDataType;
+use crate::IdxElement;
+use crate::IdxError;
+use crate::IdxShape;
+use crate::IdxValue;
+use crate::StreamingIdxWriter;
+
+type Records<'a> = Box<dyn Iterator<Item = Result<Vec<IdxValue>, IdxError>> + 'a>;
+
+/// Chain of transforms over the records of a cursor, nothing is read before the pipeline is iterated
+pub struct Pipeline<'a> {
+    records: Records<'a>,
+    data_type: IdxDataType,
+    record_shape: Vec<u32>,
+}
+
+/// Starts a pipeline with the values of each record along the leading dimension, a scalar file is a single record
+pub fn records<R: Read + Seek>(cursor: &mut IdxCursor<R>) -> Pipeline<'_> {
+    let data_type = cursor.data_type;
+    let leading = cursor.dimensions().len().min(1);
+    let record_shape = cursor.dimensions()[leading..].to_vec();
+    let records = (0..cursor.num_records()).map(move |i| {
+        let index = [i];
+        let record = cursor.get_record(&index[..leading])?;
+        record.as_bytes()
+            .chunks_exact(data_type.get_size() as usize)
+            .map(|b| IdxValue::decode(data_type, b))
+            .collect()
+    });
+    Pipeline { records: Box::new(records), data_type, record_shape }
+}
+
+impl<'a> Pipeline<'a> {
+    /// Data type the records have after all transforms so far
+    pub fn data_type(&self) -> IdxDataType {
+        self.data_type
+    }
+
+    /// Shape of each record after all transforms so far
+    pub fn record_shape(&self) -> &[u32] {
+        &self.record_shape
+    }
+
+    pub(crate) fn map_records<F>(self, data_type: IdxDataType, record_shape: Vec<u32>, mut f: F) -> Pipeline<'a>
+    where
+        F: FnMut(Vec<IdxValue>) -> Vec<IdxValue> + 'a,
+    {
+        let records = self.records.map(move |res| res.map(&mut f));
+        Pipeline { records: Box::new(records), data_type, record_shape }
+    }
+
+    /// Applies `f` to every value, results keep the data type and have to be exactly representable in it
+    pub fn map_values<F: FnMut(IdxValue) -> IdxValue + 'a>(self, mut f: F) -> Pipeline<'a> {
+        let (data_type, record_shape) = (self.data_type, self.record_shape.clone());
+        self.map_records(data_type, record_shape, move |values| values.into_iter().map(&mut f).collect())
+    }
+
+    /// Multiplies every value by `factor`, the records become doubles
+    pub fn scale(self, factor: f64) -> Pipeline<'a> {
+        let record_shape = self.record_shape.clone();
+        self.map_records(IdxDataType::Double, record_shape, move |values| {
+            values.into_iter().map(|v| IdxValue::Double(v.as_f64() * factor)).collect()
+        })
+    }
+
+    /// Converts every value to `T` like an `as` cast
+    pub fn cast<T: IdxElement + Into<IdxValue>>(self) -> Pipeline<'a> {
+        let record_shape = self.record_shape.clone();
+        self.map_records(T::DATA_TYPE, record_shape, |values| {
+            values.into_iter().map(|v| T::from_value_lossy(v).into()).collect()
+        })
+    }
+
+    /// Keeps a range of each record dimension, dimensions without a range are kept completely
+    ///
+    /// `crop(&[4..24, 4..24])` cuts the centre 20x20 pixels out of 28x28 images.
+    pub fn crop(self, ranges: &[Range<u32>]) -> Result<Pipeline<'a>, IdxError> {
+        let dimensions = &self.record_shape;
+        if ranges.len() > dimensions.len() {
+            return Err(IdxError::DimensionMismatch{ needed: dimensions.len(), supplied: ranges.len() });
+        }
+        let mut starts = Vec::with_capacity(dimensions.len());
+        let mut sizes = Vec::with_capacity(dimensions.len());
+        for (i, dimension) in dimensions.iter().enumerate() {
+            let range = ranges.get(i).cloned().unwrap_or(0..*dimension);
+            if range.start > range.end || range.end > *dimension {
+                return Err(IdxError::OutOfBounds{ dimension: i, max: *dimension, index: range.end.max(range.start) });
+            }
+            starts.push(range.start);
+            sizes.push(range.end - range.start);
+        }
+        // Positions of the kept values are the same for every record
+        let source = IdxShape::new(dimensions.clone());
+        let cropped = IdxShape::new(sizes.clone());
+        let mut index = vec![0; sizes.len()];
+        let mut positions = Vec::with_capacity(cropped.len() as usize);
+        for _ in 0..cropped.len() {
+            let pos: u64 = index.iter().zip(&starts).zip(source.strides()).map(|((i, s), stride)| (i + s) as u64 * stride).sum();
+            positions.push(pos as usize);
+            advance(&mut index, &sizes);
+        }
+        let data_type = self.data_type;
+        Ok(self.map_records(data_type, sizes, move |values| positions.iter().map(|pos| values[*pos]).collect()))
+    }
+
+    /// Turns every record into a single dimension of all its values
+    pub fn flatten(self) -> Pipeline<'a> {
+        let (data_type, len) = (self.data_type, self.record_shape.iter().product());
+        self.map_records(data_type, vec![len], |values| values)
+    }
+
+    /// Writes all records as N x record shape file, fails on the first record that cannot be read or stored
+    pub fn collect_to_idx<W: Write + Seek>(self, writer: W) -> Result<W, IdxError> {
+        let mut writer = StreamingIdxWriter::new(writer, self.data_type, &self.record_shape)?;
+        for values in self.records {
+            writer.append_record(&values?)?;
+        }
+        writer.finish()
+    }
+}
+
+/// Records as values in storage order
+impl<'a> Iterator for Pipeline<'a> {
+    type Item = Result<Vec<IdxValue>, IdxError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.records.next()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io;
+
+    use super::records;
+    use crate::IdxCursor;
+    use crate::IdxDataType;
+    use crate::IdxError;
+    use crate::IdxValue;
+    use crate::IdxWriter;
+
+    /// u8 file of shape [2, 3, 4] where every element holds its flat index
+    fn cursor() -> IdxCursor<io::Cursor<Vec<u8>>> {
+        let values: Vec<u8> = (0..24).collect();
+        IdxCursor::from_bytes(IdxWriter::write_array(io::Cursor::new(Vec::new()), &[2, 3, 4], &values).unwrap().into_inner()).unwrap()
+    }
+
+    fn as_f64(records: Vec<Result<Vec<IdxValue>, IdxError>>) -> Vec<Vec<f64>> {
+        records.into_iter().map(|r| r.unwrap().iter().map(IdxValue::as_f64).collect()).collect()
+    }
+
+    #[test]
+    fn crop_keeps_the_region() {
+        let mut cursor = cursor();
+        let cropped = records(&mut cursor).crop(&[1..3, 2..4]).unwrap();
+        assert_eq!(cropped.record_shape(), &[2, 2]);
+        assert_eq!(as_f64(cropped.collect()), vec![vec![6.0, 7.0, 10.0, 11.0], vec![18.0, 19.0, 22.0, 23.0]]);
+        // Dimensions without a range are kept completely
+        let rows = records(&mut cursor).crop(std::slice::from_ref(&(2..3))).unwrap();
+        assert_eq!(rows.record_shape(), &[1, 4]);
+        assert_eq!(as_f64(rows.collect()), vec![vec![8.0, 9.0, 10.0, 11.0], vec![20.0, 21.0, 22.0, 23.0]]);
+        assert!(matches!(records(&mut cursor).crop(&[0..3, 1..5]).err(), Some(IdxError::OutOfBounds{ dimension: 1, max: 4, index: 5 })));
+        assert!(matches!(records(&mut cursor).crop(&[0..3, 0..4, 0..1]).err(), Some(IdxError::DimensionMismatch{ needed: 2, supplied: 3 })));
+    }
+
+    #[test]
+    fn flatten_shape() {
+        let mut cursor = cursor();
+        let flat = records(&mut cursor).crop(&[0..2, 1..4]).unwrap().flatten();
+        assert_eq!(flat.record_shape(), &[6]);
+        assert_eq!(flat.data_type(), IdxDataType::UnsignedByte);
+        let written = flat.collect_to_idx(io::Cursor::new(Vec::new())).unwrap().into_inner();
+        let (values, shape) = IdxCursor::from_bytes(written).unwrap().to_vec::<u8>().unwrap();
+        assert_eq!(shape.dimensions(), &[2, 6]);
+        assert_eq!(values, vec![1, 2, 3, 5, 6, 7, 13, 14, 15, 17, 18, 19]);
+    }
+
+    #[test]
+    fn cast_truncates_and_saturates() {
+        let mut cursor = cursor();
+        // Like `as`, fractions are cut off towards zero
+        let cast = records(&mut cursor).scale(0.75).cast::<u8>();
+        assert_eq!(cast.data_type(), IdxDataType::UnsignedByte);
+        let values = as_f64(cast.collect());
+        assert_eq!(&values[0][..4], &[0.0, 0.0, 1.0, 2.0]);
+        assert_eq!(values[1][11], 17.0);
+        let values = as_f64(records(&mut cursor).scale(-0.75).cast::<i8>().collect());
+        assert_eq!(&values[0][..4], &[0.0, 0.0, -1.0, -2.0]);
+        // Values outside of the type saturate at its bounds
+        let values = as_f64(records(&mut cursor).scale(20.0).cast::<u8>().collect());
+        assert_eq!(&values[0][..3], &[0.0, 20.0, 40.0]);
+        assert_eq!(values[1][11], 255.0);
+        let values = as_f64(records(&mut cursor).scale(-10.0).cast::<i8>().collect());
+        assert_eq!(values[1][11], -128.0);
+    }
+}
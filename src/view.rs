@@ -0,0 +1,365 @@
+use std::io::Read;
+use std::io::Seek;
+use std::io::SeekFrom;
+use std::io::Write;
+use std::ops::Range;
+
+use crate::decode_into;
+use crate::read_exact_at;
+use crate::IdxCursor;
+use crate::IdxDataType;
+use crate::IdxElement;
+use crate::IdxError;
+use crate::IdxShape;
+use crate::IdxValue;
+use crate::StreamingIdxWriter;
+
+/// Rectangular region of a file, created by `IdxCursor::slice`, elements are only read when accessed
+pub struct IdxView<'a, R: Read + Seek> {
+    cursor: &'a mut IdxCursor<R>,
+    // Start of the region in every dimension of the file
+    start: Vec<u32>,
+    // Dimension of the file behind every dimension of the view
+    axes: Vec<usize>,
+    shape: IdxShape,
+    // Dimensions the payload is indexed with, those of the file unless reshaped
+    layout: IdxShape,
+}
+
+impl<R: Read + Seek> IdxCursor<R> {
+    /// Selects a range of each dimension, dimensions without a range are taken completely
+    ///
+    /// `slice(&[1000..2000])` takes records 1000 to 1999, `slice(&[0..n, 4..24, 4..24])` crops every image to 20x20.
+    pub fn slice(&mut self, ranges: &[Range<u32>]) -> Result<IdxView<'_, R>, IdxError> {
+        let dimensions = self.shape.dimensions();
+        if ranges.len() > dimensions.len() {
+            return Err(IdxError::DimensionMismatch{ needed: dimensions.len(), supplied: ranges.len() });
+        }
+        let mut start = Vec::with_capacity(dimensions.len());
+        let mut sizes = Vec::with_capacity(dimensions.len());
+        for (i, dimension) in dimensions.iter().enumerate() {
+            let range = ranges.get(i).cloned().unwrap_or(0..*dimension);
+            if range.start > range.end || range.end > *dimension {
+                return Err(IdxError::OutOfBounds{ dimension: i, max: *dimension, index: range.end.max(range.start) });
+            }
+            start.push(range.start);
+            sizes.push(range.end - range.start);
+        }
+        let axes = (0..dimensions.len()).collect();
+        let layout = self.shape.clone();
+        Ok(IdxView { cursor: self, start, axes, shape: IdxShape::new(sizes), layout })
+    }
+
+    /// Indexes the whole payload with other dimensions of the same number of elements, without reading or copying
+    ///
+    /// Fails with `ShapeMismatch` if the number of elements differs.
+    ///
+    /// ```
+    /// use idxrs::{testing, IdxCursor, IdxDataType};
+    ///
+    /// let mut cursor = IdxCursor::from_bytes(testing::generate_random_idx(IdxDataType::UnsignedByte, &[10, 28, 28], 1)?)?;
+    /// let pixel = cursor.get(&[3, 1, 2])?;
+    /// let mut flat = cursor.reshape(&[10, 784])?;
+    /// assert_eq!(flat.get(&[3, 28 + 2])?, pixel);
+    /// assert!(cursor.reshape(&[10, 700]).is_err());
+    /// # Ok::<(), idxrs::IdxError>(())
+    /// ```
+    pub fn reshape(&mut self, dimensions: &[u32]) -> Result<IdxView<'_, R>, IdxError> {
+        let layout = IdxShape::try_new(dimensions.to_vec())?;
+        if layout.len() != self.shape.len() {
+            return Err(IdxError::ShapeMismatch{ expected: self.shape.dimensions().to_vec(), found: dimensions.to_vec() });
+        }
+        let start = vec![0; dimensions.len()];
+        let axes = (0..dimensions.len()).collect();
+        Ok(IdxView { cursor: self, start, axes, shape: layout.clone(), layout })
+    }
+}
+
+impl<'a, R: Read + Seek> IdxView<'a, R> {
+    /// Reorders the dimensions, dimension `i` of the new view is dimension `axes[i]` of this one
+    ///
+    /// Nothing is read or copied, `get` and everything else follow the new order. Converts e.g. records stored as
+    /// height x width x channels to channels x height x width:
+    ///
+    /// ```
+    /// use std::io;
+    /// use idxrs::{IdxCursor, IdxWriter};
+    ///
+    /// // 1 record of 2 x 2 pixels with 3 channels each
+    /// let hwc: Vec<u8> = (0..12).collect();
+    /// let data = IdxWriter::write_array(io::Cursor::new(Vec::new()), &[1, 2, 2, 3], &hwc)?.into_inner();
+    /// let mut cursor = IdxCursor::from_bytes(data)?;
+    /// let mut chw = cursor.slice(&[])?.permute(&[0, 3, 1, 2])?;
+    /// assert_eq!(chw.dimensions(), &[1, 3, 2, 2]);
+    /// assert_eq!(chw.get_as::<u8>(&[0, 2, 1, 0])?, 8);
+    ///
+    /// let written = chw.write_to(io::Cursor::new(Vec::new()))?.into_inner();
+    /// let (values, _) = IdxCursor::from_bytes(written)?.to_vec::<u8>()?;
+    /// assert_eq!(values, vec![0, 3, 6, 9, 1, 4, 7, 10, 2, 5, 8, 11]);
+    /// # Ok::<(), idxrs::IdxError>(())
+    /// ```
+    pub fn permute(self, axes: &[usize]) -> Result<IdxView<'a, R>, IdxError> {
+        let dimensions = self.shape.dimensions();
+        if axes.len() != dimensions.len() {
+            return Err(IdxError::DimensionMismatch{ needed: dimensions.len(), supplied: axes.len() });
+        }
+        let mut seen = vec![false; axes.len()];
+        for axis in axes {
+            if *axis >= axes.len() || seen[*axis] {
+                return Err(IdxError::InvalidPermutation(axes.to_vec()));
+            }
+            seen[*axis] = true;
+        }
+        let sizes = axes.iter().map(|a| dimensions[*a]).collect();
+        let axes = axes.iter().map(|a| self.axes[*a]).collect();
+        Ok(IdxView { cursor: self.cursor, start: self.start, axes, shape: IdxShape::new(sizes), layout: self.layout })
+    }
+
+    /// Swaps rows and columns of a 2-D view
+    pub fn transpose(self) -> Result<IdxView<'a, R>, IdxError> {
+        let dimensions = self.shape.dimensions().len();
+        if dimensions != 2 {
+            return Err(IdxError::DimensionMismatch{ needed: 2, supplied: dimensions });
+        }
+        self.permute(&[1, 0])
+    }
+
+    /// Sizes of the selected ranges, in the order of `permute`
+    pub fn dimensions(&self) -> &[u32] {
+        self.shape.dimensions()
+    }
+
+    pub fn data_type(&self) -> IdxDataType {
+        self.cursor.data_type
+    }
+
+    /// Number of elements in the view
+    pub fn len(&self) -> u64 {
+        self.shape.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.shape.is_empty()
+    }
+
+    /// Reads element at indices relative to the start of the view
+    pub fn get(&mut self, indices: &[u32]) -> Result<IdxValue, IdxError> {
+        self.shape.flat_offset(indices)?;
+        let mut absolute = self.start.clone();
+        for (index, axis) in indices.iter().zip(&self.axes) {
+            absolute[*axis] += index;
+        }
+        let size = self.cursor.element_size;
+        let pos = self.cursor.header_len + self.layout.flat_offset(&absolute)? * size;
+        let mut buffer = [0; 8];
+        let buffer = &mut buffer[..size as usize];
+        self.cursor.reader.seek(SeekFrom::Start(pos))?;
+        read_exact_at(&mut self.cursor.reader, pos, buffer)?;
+        self.cursor.endianness.swap_be(buffer, size as usize);
+        IdxValue::decode(self.cursor.data_type, buffer)
+    }
+
+    /// Reads element and converts it to `T`, fails if the value is not exactly representable
+    pub fn get_as<T: IdxElement>(&mut self, indices: &[u32]) -> Result<T, IdxError> {
+        T::from_value(self.get(indices)?)
+    }
+
+    /// Elements along the last dimension are the longest runs that can be contiguous in the file
+    fn row_len(&self) -> u64 {
+        self.shape.dimensions().last().map(|d| *d as u64).unwrap_or(1)
+    }
+
+    fn rows(&self) -> u64 {
+        self.shape.len().checked_div(self.row_len()).unwrap_or(0)
+    }
+
+    /// Reads big endian bytes of row `row` into `buf`, which has to hold exactly one row
+    fn read_row(&mut self, row: u64, buf: &mut [u8]) -> Result<(), IdxError> {
+        let dimensions = self.shape.dimensions();
+        let strides = self.layout.strides();
+        let mut offset = 0;
+        let mut rest = row;
+        // Split the row number into indices of all but the last dimension, starting from the back
+        for i in (0..dimensions.len()).rev() {
+            let index = if i + 1 == dimensions.len() { 0 } else {
+                let index = rest % dimensions[i] as u64;
+                rest /= dimensions[i] as u64;
+                index
+            };
+            let axis = self.axes[i];
+            offset += (self.start[axis] as u64 + index) * strides[axis];
+        }
+        let size = self.cursor.element_size;
+        // A permuted last dimension has its elements spread out, they are read one by one
+        let step = self.axes.last().map_or(1, |axis| strides[*axis]);
+        let run = if step == 1 { buf.len() } else { size as usize };
+        for (j, part) in buf.chunks_exact_mut(run.max(1)).enumerate() {
+            let pos = self.cursor.header_len + (offset + j as u64 * step) * size;
+            self.cursor.reader.seek(SeekFrom::Start(pos))?;
+            read_exact_at(&mut self.cursor.reader, pos, part)?;
+        }
+        self.cursor.endianness.swap_be(buf, size as usize);
+        Ok(())
+    }
+
+    fn read_bytes(&mut self) -> Result<Vec<u8>, IdxError> {
+        let row_bytes = (self.row_len() * self.cursor.element_size) as usize;
+        let rows = self.rows();
+        let mut bytes = vec![0; rows as usize * row_bytes];
+        for (row, buf) in (0..rows).zip(bytes.chunks_exact_mut(row_bytes.max(1))) {
+            self.read_row(row, buf)?;
+        }
+        Ok(bytes)
+    }
+
+    /// Reads all elements of the view converted to `T`, fails on values that are not exactly representable
+    pub fn to_vec<T: IdxElement>(&mut self) -> Result<(Vec<T>, IdxShape), IdxError> {
+        let bytes = self.read_bytes()?;
+        let mut out = Vec::with_capacity(self.shape.len() as usize);
+        decode_into(self.cursor.data_type, &bytes, &mut out, false)?;
+        Ok((out, self.shape.clone()))
+    }
+
+    /// Iterates over all elements of the view in storage order with indices relative to the view
+    pub fn iter(&mut self) -> IdxViewIter<'_, 'a, R> {
+        IdxViewIter { view: self, next: 0, row: Vec::new(), failed: false }
+    }
+
+    /// Writes the view as a new big endian IDX file and returns the writer
+    pub fn write_to<W: Write + Seek>(&mut self, writer: W) -> Result<W, IdxError> {
+        let dimensions = self.shape.dimensions().to_vec();
+        if dimensions.is_empty() {
+            return Err(IdxError::DimensionMismatch{ needed: 1, supplied: 0 });
+        }
+        let mut records = StreamingIdxWriter::new(writer, self.cursor.data_type, &dimensions[1..])?;
+        let row_bytes = (self.row_len() * self.cursor.element_size) as usize;
+        let rows_per_record = self.rows().checked_div(dimensions[0] as u64).unwrap_or(0);
+        let mut record = vec![0; rows_per_record as usize * row_bytes];
+        for i in 0..dimensions[0] as u64 {
+            for (j, buf) in record.chunks_exact_mut(row_bytes.max(1)).enumerate() {
+                self.read_row(i * rows_per_record + j as u64, buf)?;
+            }
+            // Records without elements take no bytes, they are counted one by one
+            if record.is_empty() {
+                records.append_record(&[])?;
+            } else {
+                records.append_record_bytes(&record)?;
+            }
+        }
+        records.finish()
+    }
+}
+
+/// Iterator over the elements of a view, created by `IdxView::iter`
+pub struct IdxViewIter<'v, 'a, R: Read + Seek> {
+    view: &'v mut IdxView<'a, R>,
+    next: u64,
+    // Big endian bytes of the row that contains the next element
+    row: Vec<u8>,
+    failed: bool,
+}
+
+impl<'v, 'a, R: Read + Seek> Iterator for IdxViewIter<'v, 'a, R> {
+    type Item = Result<(Vec<u32>, IdxValue), IdxError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.failed || self.next >= self.view.len() {
+            return None;
+        }
+        let size = self.view.cursor.element_size as usize;
+        let row_len = self.view.row_len();
+        let column = (self.next % row_len) as usize;
+        if column == 0 {
+            self.row.resize(row_len as usize * size, 0);
+            let mut row = std::mem::take(&mut self.row);
+            let res = self.view.read_row(self.next / row_len, &mut row);
+            self.row = row;
+            if let Err(e) = res {
+                self.failed = true;
+                return Some(Err(e));
+            }
+        }
+        let indices = self.view.shape.strides().iter().zip(self.view.shape.dimensions())
+            .map(|(stride, dimension)| (self.next / stride % *dimension as u64) as u32)
+            .collect();
+        self.next += 1;
+        let value = IdxValue::decode(self.view.cursor.data_type, &self.row[column * size..(column + 1) * size]);
+        Some(value.map(|value| (indices, value)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io;
+    use std::ops::Range;
+
+    use crate::IdxCursor;
+    use crate::IdxElement;
+    use crate::IdxError;
+    use crate::IdxWriter;
+
+    /// u8 file of shape [3, 4, 5] where every element holds its flat index
+    fn cursor() -> IdxCursor<io::Cursor<Vec<u8>>> {
+        let values: Vec<u8> = (0..60).collect();
+        IdxCursor::from_bytes(IdxWriter::write_array(io::Cursor::new(Vec::new()), &[3, 4, 5], &values).unwrap().into_inner()).unwrap()
+    }
+
+    #[test]
+    fn cropped_region() {
+        let mut cursor = cursor();
+        let mut view = cursor.slice(&[1..3, 1..3, 2..5]).unwrap();
+        assert_eq!(view.dimensions(), &[2, 2, 3]);
+        assert_eq!(view.len(), 12);
+        assert_eq!(view.get_as::<u8>(&[0, 0, 0]).unwrap(), 27);
+        assert_eq!(view.get_as::<u8>(&[1, 1, 2]).unwrap(), 54);
+        let (values, shape) = view.to_vec::<u8>().unwrap();
+        assert_eq!(shape.dimensions(), &[2, 2, 3]);
+        assert_eq!(values, vec![27, 28, 29, 32, 33, 34, 47, 48, 49, 52, 53, 54]);
+        let iterated: Vec<(Vec<u32>, u8)> = view.iter().map(|e| e.map(|(i, v)| (i, u8::from_value(v).unwrap())).unwrap()).collect();
+        assert_eq!(iterated.len(), 12);
+        assert_eq!(iterated[4], (vec![0, 1, 1], 33));
+        assert_eq!(iterated[11], (vec![1, 1, 2], 54));
+        // Indices are relative to the view and bound by its dimensions
+        assert!(matches!(view.get(&[2, 0, 0]), Err(IdxError::OutOfBounds{ dimension: 0, max: 2, index: 2 })));
+    }
+
+    #[test]
+    fn empty_ranges() {
+        let mut cursor = cursor();
+        let mut view = cursor.slice(&[0..3, 2..2]).unwrap();
+        assert_eq!(view.dimensions(), &[3, 0, 5]);
+        assert!(view.is_empty());
+        assert!(view.to_vec::<u8>().unwrap().0.is_empty());
+        assert_eq!(view.iter().count(), 0);
+        let written = view.write_to(io::Cursor::new(Vec::new())).unwrap().into_inner();
+        assert_eq!(IdxCursor::from_bytes(written).unwrap().dimensions(), &[3, 0, 5]);
+
+        let mut view = cursor.slice(&[1..1, 0..4]).unwrap();
+        assert_eq!(view.dimensions(), &[0, 4, 5]);
+        let written = view.write_to(io::Cursor::new(Vec::new())).unwrap().into_inner();
+        assert_eq!(IdxCursor::from_bytes(written).unwrap().dimensions(), &[0, 4, 5]);
+    }
+
+    #[test]
+    fn ranges_out_of_range() {
+        let mut cursor = cursor();
+        assert!(matches!(cursor.slice(&[0..4, 0..4]), Err(IdxError::OutOfBounds{ dimension: 0, max: 3, index: 4 })));
+        assert!(matches!(cursor.slice(&[0..3, 0..4, 3..6]), Err(IdxError::OutOfBounds{ dimension: 2, max: 5, index: 6 })));
+        assert!(matches!(cursor.slice(&[0..3, Range { start: 2, end: 1 }]), Err(IdxError::OutOfBounds{ dimension: 1, max: 4, index: 2 })));
+        assert!(matches!(cursor.slice(&[0..3, 0..4, 0..5, 0..1]), Err(IdxError::DimensionMismatch{ needed: 3, supplied: 4 })));
+    }
+
+    #[test]
+    fn write_round_trip() {
+        let values: Vec<i16> = (0..24).map(|v| v * 1000 - 12000).collect();
+        let data = IdxWriter::write_array(io::Cursor::new(Vec::new()), &[2, 3, 4], &values).unwrap().into_inner();
+        let mut cursor = IdxCursor::from_bytes(data).unwrap();
+        let mut view = cursor.slice(&[0..2, 1..3, 1..4]).unwrap();
+        let (expected, _) = view.to_vec::<i16>().unwrap();
+        assert_eq!(expected, vec![-7000, -6000, -5000, -3000, -2000, -1000, 5000, 6000, 7000, 9000, 10000, 11000]);
+        let written = view.write_to(io::Cursor::new(Vec::new())).unwrap().into_inner();
+        let (read, shape) = IdxCursor::from_bytes(written).unwrap().to_vec::<i16>().unwrap();
+        assert_eq!(shape.dimensions(), &[2, 2, 3]);
+        assert_eq!(read, expected);
+    }
+}
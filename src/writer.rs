@@ -0,0 +1,422 @@
+use std::convert::TryFrom;
+use std::fs::File;
+use std::fs::OpenOptions;
+use std::io;
+use std::io::Read;
+use std::io::Seek;
+use std::io::SeekFrom;
+use std::io::Write;
+use std::path::Path;
+
+use crate::byte_offset;
+use crate::read_header;
+use crate::Endianness;
+use crate::IdxDataType;
+use crate::IdxElement;
+use crate::IdxError;
+use crate::IdxOptions;
+use crate::IdxShape;
+use crate::IdxValue;
+
+pub struct IdxWriter<W: Write + Seek> {
+    writer: W,
+    shape: IdxShape,
+    data_type: IdxDataType,
+    endianness: Endianness,
+    next: u64,
+}
+
+impl<W: Write + Seek> IdxWriter<W> {
+    /// Writes header for the data type and dimensions and creates writer for the payload
+    ///
+    /// Without dimensions the file holds a single scalar, written with `append` or `set(&[], value)`.
+    pub fn new(writer: W, data_type: IdxDataType, dimensions: &[u32]) -> Result<IdxWriter<W>, IdxError> {
+        IdxWriter::with_endianness(writer, data_type, dimensions, Endianness::Big)
+    }
+
+    /// Like `new`, but writes dimension sizes and elements in the given byte order
+    pub fn with_endianness(mut writer: W, data_type: IdxDataType, dimensions: &[u32], endianness: Endianness) -> Result<IdxWriter<W>, IdxError> {
+        let shape = IdxShape::try_new(dimensions.to_vec())?;
+        write_header(&mut writer, data_type, dimensions, endianness)?;
+        Ok(IdxWriter {
+            writer, shape, data_type, endianness, next: 0
+        })
+    }
+
+    /// Writes value at the given indices, same indexing as `IdxCursor::get`
+    pub fn set(&mut self, indices: &[u32], value: IdxValue) -> Result<(), IdxError> {
+        let pos = self.shape.flat_offset(indices)?;
+        self.write_at(pos, value)
+    }
+
+    /// Writes value at the next element in storage order
+    pub fn append(&mut self, value: IdxValue) -> Result<(), IdxError> {
+        if self.next >= self.shape.len() {
+            // A scalar file counts as a single record
+            let max = self.shape.dimensions().first().copied().unwrap_or(1);
+            return Err(IdxError::OutOfBounds{ dimension: 0, max, index: max });
+        }
+        self.write_at(self.next, value)?;
+        self.next += 1;
+        Ok(())
+    }
+
+    /// Writes a complete big endian file of `values` in storage order, the data type follows from `T`
+    ///
+    /// Counterpart of `IdxCursor::to_vec`, fails with `LengthMismatch` unless there is one value per element of the
+    /// dimensions.
+    ///
+    /// ```
+    /// use std::io;
+    /// use idxrs::{IdxCursor, IdxWriter};
+    ///
+    /// let data = IdxWriter::write_array(io::Cursor::new(Vec::new()), &[2, 3], &[1i16, 2, 3, 4, 5, 6])?.into_inner();
+    /// let (values, shape) = IdxCursor::from_bytes(data)?.to_vec::<i16>()?;
+    /// assert_eq!((values, shape.dimensions()), (vec![1, 2, 3, 4, 5, 6], &[2, 3][..]));
+    /// # Ok::<(), idxrs::IdxError>(())
+    /// ```
+    pub fn write_array<T: IdxElement + Into<IdxValue>>(writer: W, dimensions: &[u32], values: &[T]) -> Result<W, IdxError> {
+        let mut out = IdxWriter::new(writer, T::DATA_TYPE, dimensions)?;
+        let size = T::DATA_TYPE.get_size() as u64;
+        if values.len() as u64 != out.shape.len() {
+            return Err(IdxError::LengthMismatch{ expected: out.shape.len() * size, actual: values.len() as u64 * size });
+        }
+        for value in values {
+            out.append((*value).into())?;
+        }
+        out.finish()
+    }
+
+    /// Writes a complete big endian file with one record per slice, the data type follows from `T` and the
+    /// dimensions are the number of records and the length of the first slice
+    ///
+    /// Fails with `LengthMismatch` if a slice is longer or shorter than the first one. Without any slices the
+    /// file has a single dimension of size 0.
+    ///
+    /// ```
+    /// use std::io;
+    /// use idxrs::{IdxCursor, IdxWriter};
+    ///
+    /// let rows = vec![vec![0.5f32, 1.0], vec![1.5, 2.0], vec![2.5, 3.0]];
+    /// let data = IdxWriter::write_records(io::Cursor::new(Vec::new()), rows.iter().map(|r| &r[..]))?.into_inner();
+    /// let cursor = IdxCursor::from_bytes(data)?;
+    /// assert_eq!(cursor.dimensions(), &[3, 2]);
+    /// # Ok::<(), idxrs::IdxError>(())
+    /// ```
+    pub fn write_records<'a, T, I>(writer: W, records: I) -> Result<W, IdxError>
+    where
+        T: IdxElement + Into<IdxValue> + 'a,
+        I: IntoIterator<Item = &'a [T]>,
+    {
+        let mut records = records.into_iter().peekable();
+        let record_shape = match records.peek() {
+            Some(first) => vec![first.len() as u32],
+            None => Vec::new(),
+        };
+        let mut out = StreamingIdxWriter::new(writer, T::DATA_TYPE, &record_shape)?;
+        let mut values = Vec::with_capacity(out.record_len());
+        for record in records {
+            values.clear();
+            values.extend(record.iter().map(|v| (*v).into()));
+            out.append_record(&values)?;
+        }
+        out.finish()
+    }
+
+    /// Pads the payload with zeros up to its full size and returns the inner writer
+    pub fn finish(mut self) -> Result<W, IdxError> {
+        let end = byte_offset(self.shape.dimensions().len(), self.shape.len(), self.data_type.get_size() as u64)?;
+        let len = self.writer.seek(SeekFrom::End(0))?;
+        if len < end {
+            io::copy(&mut io::repeat(0).take(end - len), &mut self.writer)?;
+        }
+        self.writer.flush()?;
+        Ok(self.writer)
+    }
+
+    fn write_at(&mut self, element: u64, value: IdxValue) -> Result<(), IdxError> {
+        let mut bytes = encode(self.data_type, &value)?;
+        self.endianness.swap_be(&mut bytes, self.data_type.get_size() as usize);
+        let pos = byte_offset(self.shape.dimensions().len(), element, self.data_type.get_size() as u64)?;
+        self.writer.seek(SeekFrom::Start(pos))?;
+        Ok(self.writer.write_all(&bytes)?)
+    }
+}
+
+/// Writer for files whose number of records is only known once all of them are written
+pub struct StreamingIdxWriter<W: Write + Seek> {
+    writer: W,
+    data_type: IdxDataType,
+    endianness: Endianness,
+    record_shape: Vec<u32>,
+    records: u32,
+}
+
+impl StreamingIdxWriter<File> {
+    /// Opens an existing file to append records after its last one, `finish` updates the record count
+    ///
+    /// Records are written in the byte order of the file, which is the one whose header matches the length of the
+    /// file, big endian if both do. Fails with `DataTypeMismatch` or `ShapeMismatch` unless the file holds records of
+    /// `data_type` and `record_shape`, and with `LengthMismatch` if its payload is incomplete or followed by other
+    /// bytes. `IdxWriter` cannot append, its dimensions are fixed once the header is written.
+    ///
+    /// ```no_run
+    /// use idxrs::{IdxDataType, IdxValue, StreamingIdxWriter};
+    ///
+    /// let mut writer = StreamingIdxWriter::append_to("labels.idx", IdxDataType::UnsignedByte, &[])?;
+    /// writer.append_record(&[IdxValue::UnsignedByte(7)])?;
+    /// writer.finish()?;
+    /// # Ok::<(), idxrs::IdxError>(())
+    /// ```
+    pub fn append_to<P: AsRef<Path>>(path: P, data_type: IdxDataType, record_shape: &[u32]) -> Result<StreamingIdxWriter<File>, IdxError> {
+        let mut file = OpenOptions::new().read(true).write(true).open(path)?;
+        let actual = file.seek(SeekFrom::End(0))?;
+        let (endianness, found, shape) = header_matching_length(&mut file, actual)?;
+        if found != data_type {
+            return Err(IdxError::DataTypeMismatch{ expected: data_type, found });
+        }
+        let dimensions = shape.dimensions();
+        if dimensions.is_empty() {
+            return Err(IdxError::DimensionMismatch{ needed: record_shape.len() + 1, supplied: 0 });
+        }
+        if &dimensions[1..] != record_shape {
+            return Err(IdxError::ShapeMismatch{ expected: record_shape.to_vec(), found: dimensions[1..].to_vec() });
+        }
+        // Records are appended at the end, which has to be the end of the payload
+        let expected = byte_offset(dimensions.len(), shape.len(), data_type.get_size() as u64)?;
+        if actual != expected {
+            return Err(IdxError::LengthMismatch{ expected, actual });
+        }
+        Ok(StreamingIdxWriter {
+            writer: file, data_type, endianness, record_shape: record_shape.to_vec(), records: dimensions[0]
+        })
+    }
+}
+
+/// Header of the file in the byte order whose payload ends at `len`, the big endian header or its error if neither
+/// does
+fn header_matching_length(file: &mut File, len: u64) -> Result<(Endianness, IdxDataType, IdxShape), IdxError> {
+    let mut big = None;
+    for endianness in &[Endianness::Big, Endianness::Little] {
+        file.seek(SeekFrom::Start(0))?;
+        let options = IdxOptions { endianness: *endianness, ..IdxOptions::default() };
+        // A header that overflows in one byte order may still be valid in the other
+        let res = read_header(&mut io::BufReader::new(&mut *file), &options).and_then(|(data_type, shape)| {
+            let end = byte_offset(shape.dimensions().len(), shape.len(), data_type.get_size() as u64)?;
+            Ok((*endianness, data_type, shape, end))
+        });
+        match res {
+            Ok((endianness, data_type, shape, end)) if end == len => return Ok((endianness, data_type, shape)),
+            res => { big.get_or_insert(res); }
+        }
+    }
+    let (endianness, data_type, shape, _) = big.expect("big endian header was read")?;
+    Ok((endianness, data_type, shape))
+}
+
+impl<W: Write + Seek> StreamingIdxWriter<W> {
+    /// Writes header with a placeholder for the leading dimension, followed by the record shape
+    pub fn new(writer: W, data_type: IdxDataType, record_shape: &[u32]) -> Result<StreamingIdxWriter<W>, IdxError> {
+        StreamingIdxWriter::with_endianness(writer, data_type, record_shape, Endianness::Big)
+    }
+
+    /// Like `new`, but writes dimension sizes and elements in the given byte order
+    pub fn with_endianness(mut writer: W, data_type: IdxDataType, record_shape: &[u32], endianness: Endianness) -> Result<StreamingIdxWriter<W>, IdxError> {
+        let mut dimensions = Vec::with_capacity(record_shape.len() + 1);
+        dimensions.push(0);
+        dimensions.extend_from_slice(record_shape);
+        write_header(&mut writer, data_type, &dimensions, endianness)?;
+        Ok(StreamingIdxWriter {
+            writer, data_type, endianness, record_shape: record_shape.to_vec(), records: 0
+        })
+    }
+
+    /// Number of elements in each record
+    pub fn record_len(&self) -> usize {
+        self.record_shape.iter().map(|d| *d as usize).product()
+    }
+
+    /// Appends one record given as its values in storage order
+    ///
+    /// Fails with `LengthMismatch` unless there are exactly `record_len()` values, with `ValueOutOfRange` if a value
+    /// cannot be stored exactly in the data type and with `Overflow` if the file already has `u32::MAX` records,
+    /// nothing is written in all cases.
+    pub fn append_record(&mut self, values: &[IdxValue]) -> Result<(), IdxError> {
+        let size = self.data_type.get_size() as usize;
+        if values.len() != self.record_len() {
+            return Err(IdxError::LengthMismatch{ expected: (self.record_len() * size) as u64, actual: (values.len() * size) as u64 });
+        }
+        let records = self.records.checked_add(1).ok_or(IdxError::Overflow)?;
+        let mut record = Vec::with_capacity(values.len() * size);
+        for value in values {
+            record.extend_from_slice(&encode(self.data_type, value)?);
+        }
+        self.endianness.swap_be(&mut record, size);
+        self.writer.write_all(&record)?;
+        self.records = records;
+        Ok(())
+    }
+
+    /// Appends whole records given as big endian bytes of the data type, e.g. copied from another file
+    ///
+    /// Fails with `LengthMismatch` if the bytes do not hold a whole number of records and with `Overflow` if the
+    /// file would have more than `u32::MAX` records, nothing is written in both cases.
+    pub fn append_record_bytes(&mut self, bytes: &[u8]) -> Result<(), IdxError> {
+        let size = self.data_type.get_size() as u64;
+        let record_bytes = self.record_len() as u64 * size;
+        // Records without elements only allow empty input
+        if !(bytes.len() as u64).is_multiple_of(record_bytes) {
+            let expected = (bytes.len() as u64 / record_bytes.max(1) + 1) * record_bytes;
+            return Err(IdxError::LengthMismatch{ expected, actual: bytes.len() as u64 });
+        }
+        // Records without elements take no bytes, so they cannot be counted from raw data
+        let count = (bytes.len() as u64).checked_div(record_bytes).unwrap_or(0);
+        let records = u32::try_from(count).ok().and_then(|n| self.records.checked_add(n)).ok_or(IdxError::Overflow)?;
+        if self.endianness == Endianness::Big {
+            self.writer.write_all(bytes)?;
+        } else {
+            let mut swapped = bytes.to_vec();
+            self.endianness.swap_be(&mut swapped, size as usize);
+            self.writer.write_all(&swapped)?;
+        }
+        self.records = records;
+        Ok(())
+    }
+
+    /// Number of records appended so far
+    pub fn records(&self) -> u32 {
+        self.records
+    }
+
+    pub fn record_shape(&self) -> &[u32] {
+        &self.record_shape
+    }
+
+    /// Patches the record count into the header and returns the inner writer
+    pub fn finish(mut self) -> Result<W, IdxError> {
+        // Leading dimension directly follows the 4 byte magic number
+        self.writer.seek(SeekFrom::Start(4))?;
+        self.writer.write_all(&self.endianness.u32_bytes(self.records))?;
+        self.writer.seek(SeekFrom::End(0))?;
+        self.writer.flush()?;
+        Ok(self.writer)
+    }
+}
+
+/// Writes magic number and dimension sizes at the start of the writer
+pub(crate) fn write_header<W: Write + Seek>(writer: &mut W, data_type: IdxDataType, dimensions: &[u32], endianness: Endianness) -> Result<(), IdxError> {
+    // Number of dimensions has to fit into the fourth byte
+    if dimensions.len() > u8::MAX as usize {
+        return Err(IdxError::TooManyDimensions(dimensions.len()));
+    }
+    // Magic number: two zero bytes, data type and number of dimensions
+    let magic: [u8; 4] = [0, 0, data_type.get_code(), dimensions.len() as u8];
+    writer.seek(SeekFrom::Start(0))?;
+    writer.write_all(&magic)?;
+    // Followed by the size of each dimension (each 32bit)
+    for dimension in dimensions {
+        writer.write_all(&endianness.u32_bytes(*dimension))?;
+    }
+    Ok(())
+}
+
+/// Converts value to big endian bytes of the data type, values of other types are accepted if they fit exactly
+fn encode(data_type: IdxDataType, value: &IdxValue) -> Result<Box<[u8]>, IdxError> {
+    if value.data_type() == data_type {
+        return Ok(value.to_be_bytes());
+    }
+    let out_of_range = |_| IdxError::ValueOutOfRange{ value: *value, data_type };
+    let bytes: Box<[u8]> = match data_type {
+        IdxDataType::UnsignedByte => Box::new(u8::from_value(*value).map_err(out_of_range)?.to_be_bytes()),
+        IdxDataType::SignedByte   => Box::new(i8::from_value(*value).map_err(out_of_range)?.to_be_bytes()),
+        IdxDataType::Short        => Box::new(i16::from_value(*value).map_err(out_of_range)?.to_be_bytes()),
+        IdxDataType::Int          => Box::new(i32::from_value(*value).map_err(out_of_range)?.to_be_bytes()),
+        IdxDataType::Float        => Box::new(f32::from_value(*value).map_err(out_of_range)?.to_be_bytes()),
+        IdxDataType::Double       => Box::new(f64::from_value(*value).map_err(out_of_range)?.to_be_bytes()),
+        #[cfg(feature = "extensions")]
+        IdxDataType::UnsignedShort => Box::new(u16::from_value(*value).map_err(out_of_range)?.to_be_bytes()),
+        #[cfg(feature = "extensions")]
+        IdxDataType::UnsignedInt  => Box::new(u32::from_value(*value).map_err(out_of_range)?.to_be_bytes()),
+        #[cfg(feature = "extensions")]
+        IdxDataType::Long         => Box::new(i64::from_value(*value).map_err(out_of_range)?.to_be_bytes()),
+        #[cfg(feature = "extensions")]
+        IdxDataType::UnsignedLong => Box::new(u64::from_value(*value).map_err(out_of_range)?.to_be_bytes()),
+    };
+    Ok(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::io;
+    use std::path::PathBuf;
+
+    use super::StreamingIdxWriter;
+    use crate::Endianness;
+    use crate::IdxCursor;
+    use crate::IdxDataType;
+    use crate::IdxError;
+    use crate::IdxOptions;
+    use crate::IdxValue;
+
+    /// Path in the temp directory that no other test uses
+    fn temp_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("idxrs-writer-{}-{}", std::process::id(), name))
+    }
+
+    fn shorts(values: &[i16]) -> Vec<IdxValue> {
+        values.iter().map(|v| IdxValue::Short(*v)).collect()
+    }
+
+    #[test]
+    fn append_keeps_byte_order() {
+        for endianness in &[Endianness::Big, Endianness::Little] {
+            let path = temp_path(&format!("order-{:?}.idx", endianness));
+            let file = fs::File::create(&path).unwrap();
+            let mut writer = StreamingIdxWriter::with_endianness(file, IdxDataType::Short, &[2], *endianness).unwrap();
+            writer.append_record(&shorts(&[1, 2])).unwrap();
+            writer.finish().unwrap();
+
+            let mut writer = StreamingIdxWriter::append_to(&path, IdxDataType::Short, &[2]).unwrap();
+            assert_eq!(writer.records(), 1);
+            writer.append_record(&shorts(&[3, -4])).unwrap();
+            writer.finish().unwrap();
+
+            let options = IdxOptions { endianness: *endianness, ..IdxOptions::default() };
+            let mut cursor = IdxCursor::with_options(io::BufReader::new(fs::File::open(&path).unwrap()), options).unwrap();
+            assert_eq!(cursor.dimensions(), &[2, 2]);
+            assert_eq!(cursor.to_vec::<i16>().unwrap().0, vec![1, 2, 3, -4]);
+            fs::remove_file(path).unwrap();
+        }
+    }
+
+    #[test]
+    fn append_beyond_u32_records() {
+        // Records without elements take no bytes, so the file can already hold u32::MAX of them
+        let path = temp_path("full.idx");
+        let file = fs::File::create(&path).unwrap();
+        let mut writer = StreamingIdxWriter::new(file, IdxDataType::UnsignedByte, &[0]).unwrap();
+        writer.records = u32::MAX;
+        writer.finish().unwrap();
+        let before = fs::read(&path).unwrap();
+
+        let mut writer = StreamingIdxWriter::append_to(&path, IdxDataType::UnsignedByte, &[0]).unwrap();
+        assert_eq!(writer.records(), u32::MAX);
+        assert!(matches!(writer.append_record(&[]), Err(IdxError::Overflow)));
+        assert!(matches!(writer.append_record_bytes(&[]), Ok(())));
+        writer.finish().unwrap();
+        assert_eq!(fs::read(&path).unwrap(), before);
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn record_bytes_beyond_u32_records() {
+        let mut writer = StreamingIdxWriter::new(io::Cursor::new(Vec::new()), IdxDataType::UnsignedByte, &[]).unwrap();
+        writer.records = u32::MAX - 1;
+        assert!(matches!(writer.append_record_bytes(&[1, 2]), Err(IdxError::Overflow)));
+        writer.append_record_bytes(&[1]).unwrap();
+        assert_eq!(writer.records(), u32::MAX);
+        // Nothing but the header and the one record was written
+        assert_eq!(writer.finish().unwrap().into_inner().len(), 8 + 1);
+    }
+}
@@ -0,0 +1,57 @@
+use std::io::Read;
+use std::io::Seek;
+
+use crate::IdxCursor;
+use crate::IdxError;
+use crate::IdxSlice;
+use crate::IdxValue;
+
+/// Iterator over records of a data file together with their labels, created by `zip`
+pub struct IdxZip<'a, R: Read + Seek, L: Read + Seek> {
+    records: &'a mut IdxCursor<R>,
+    labels: &'a mut IdxCursor<L>,
+    next: u32,
+    len: u32,
+    failed: bool,
+}
+
+/// Pairs every record with the label of the same index, any data type is accepted for both files
+///
+/// Fails before reading any record if `labels` is not one dimensional or the record counts differ.
+pub fn zip<'a, R, L>(records: &'a mut IdxCursor<R>, labels: &'a mut IdxCursor<L>) -> Result<IdxZip<'a, R, L>, IdxError>
+where
+    R: Read + Seek,
+    L: Read + Seek,
+{
+    if records.dimensions().is_empty() {
+        return Err(IdxError::DimensionMismatch{ needed: 1, supplied: 0 });
+    }
+    if labels.dimensions().len() != 1 {
+        return Err(IdxError::DimensionMismatch{ needed: 1, supplied: labels.dimensions().len() });
+    }
+    let (left, right) = (records.dimensions()[0], labels.dimensions()[0]);
+    if left != right {
+        return Err(IdxError::RecordCountMismatch{ left, right });
+    }
+    Ok(IdxZip { records, labels, next: 0, len: left, failed: false })
+}
+
+impl<'a, R: Read + Seek, L: Read + Seek> Iterator for IdxZip<'a, R, L> {
+    type Item = Result<(IdxSlice, IdxValue), IdxError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.failed || self.next >= self.len {
+            return None;
+        }
+        let i = self.next;
+        self.next += 1;
+        let res = self.records.get_record(&[i]).and_then(|record| Ok((record, self.labels.get(&[i])?)));
+        self.failed = res.is_err();
+        Some(res)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let n = if self.failed { 0 } else { (self.len - self.next) as usize };
+        (0, Some(n))
+    }
+}